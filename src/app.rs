@@ -1,155 +1,1338 @@
 // src/app.rs
 
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{Event as CEvent, KeyCode, KeyEvent, MouseEvent};
+use git2::{Oid, Repository as GitRepository, RepositoryState};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::ExitStatus,
+    sync::mpsc::Sender,
+    time::{Duration, Instant},
+};
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout},
-    widgets::{Block, Borders},
     Frame,
 };
 
+use crate::events::Event;
+use crate::git::repository::{Repository, Snapshot};
+use crate::git_utils::{abort_operation, continue_operation, gpg_sign_enabled, Transfer};
+use crate::keymap::KeyMap;
+use crate::task::{RunningTask, Task, TaskResult};
+use crate::theme::Theme;
 use crate::tui_module::{
-    branch_view::BranchView, commit_view::CommitView, help_view::HelpView, log_view::LogView,
-    status_view::StatusView,
+    blame_view::BlameView,
+    branch_view::BranchView,
+    clone_view::CloneView,
+    clone_view::InputMode as CloneInputMode,
+    commit_view::CommitView,
+    config_view::ConfigView,
+    confirm_dialog::{ConfirmDialog, DialogResult},
+    conflict_view::ConflictView,
+    diff_view::DiffRequest,
+    diff_view::DiffView,
+    help_view::HelpView,
+    interactive_rebase_view::InteractiveRebaseView,
+    log_view::LogView,
+    message_log::MessageLog,
+    progress::render_transfer,
+    reflog_view::ReflogView,
+    remote_view::RemoteView,
+    spinner::Spinner,
+    stash_view::StashView,
+    tag_view::TagView,
+    views::{status_view::StatusView, View},
+    welcome_view::WelcomeView,
 };
 
 pub struct App {
+    pub repo_path: String,
+    /// The repository, opened once and shared by every view; `None` until
+    /// one has been cloned.
+    pub repo: Option<Repository>,
     pub active_view: ActiveView,
     pub status_view: StatusView,
     pub log_view: LogView,
     pub branch_view: BranchView,
+    pub tag_view: TagView,
+    pub remote_view: RemoteView,
     pub commit_view: CommitView,
+    pub stash_view: StashView,
+    pub reflog_view: ReflogView,
+    pub config_view: ConfigView,
+    pub diff_view: DiffView,
+    pub blame_view: BlameView,
+    pub conflict_view: ConflictView,
+    pub rebase_view: InteractiveRebaseView,
+    pub clone_view: CloneView,
+    pub welcome_view: WelcomeView,
     pub help_view: HelpView,
-    pub messages: Vec<String>, 
+    /// Messages pushed since the last event, moved into `message_log` by
+    /// `flush_messages`.
+    pub messages: Vec<String>,
+    pub message_log: MessageLog,
+    /// View to return to when leaving a transient view such as blame.
+    pub previous_view: ActiveView,
+    pub theme: Theme,
+    pub keys: KeyMap,
+    /// The view and repository state as of the last refresh; ticks skip the
+    /// rescan while both are unchanged.
+    pub last_refresh: Option<(ActiveView, Snapshot)>,
+    /// Where HEAD pointed, and on which branch, when the log was last
+    /// reloaded by a refresh; index changes alone don't reload it.
+    pub log_head: Option<(Option<Oid>, Option<String>)>,
+    /// How often ticks check the repository for changes.
+    pub refresh_interval: Duration,
+    /// When ticks last checked the repository.
+    pub last_refresh_check: Instant,
+    /// Where background tasks report back to the main loop.
+    pub events: Sender<Event<CEvent>>,
+    /// Network operations currently running in the background.
+    pub running: Vec<RunningTask>,
+    /// Advanced on every tick, for tasks and slow operations.
+    pub spinner: Spinner,
+    /// Slow operation about to run, shown with the spinner meanwhile.
+    pub busy: Option<String>,
+    /// Key that starts the `busy` operation, handled once a frame showing
+    /// the spinner is on screen.
+    pub deferred: Option<KeyEvent>,
+    /// File for the main loop to open in `$EDITOR`, with the UI suspended.
+    pub edit_requested: Option<PathBuf>,
+    /// What the repository is in the middle of, read on every tick.
+    pub repo_state: RepositoryState,
+    /// The commit HEAD points at while detached, read on every tick.
+    pub detached_head: Option<String>,
+    /// Whether the index had conflicts when last checked, so the conflict
+    /// view only opens when an operation leaves new ones.
+    pub conflicted: bool,
+    /// Asks before quitting while a network operation is running.
+    pub quit_confirm: Option<ConfirmDialog<()>>,
+    /// Asks before abandoning a merge, cherry-pick or revert.
+    pub abort_confirm: Option<ConfirmDialog<()>>,
 }
 
-#[derive(PartialEq, Debug)]
+/// Shown on opening a bare repository.
+const BARE_REPOSITORY: &str =
+    "This is a bare repository: with no work tree, Status, Commit and Stash are unavailable.";
+
+/// Milliseconds between repository checks when no `--refresh-interval` is given.
+pub const DEFAULT_REFRESH_INTERVAL_MS: u64 = 250;
+
+#[derive(PartialEq, Debug, Clone, Copy)]
 pub enum ActiveView {
     Status,
     Log,
     Branch,
+    Tag,
+    Remote,
     Commit,
-    Help,
+    Stash,
+    Reflog,
+    Config,
+    Diff,
+    Blame,
+    Conflict,
+    Rebase,
+    Clone,
+    Welcome,
 }
 
+/// The views Tab cycles through, in order.
+const VIEW_CYCLE: [ActiveView; 9] = [
+    ActiveView::Status,
+    ActiveView::Log,
+    ActiveView::Branch,
+    ActiveView::Tag,
+    ActiveView::Remote,
+    ActiveView::Commit,
+    ActiveView::Stash,
+    ActiveView::Reflog,
+    ActiveView::Config,
+];
+
 impl App {
-    pub fn new() -> App {
+    pub fn new(
+        repo_path: String,
+        theme: Theme,
+        keys: KeyMap,
+        events: Sender<Event<CEvent>>,
+        startup_view: Option<&str>,
+    ) -> App {
+        let repo = match Repository::open(&repo_path) {
+            Ok(repo) => Some(repo),
+            Err(e) => {
+                log::warn!("Failed to open repository at '{}': {}", repo_path, e);
+                None
+            }
+        };
+        // Without a repository the only useful thing to do is create one.
+        let bare = repo.as_ref().is_some_and(|repo| repo.repo.is_bare());
+        let mut messages = if bare {
+            vec![BARE_REPOSITORY.to_string()]
+        } else {
+            Vec::new()
+        };
+        let default_view = if bare {
+            ActiveView::Log
+        } else {
+            ActiveView::Status
+        };
+        let active_view = match (&repo, startup_view) {
+            (None, _) => ActiveView::Welcome,
+            (Some(_), None) => default_view,
+            (Some(_), Some(name)) => match view_named(name) {
+                Some(view) if !(bare && needs_work_tree(view)) => view,
+                Some(view) => {
+                    messages.push(format!(
+                        "The {:?} view needs a work tree, and this repository is bare.",
+                        view
+                    ));
+                    default_view
+                }
+                None => {
+                    messages.push(format!(
+                        "Unknown view '{}'; opening {:?}.",
+                        name, default_view
+                    ));
+                    default_view
+                }
+            },
+        };
+        let gpg_sign = repo
+            .as_ref()
+            .is_some_and(|repo| gpg_sign_enabled(&repo.repo));
+
         App {
-            active_view: ActiveView::Status,
+            active_view,
             status_view: StatusView::new(),
             log_view: LogView::new(),
             branch_view: BranchView::new(),
-            commit_view: CommitView::new(),
+            tag_view: TagView::new(),
+            remote_view: RemoteView::new(),
+            commit_view: CommitView::new(gpg_sign),
+            stash_view: StashView::new(),
+            reflog_view: ReflogView::new(),
+            config_view: ConfigView::new(),
+            diff_view: DiffView::new(),
+            blame_view: BlameView::new(),
+            conflict_view: ConflictView::new(),
+            rebase_view: InteractiveRebaseView::new(),
+            clone_view: CloneView::new(repo_path.clone()),
+            welcome_view: WelcomeView::new(repo_path.clone()),
+            repo_path,
+            repo,
             help_view: HelpView::new(),
-            messages: Vec::new(),
+            messages,
+            message_log: MessageLog::default(),
+            previous_view: ActiveView::Status,
+            theme,
+            keys,
+            last_refresh: None,
+            log_head: None,
+            refresh_interval: Duration::from_millis(DEFAULT_REFRESH_INTERVAL_MS),
+            last_refresh_check: Instant::now(),
+            events,
+            running: Vec::new(),
+            spinner: Spinner::default(),
+            busy: None,
+            deferred: None,
+            edit_requested: None,
+            repo_state: RepositoryState::Clean,
+            detached_head: None,
+            conflicted: false,
+            quit_confirm: None,
+            abort_confirm: None,
         }
     }
 
     pub fn render<B: Backend>(&mut self, f: &mut Frame<B>) {
-        // Define the layout
+        // Short terminals give up the padding around the title and footer,
+        // then the messages pane, before squeezing the main content.
+        let height = f.size().height;
+        let (bar, messages) = match height {
+            0..=11 => (1, 0),
+            12..=19 => (1, 3),
+            _ => (3, 5),
+        };
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .margin(1)
             .constraints(
                 [
-                    Constraint::Length(3), // Title
-                    Constraint::Min(1),    // Main Content
-                    Constraint::Length(5), // Messages
-                    Constraint::Length(3), // Footer
+                    Constraint::Length(bar),      // Title
+                    Constraint::Min(1),           // Main Content
+                    Constraint::Length(messages), // Messages
+                    Constraint::Length(bar),      // Footer
                 ]
                 .as_ref(),
             )
             .split(f.size());
 
-        // Render the title
-        let title = tui::widgets::Paragraph::new("Rugit-TUI: Rust Git Interface")
-            .style(tui::style::Style::default().fg(tui::style::Color::Yellow))
-            .alignment(tui::layout::Alignment::Center);
+        // Render the title, or what the repository is in the middle of
+        let title = match self.state_banner() {
+            Some(banner) => tui::widgets::Paragraph::new(banner).style(
+                tui::style::Style::default()
+                    .fg(self.theme.removed)
+                    .add_modifier(tui::style::Modifier::BOLD),
+            ),
+            None => tui::widgets::Paragraph::new("Rugit-TUI: Rust Git Interface")
+                .style(tui::style::Style::default().fg(self.theme.title)),
+        }
+        .alignment(tui::layout::Alignment::Center);
         f.render_widget(title, chunks[0]);
 
         // Render the main content based on the active view
         match self.active_view {
-            ActiveView::Status => self.status_view.render(f, chunks[1]),
-            ActiveView::Log => self.log_view.render(f, chunks[1]),
-            ActiveView::Branch => self.branch_view.render(f, chunks[1]),
-            ActiveView::Commit => self.commit_view.render(f, chunks[1]),
-            ActiveView::Help => self.help_view.render(f, chunks[1]),
+            ActiveView::Status => self
+                .status_view
+                .render(f, chunks[1], &self.theme, &self.keys),
+            ActiveView::Log => self.log_view.render(f, chunks[1], &self.theme, &self.keys),
+            ActiveView::Branch => self
+                .branch_view
+                .render(f, chunks[1], &self.theme, &self.keys),
+            ActiveView::Tag => self.tag_view.render(f, chunks[1], &self.theme),
+            ActiveView::Remote => self.remote_view.render(f, chunks[1], &self.theme),
+            ActiveView::Commit => self
+                .commit_view
+                .render(f, chunks[1], &self.theme, &self.keys),
+            ActiveView::Stash => self
+                .stash_view
+                .render(f, chunks[1], &self.theme, &self.keys),
+            ActiveView::Reflog => self.reflog_view.render(f, chunks[1], &self.theme),
+            ActiveView::Config => self
+                .config_view
+                .render(f, chunks[1], &self.theme, &self.keys),
+            ActiveView::Diff => self.diff_view.render(f, chunks[1], &self.theme, &self.keys),
+            ActiveView::Blame => self.blame_view.render(f, chunks[1], &self.theme),
+            ActiveView::Conflict => {
+                self.conflict_view
+                    .render(f, chunks[1], &self.theme, &self.keys)
+            }
+            ActiveView::Rebase => self
+                .rebase_view
+                .render(f, chunks[1], &self.theme, &self.keys),
+            ActiveView::Clone => self.clone_view.render(f, chunks[1], &self.theme),
+            ActiveView::Welcome => self
+                .welcome_view
+                .render(f, chunks[1], &self.theme, &self.keys),
+        }
+
+        // Transfers in progress get a gauge over the view
+        if let Some((running, transfer)) = self
+            .running
+            .iter()
+            .find_map(|running| running.transfer.as_ref().map(|t| (running, t)))
+        {
+            render_transfer(
+                f,
+                chunks[1],
+                &running.task.describe(),
+                transfer,
+                running.started.elapsed(),
+                &self.theme,
+            );
+        }
+
+        self.help_view.render(f, chunks[1], &self.theme, &self.keys);
+
+        if let Some(label) = &self.busy {
+            self.spinner.render(f, chunks[1], label, &self.theme);
+        }
+
+        if let Some(dialog) = &self.quit_confirm {
+            dialog.render(f, chunks[1], &self.theme);
+        }
+        if let Some(dialog) = &self.abort_confirm {
+            dialog.render(f, chunks[1], &self.theme);
         }
 
         // Render the messages
-        let messages_text = self.messages.join("\n");
-        let messages = tui::widgets::Paragraph::new(messages_text)
-            .block(Block::default().borders(Borders::ALL).title("Messages"))
-            .style(tui::style::Style::default().fg(tui::style::Color::Magenta))
-            .alignment(tui::layout::Alignment::Left)
-            .wrap(tui::widgets::Wrap { trim: true });
-        f.render_widget(messages, chunks[2]);
-
-        // Render the footer
-        let footer = tui::widgets::Paragraph::new("Press 'q' to exit | Tab to switch views")
-            .style(tui::style::Style::default().fg(tui::style::Color::Magenta))
+        self.message_log
+            .render(f, chunks[2], &self.theme, &self.keys);
+
+        // Render the footer, or a spinner for tasks that have no progress bar
+        let waiting: Vec<&Task> = self
+            .running
+            .iter()
+            .filter(|running| running.transfer.is_none())
+            .map(|running| &running.task)
+            .collect();
+        let footer_text = match waiting.first() {
+            Some(task) => format!(
+                "{} {}...{}",
+                self.spinner.frame(),
+                task.describe(),
+                match waiting.len() {
+                    1 => String::new(),
+                    n => format!(" (+{} more)", n - 1),
+                }
+            ),
+            None => format!(
+                "Press '{}' to exit | {} to switch views | '{}' for help",
+                self.keys.key_for("global.quit"),
+                self.keys.key_for("global.next_view"),
+                self.keys.key_for("global.help")
+            ),
+        };
+        let footer = tui::widgets::Paragraph::new(footer_text)
+            .style(tui::style::Style::default().fg(self.theme.messages))
             .alignment(tui::layout::Alignment::Center);
         f.render_widget(footer, chunks[3]);
     }
 
+    /// Describes an operation in progress and the keys that carry it on.
+    fn state_banner(&self) -> Option<String> {
+        let resolve = format!(
+            "'{}' in Status resolves conflicts, '{}' continues",
+            self.keys.key_for("status.conflicts"),
+            self.keys.key_for("global.continue")
+        );
+        let abort = format!("'{}' aborts", self.keys.key_for("global.abort"));
+        let banner = match self.repo_state {
+            RepositoryState::Clean => {
+                let id = self.detached_head.as_ref()?;
+                return Some(format!(
+                    "DETACHED HEAD at {:.7}: create a branch to keep new commits",
+                    id
+                ));
+            }
+            RepositoryState::Merge => format!("MERGING: {}, {}", resolve, abort),
+            RepositoryState::Rebase
+            | RepositoryState::RebaseInteractive
+            | RepositoryState::RebaseMerge => format!(
+                "REBASING: {}, '{}' in Branches aborts",
+                resolve,
+                self.keys.key_for("branch.abort_rebase")
+            ),
+            RepositoryState::CherryPick => format!("CHERRY-PICKING: {}, {}", resolve, abort),
+            RepositoryState::Revert => format!("REVERTING: {}, {}", resolve, abort),
+            RepositoryState::CherryPickSequence => {
+                "CHERRY-PICKING: finish with 'git cherry-pick --continue'".to_string()
+            }
+            RepositoryState::RevertSequence => {
+                "REVERTING: finish with 'git revert --continue'".to_string()
+            }
+            RepositoryState::Bisect => "BISECTING: finish with 'git bisect reset'".to_string(),
+            RepositoryState::ApplyMailbox | RepositoryState::ApplyMailboxOrRebase => {
+                "APPLYING PATCHES: finish with 'git am --continue' or '--abort'".to_string()
+            }
+        };
+        Some(banner)
+    }
+
+    /// Moves new messages into the log, which stamps them with the time.
+    pub fn flush_messages(&mut self) {
+        for message in self.messages.drain(..) {
+            log::info!("{}", message);
+            self.message_log.push(message);
+        }
+    }
+
+    /// Forwards clicks and scroll-wheel events to the list views.
+    pub fn handle_mouse(&mut self, event: MouseEvent) {
+        let Some(repo) = &self.repo else {
+            return;
+        };
+        match self.active_view {
+            ActiveView::Status => self.status_view.handle_mouse(event),
+            ActiveView::Log => self.log_view.handle_mouse(event, &repo.repo),
+            ActiveView::Branch => self.branch_view.handle_mouse(event),
+            _ => {}
+        }
+    }
+
     pub fn handle_input(&mut self, key: KeyEvent) -> bool {
-        if key.code == KeyCode::Char('q') {
-            return true;
+        // Only the very next key confirms a force push, and only when the
+        // branch view gets it; global keys such as Tab disarm it.
+        let force_push_armed = std::mem::take(&mut self.branch_view.force_push_armed);
+
+        if let Some(dialog) = self.quit_confirm.take() {
+            match dialog.handle_input(key) {
+                DialogResult::Open(dialog) => self.quit_confirm = Some(dialog),
+                DialogResult::Confirmed(()) => return true,
+                DialogResult::Cancelled => {}
+            }
+            return false;
+        }
+        if let Some(dialog) = self.abort_confirm.take() {
+            match dialog.handle_input(key) {
+                DialogResult::Open(dialog) => self.abort_confirm = Some(dialog),
+                DialogResult::Confirmed(()) => self.abort_operation(),
+                DialogResult::Cancelled => self.messages.push("Abort cancelled.".to_string()),
+            }
+            return false;
+        }
+
+        // While typing, `q` is just a letter.
+        if self.keys.is("global.quit", &key) && !self.captures_input() {
+            return self.request_quit();
+        }
+
+        // The help covers the view, so it takes every other key while open.
+        if self.help_view.visible {
+            self.help_view.handle_input(key, &self.keys);
+            return false;
+        }
+        if self.keys.is("global.help", &key) && !self.captures_input() {
+            self.help_view.toggle();
+            return false;
+        }
+
+        if self.keys.is("global.refresh", &key) {
+            self.last_refresh = None;
+            self.log_head = None;
+            self.refresh();
+            self.messages.push("Refreshed.".to_string());
+            return false;
+        }
+
+        if self.keys.is("global.continue", &key)
+            && self.repo_state != RepositoryState::Clean
+            && !self.captures_input()
+        {
+            self.continue_operation();
+            return false;
+        }
+
+        if self.keys.is("global.abort", &key)
+            && matches!(
+                self.repo_state,
+                RepositoryState::Merge | RepositoryState::CherryPick | RepositoryState::Revert
+            )
+            && !self.captures_input()
+        {
+            self.abort_confirm = Some(ConfirmDialog::new(
+                "Abort the operation in progress? Its changes, resolved or not, are discarded."
+                    .to_string(),
+                (),
+            ));
+            return false;
         }
 
-        if key.code == KeyCode::Tab {
-            self.switch_view();
+        if self.keys.is("global.next_view", &key) {
+            self.switch_view(1);
             return false;
         }
 
+        if self.keys.is("global.prev_view", &key) {
+            self.switch_view(VIEW_CYCLE.len() - 1);
+            return false;
+        }
+
+        if !self.captures_input() {
+            match self.keys.action("global", &key) {
+                Some("go_help") => {
+                    self.help_view.toggle();
+                    return false;
+                }
+                Some(action) => {
+                    if let Some(view) = view_for_action(action) {
+                        self.go_to_view(view);
+                        return false;
+                    }
+                }
+                None => {}
+            }
+        }
+
+        if self.keys.is("global.focus_messages", &key) {
+            self.message_log.focused = !self.message_log.focused;
+            return false;
+        }
+
+        if self.message_log.focused {
+            self.message_log.handle_input(key, &self.keys);
+            return false;
+        }
+
+        match self.active_view {
+            // Esc leaves the clone prompt for the welcome screen unless a
+            // clone is already running.
+            ActiveView::Clone
+                if key.code == KeyCode::Esc
+                    && self.repo.is_none()
+                    && self.clone_view.input_mode != CloneInputMode::Cloning =>
+            {
+                self.active_view = ActiveView::Welcome;
+            }
+            ActiveView::Clone => {
+                if let Err(e) = self.clone_view.handle_input(key, &mut self.messages) {
+                    log::error!("{:?}", e);
+                    self.messages.push(format!("Error: {}", e));
+                }
+            }
+            ActiveView::Welcome => {
+                self.welcome_view
+                    .handle_input(key, &mut self.messages, &self.keys);
+                if let Some(path) = self.welcome_view.open.take() {
+                    self.open_repository(path);
+                } else if std::mem::take(&mut self.welcome_view.clone_requested) {
+                    self.active_view = ActiveView::Clone;
+                }
+            }
+            _ => {
+                if self.active_view == ActiveView::Branch {
+                    self.branch_view.force_push_armed = force_push_armed;
+                }
+                match self.slow_operation(&key) {
+                    Some(label) => {
+                        self.busy = Some(label.to_string());
+                        self.deferred = Some(key);
+                    }
+                    None => self.handle_repo_input(key),
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Runs the operation deferred by `handle_input`, if any, now that the
+    /// spinner has been drawn. Returns whether there was one.
+    pub fn run_deferred(&mut self) -> bool {
+        let Some(key) = self.deferred.take() else {
+            return false;
+        };
+        let started = Instant::now();
+        self.handle_repo_input(key);
+        // Taken outside the macro, which skips its arguments when not logging.
+        let label = self.busy.take().unwrap_or_default();
+        log::debug!("{} took {:?}", label, started.elapsed());
+        true
+    }
+
+    /// Names the operations that can take a while on big repositories, so
+    /// the spinner is shown before they block the loop.
+    fn slow_operation(&self, key: &KeyEvent) -> Option<&'static str> {
+        if self.captures_input() {
+            return None;
+        }
         match self.active_view {
-            ActiveView::Status => {
-                // if let Err(e) = self.status_view.handle_input(key) {
-                //     self.messages.push(format!("Error: {}", e));
-                // }
+            ActiveView::Branch if self.branch_view.comparison.is_some() => {
+                match self.keys.action("branch.compare", key)? {
+                    "merge" => Some("Merging"),
+                    "rebase" => Some("Rebasing"),
+                    _ => None,
+                }
+            }
+            ActiveView::Branch => match self.keys.action("branch", key)? {
+                "switch" => Some("Switching branches"),
+                "rebase" => Some("Rebasing"),
+                _ => None,
+            },
+            ActiveView::Log if self.log_view.detailed_commit.is_none() => {
+                match self.keys.action("log", key)? {
+                    "revert" => Some("Reverting"),
+                    _ => None,
+                }
+            }
+            ActiveView::Stash => match self.keys.action("stash", key)? {
+                "pop" | "apply" => Some("Applying stash"),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Hands `key` to the active view, which borrows the repository for the
+    /// duration.
+    fn handle_repo_input(&mut self, key: KeyEvent) {
+        match self.repo.take() {
+            Some(mut repo) => {
+                self.dispatch_repo_input(key, &mut repo.repo);
+                self.repo = Some(repo);
+                self.start_tasks();
+                self.check_conflicts();
             }
+            None => self
+                .messages
+                .push("No repository is open. Initialize or clone one first.".to_string()),
+        }
+    }
+
+    fn dispatch_repo_input(&mut self, key: KeyEvent, repo: &mut GitRepository) {
+        match self.active_view {
+            ActiveView::Status => match (self.keys.action("status", &key), key.code) {
+                // Prompts and dialogs get Enter and 'b' too.
+                _ if self.status_view.captures_input() => {
+                    self.status_view
+                        .handle_input(key, &mut self.messages, &self.keys, repo)
+                }
+                (Some("diff"), _) => self.open_diff(repo),
+                (Some("blame"), _) => {
+                    if let Some(path) = self.status_view.selected_path() {
+                        self.open_blame(&path, repo);
+                    }
+                }
+                (Some("history"), _) => {
+                    if let Some(path) = self.status_view.selected_path() {
+                        self.open_history(&path, repo);
+                    }
+                }
+                (Some("conflicts"), _) => self.open_conflicts(repo),
+                (Some("edit"), _) => {
+                    if let Some(path) = self.status_view.selected_path() {
+                        self.request_edit(&path, repo);
+                    }
+                }
+                _ => self
+                    .status_view
+                    .handle_input(key, &mut self.messages, &self.keys, repo),
+            },
             ActiveView::Log => {
-                if let Err(e) = self.log_view.handle_input(key, &mut self.messages) {
+                if let Err(e) =
+                    self.log_view
+                        .handle_input(key, &mut self.messages, &self.keys, repo)
+                {
+                    log::error!("{:?}", e);
                     self.messages.push(format!("Error: {}", e));
                 }
+                if let Some(request) = self.log_view.diff_requested.take() {
+                    self.open_commit_diff(request, repo);
+                }
+                if let Some(base) = self.log_view.rebase_requested.take() {
+                    self.open_rebase(&base, repo);
+                }
+                if std::mem::take(&mut self.log_view.reword_requested)
+                    && self.commit_view.start_reword(&mut self.messages, repo)
+                {
+                    self.active_view = ActiveView::Commit;
+                }
+                self.log_view.load_diffstats(repo);
             }
             ActiveView::Branch => {
-                if let Err(e) = self.branch_view.handle_input(key, &mut self.messages) {
+                if let Err(e) =
+                    self.branch_view
+                        .handle_input(key, &mut self.messages, &self.keys, repo)
+                {
+                    log::error!("{:?}", e);
+                    self.messages.push(format!("Error: {}", e));
+                }
+                if let Some(request) = self.branch_view.diff_requested.take() {
+                    self.open_commit_diff(request, repo);
+                }
+            }
+            ActiveView::Tag => {
+                // New tags point at whatever is highlighted in the log.
+                self.tag_view.target = self
+                    .log_view
+                    .items
+                    .get(self.log_view.selected)
+                    .map(|commit| commit.id.clone());
+                if let Err(e) =
+                    self.tag_view
+                        .handle_input(key, &mut self.messages, &self.keys, repo)
+                {
+                    log::error!("{:?}", e);
+                    self.messages.push(format!("Error: {}", e));
+                }
+            }
+            ActiveView::Remote => {
+                if let Err(e) =
+                    self.remote_view
+                        .handle_input(key, &mut self.messages, &self.keys, repo)
+                {
+                    log::error!("{:?}", e);
                     self.messages.push(format!("Error: {}", e));
                 }
             }
             ActiveView::Commit => {
-                if let Err(e) = self.commit_view.handle_input(key, &mut self.messages) {
+                if let Err(e) =
+                    self.commit_view
+                        .handle_input(key, &mut self.messages, &self.keys, repo)
+                {
+                    log::error!("{:?}", e);
+                    self.messages.push(format!("Error: {}", e));
+                }
+                if std::mem::take(&mut self.commit_view.reword_finished) {
+                    self.log_view.update(repo);
+                    self.active_view = ActiveView::Log;
+                }
+            }
+            ActiveView::Stash => {
+                if let Err(e) =
+                    self.stash_view
+                        .handle_input(key, &mut self.messages, &self.keys, repo)
+                {
+                    log::error!("{:?}", e);
+                    self.messages.push(format!("Error: {}", e));
+                }
+            }
+            ActiveView::Reflog => {
+                if let Err(e) =
+                    self.reflog_view
+                        .handle_input(key, &mut self.messages, &self.keys, repo)
+                {
+                    log::error!("{:?}", e);
                     self.messages.push(format!("Error: {}", e));
                 }
             }
-            ActiveView::Help => {
-                self.help_view.handle_input(key);
+            ActiveView::Config => {
+                if let Err(e) =
+                    self.config_view
+                        .handle_input(key, &mut self.messages, &self.keys, repo)
+                {
+                    log::error!("{:?}", e);
+                    self.messages.push(format!("Error: {}", e));
+                }
             }
+            ActiveView::Diff => {
+                if key.code == KeyCode::Esc {
+                    // File diffs are opened from status, commit diffs elsewhere.
+                    self.active_view = if self.diff_view.range.is_some() {
+                        self.previous_view
+                    } else {
+                        ActiveView::Status
+                    };
+                } else if self.keys.is("diff.blame", &key) {
+                    if let Some(path) = self.diff_view.path.clone() {
+                        self.open_blame(&path, repo);
+                    }
+                } else if self.keys.is("diff.history", &key) {
+                    if let Some(path) = self.diff_view.path.clone() {
+                        self.open_history(&path, repo);
+                    }
+                } else if self.keys.is("diff.edit", &key) {
+                    match self.diff_view.path.clone() {
+                        Some(path) => self.request_edit(&path, repo),
+                        None => self
+                            .messages
+                            .push("Only a single file's diff can be edited.".to_string()),
+                    }
+                } else if let Err(e) =
+                    self.diff_view
+                        .handle_input(key, &mut self.messages, &self.keys, repo)
+                {
+                    log::error!("{:?}", e);
+                    self.messages.push(format!("Error: {}", e));
+                }
+            }
+            ActiveView::Blame => {
+                if key.code == KeyCode::Esc {
+                    self.active_view = self.previous_view;
+                } else if let Err(e) = self.blame_view.handle_input(key, &mut self.messages) {
+                    log::error!("{:?}", e);
+                    self.messages.push(format!("Error: {}", e));
+                }
+            }
+            ActiveView::Conflict => {
+                if key.code == KeyCode::Esc && !self.conflict_view.captures_input() {
+                    self.active_view = self.previous_view;
+                } else if let Err(e) =
+                    self.conflict_view
+                        .handle_input(key, &mut self.messages, &self.keys, repo)
+                {
+                    log::error!("{:?}", e);
+                    self.messages.push(format!("Error: {}", e));
+                }
+            }
+            ActiveView::Rebase => {
+                if key.code == KeyCode::Esc && !self.rebase_view.captures_input() {
+                    self.active_view = self.previous_view;
+                    self.messages
+                        .push("Interactive rebase cancelled.".to_string());
+                } else {
+                    self.rebase_view
+                        .handle_input(key, &mut self.messages, &self.keys, repo);
+                }
+                if std::mem::take(&mut self.rebase_view.finished) {
+                    self.log_view.update(repo);
+                    self.active_view = ActiveView::Log;
+                }
+            }
+            ActiveView::Clone | ActiveView::Welcome => {}
         }
+    }
 
+    /// Whether the active view is reading text or a choice from the keyboard.
+    fn captures_input(&self) -> bool {
+        if self.message_log.focused {
+            return false;
+        }
+        match self.active_view {
+            ActiveView::Log => self.log_view.captures_input(),
+            ActiveView::Branch => self.branch_view.captures_input(),
+            ActiveView::Tag => self.tag_view.captures_input(),
+            ActiveView::Remote => self.remote_view.captures_input(),
+            ActiveView::Commit => self.commit_view.captures_input(),
+            ActiveView::Stash => self.stash_view.captures_input(),
+            ActiveView::Reflog => self.reflog_view.captures_input(),
+            ActiveView::Config => self.config_view.captures_input(),
+            ActiveView::Clone => self.clone_view.captures_input(),
+            ActiveView::Welcome => self.welcome_view.captures_input(),
+            ActiveView::Status => self.status_view.captures_input(),
+            ActiveView::Conflict => self.conflict_view.captures_input(),
+            ActiveView::Rebase => self.rebase_view.captures_input(),
+            ActiveView::Diff | ActiveView::Blame => false,
+        }
+    }
+
+    /// Returns whether to quit now; with network operations still running
+    /// it asks for confirmation first.
+    fn request_quit(&mut self) -> bool {
+        let running = if self.clone_view.input_mode == CloneInputMode::Cloning {
+            "A clone is".to_string()
+        } else {
+            match self.running.as_slice() {
+                [] => return true,
+                [running] => format!("{} is", running.task.describe()),
+                running => format!("{} operations are", running.len()),
+            }
+        };
+        self.quit_confirm = Some(ConfirmDialog::new(
+            format!("{} still running. Quit anyway?", running),
+            (),
+        ));
         false
     }
 
-    fn switch_view(&mut self) {
-        self.active_view = match self.active_view {
-            ActiveView::Status => ActiveView::Log,
-            ActiveView::Log => ActiveView::Branch,
-            ActiveView::Branch => ActiveView::Commit,
-            ActiveView::Commit => ActiveView::Help,
-            ActiveView::Help => ActiveView::Status,
+    /// Starts the network operations the views asked for on background
+    /// threads.
+    fn start_tasks(&mut self) {
+        let requested = [self.branch_view.task.take(), self.remote_view.task.take()];
+        for task in requested.into_iter().flatten() {
+            if self.running.iter().any(|running| running.task == task) {
+                self.messages
+                    .push(format!("{} is already running.", task.describe()));
+                continue;
+            }
+            log::info!("Starting task: {:?}", task);
+            self.messages.push(format!("{}...", task.describe()));
+            self.running.push(RunningTask {
+                task: task.clone(),
+                started: Instant::now(),
+                transfer: None,
+            });
+            task.spawn(self.repo_path.clone(), self.events.clone());
+        }
+    }
+
+    /// Records the latest transfer progress of a running task.
+    pub fn task_progress(&mut self, task: Task, transfer: Transfer) {
+        if let Some(running) = self.running.iter_mut().find(|running| running.task == task) {
+            running.transfer = Some(transfer);
+        }
+    }
+
+    /// Reports a finished background task and refreshes what it changed.
+    pub fn finish_task(&mut self, result: TaskResult) {
+        if let Some(index) = self
+            .running
+            .iter()
+            .position(|running| running.task == result.task)
+        {
+            self.running.remove(index);
+        }
+        self.messages.extend(result.messages);
+        if let (Task::PushPreview { remote, branch }, Some(commits)) =
+            (result.task, result.outgoing)
+        {
+            self.branch_view
+                .confirm_push(remote, branch, commits, &mut self.messages);
+        }
+        self.last_refresh = None;
+        self.check_conflicts();
+    }
+
+    /// Reopens every view on the repository at `repo_path`, keeping messages.
+    fn open_repository(&mut self, repo_path: String) {
+        let messages = std::mem::take(&mut self.messages);
+        let message_log = std::mem::take(&mut self.message_log);
+        let running = std::mem::take(&mut self.running);
+        let refresh_interval = self.refresh_interval;
+        *self = App::new(
+            repo_path,
+            self.theme.clone(),
+            self.keys.clone(),
+            self.events.clone(),
+            None,
+        );
+        self.running = running;
+        self.refresh_interval = refresh_interval;
+        let opened = std::mem::replace(&mut self.messages, messages);
+        self.message_log = message_log;
+        if self.repo.is_some() {
+            self.messages
+                .push(format!("Opened repository at '{}'.", self.repo_path));
+        }
+        self.messages.extend(opened);
+    }
+
+    /// Opens the diff for the file highlighted in the status view.
+    fn open_diff(&mut self, repo: &GitRepository) {
+        let Some(path) = self.status_view.selected_path() else {
+            return;
+        };
+        match self.diff_view.open(&path, repo) {
+            Ok(_) => self.active_view = ActiveView::Diff,
+            Err(e) => {
+                log::error!("{:?}", e);
+                self.messages
+                    .push(format!("Failed to load diff for '{}': {}", path, e));
+            }
+        }
+    }
+
+    /// Opens a diff between two commits, returning to the current view on Esc.
+    fn open_commit_diff(&mut self, request: DiffRequest, repo: &GitRepository) {
+        match self.diff_view.open_commits(request, repo) {
+            Ok(_) => {
+                self.previous_view = self.active_view;
+                self.active_view = ActiveView::Diff;
+            }
+            Err(e) => {
+                log::error!("{:?}", e);
+                self.messages.push(format!("Failed to diff commits: {}", e));
+            }
+        }
+    }
+
+    /// Opens blame for `path`, returning to the current view on Esc.
+    fn open_blame(&mut self, path: &str, repo: &GitRepository) {
+        self.blame_view.open(path, repo);
+        self.previous_view = self.active_view;
+        self.active_view = ActiveView::Blame;
+    }
+
+    /// Asks the main loop to open `path`, relative to the work tree, in the
+    /// user's editor.
+    fn request_edit(&mut self, path: &str, repo: &GitRepository) {
+        match repo.workdir() {
+            Some(workdir) => self.edit_requested = Some(workdir.join(path)),
+            None => self.messages.push(BARE_REPOSITORY.to_string()),
+        }
+    }
+
+    /// Reports how the editor exited and reloads what the edit may have
+    /// changed.
+    pub fn finish_edit(&mut self, path: &Path, result: io::Result<ExitStatus>) {
+        let Some(repo) = &self.repo else {
+            return;
         };
+        let repo = &repo.repo;
+        let name = repo
+            .workdir()
+            .and_then(|workdir| path.strip_prefix(workdir).ok())
+            .unwrap_or(path)
+            .display()
+            .to_string();
+        match result {
+            Ok(status) if status.success() => self.messages.push(format!("Edited '{}'.", name)),
+            Ok(status) => self
+                .messages
+                .push(format!("The editor exited with {} on '{}'.", status, name)),
+            Err(e) => self.messages.push(format!(
+                "Failed to start the editor (set $EDITOR to choose one): {}",
+                e
+            )),
+        }
+        self.status_view.update(repo);
+        if self.active_view == ActiveView::Diff && self.diff_view.range.is_none() {
+            if let Some(path) = self.diff_view.path.clone() {
+                if let Err(e) = self.diff_view.open(&path, repo) {
+                    self.messages
+                        .push(format!("Failed to reload the diff: {}", e));
+                }
+            }
+        }
+        self.last_refresh = None;
+    }
+
+    /// Opens the todo list for rebasing the commits after `base`, returning
+    /// to the current view on Esc.
+    fn open_rebase(&mut self, base: &str, repo: &GitRepository) {
+        match self.rebase_view.open(base, repo) {
+            Ok(_) => {
+                self.previous_view = self.active_view;
+                self.active_view = ActiveView::Rebase;
+            }
+            Err(e) => self.messages.push(format!("Cannot rebase: {:#}", e)),
+        }
+    }
+
+    /// Opens the log filtered to the commits that changed `path`.
+    fn open_history(&mut self, path: &str, repo: &GitRepository) {
+        self.log_view.follow_path(path, repo);
+        self.active_view = ActiveView::Log;
+        self.messages.push(format!(
+            "Showing the history of '{}'. Esc shows all commits again.",
+            path
+        ));
+    }
+
+    /// Finishes the operation the repository is in the middle of.
+    fn continue_operation(&mut self) {
+        let Some(repo) = &mut self.repo else {
+            return;
+        };
+        match continue_operation(&mut repo.repo) {
+            Ok(operation) => self.messages.push(format!("Finished the {}.", operation)),
+            Err(e) => {
+                log::error!("{:?}", e);
+                self.messages.push(format!("Failed to continue: {}", e));
+            }
+        }
+        self.repo_state = repo.repo.state();
+        self.last_refresh = None;
+        // A rebase can stop again on a later commit.
+        self.check_conflicts();
+    }
+
+    /// Abandons the merge, cherry-pick or revert in progress.
+    fn abort_operation(&mut self) {
+        let Some(repo) = &self.repo else {
+            return;
+        };
+        match abort_operation(&repo.repo) {
+            Ok(operation) => self.messages.push(format!("Aborted the {}.", operation)),
+            Err(e) => {
+                log::error!("{:?}", e);
+                self.messages.push(format!("Failed to abort: {}", e));
+            }
+        }
+        self.repo_state = repo.repo.state();
+        self.conflicted = false;
+        self.last_refresh = None;
+    }
+
+    /// Opens the conflict view, returning to the current view on Esc.
+    fn open_conflicts(&mut self, repo: &GitRepository) {
+        self.conflict_view.update(repo);
+        if self.conflict_view.items.is_empty() {
+            self.messages.push("No conflicts to resolve.".to_string());
+            return;
+        }
+        if self.active_view != ActiveView::Conflict {
+            self.previous_view = self.active_view;
+        }
+        self.active_view = ActiveView::Conflict;
+    }
+
+    /// Opens the conflict view when the last operation left conflicts that
+    /// were not there before.
+    fn check_conflicts(&mut self) {
+        let Some(repo) = self.repo.take() else {
+            return;
+        };
+        let conflicted = repo
+            .repo
+            .index()
+            .and_then(|mut index| index.read(false).map(|_| index.has_conflicts()))
+            .unwrap_or(false);
+        if conflicted && !self.conflicted {
+            self.open_conflicts(&repo.repo);
+        }
+        self.conflicted = conflicted;
+        self.repo = Some(repo);
+    }
+
+    /// Moves `step` places along `VIEW_CYCLE`, wrapping around; views
+    /// outside the cycle go back to Status.
+    fn switch_view(&mut self, step: usize) {
+        // The other views have nothing to show without a repository.
+        if self.repo.is_none() {
+            return;
+        }
+        self.active_view = match VIEW_CYCLE.iter().position(|&v| v == self.active_view) {
+            // Bare repositories skip the views that need a work tree.
+            Some(i) => (1..=VIEW_CYCLE.len())
+                .map(|n| VIEW_CYCLE[(i + step * n) % VIEW_CYCLE.len()])
+                .find(|&view| self.can_show(view))
+                .unwrap_or(self.active_view),
+            None if self.is_bare() => ActiveView::Log,
+            None => ActiveView::Status,
+        };
+        self.messages
+            .push(format!("Switched to {:?}", self.active_view));
+    }
+
+    /// Whether the open repository is bare, with no work tree or index.
+    fn is_bare(&self) -> bool {
+        self.repo.as_ref().is_some_and(|repo| repo.repo.is_bare())
+    }
+
+    /// Whether `view` works in the open repository.
+    fn can_show(&self, view: ActiveView) -> bool {
+        !(self.is_bare() && needs_work_tree(view))
+    }
+
+    /// The view to reopen on next run: the active one, or Status for views
+    /// that only make sense within this session.
+    pub fn view_to_remember(&self) -> String {
+        let view = if VIEW_CYCLE.contains(&self.active_view) {
+            self.active_view
+        } else {
+            ActiveView::Status
+        };
+        format!("{:?}", view)
+    }
+
+    /// Opens on the view named by `view_to_remember` in an earlier run.
+    pub fn restore_view(&mut self, name: &str) {
+        if self.repo.is_none() {
+            return;
+        }
+        if let Some(view) = view_named(name) {
+            if self.can_show(view) {
+                self.active_view = view;
+            }
+        }
+    }
+
+    /// Jumps straight to `view`, as the number keys do.
+    fn go_to_view(&mut self, view: ActiveView) {
+        if self.repo.is_none() || self.active_view == view {
+            return;
+        }
+        if !self.can_show(view) {
+            self.messages.push(format!(
+                "The {:?} view needs a work tree, and this repository is bare.",
+                view
+            ));
+            return;
+        }
+        self.active_view = view;
         self.messages
             .push(format!("Switched to {:?}", self.active_view));
     }
 
     pub fn on_tick(&mut self) {
+        self.spinner.tick();
+        if self.active_view == ActiveView::Clone {
+            self.clone_view.update();
+            if let Some(dest) = self.clone_view.finished.take() {
+                self.open_repository(dest);
+            }
+            return;
+        }
+        if let Some(repo) = &self.repo {
+            self.repo_state = repo.repo.state();
+            self.detached_head = repo
+                .repo
+                .head_detached()
+                .unwrap_or(false)
+                .then(|| repo.repo.head().ok()?.target())
+                .flatten()
+                .map(|oid| oid.to_string());
+        }
+
+        // A new view, or anything that cleared `last_refresh`, is loaded on
+        // the next tick; otherwise the repository is only checked every
+        // `refresh_interval`.
+        let stale = self
+            .last_refresh
+            .as_ref()
+            .is_none_or(|(view, _)| *view != self.active_view);
+        if stale || self.last_refresh_check.elapsed() >= self.refresh_interval {
+            self.refresh();
+        }
+        // Diffstats are only computed for the commits scrolled into view.
+        if let (ActiveView::Log, Some(repo)) = (self.active_view, &self.repo) {
+            self.log_view.load_diffstats(&repo.repo);
+        }
+    }
+
+    /// Reloads the active view if the repository changed since the last refresh.
+    fn refresh(&mut self) {
+        self.last_refresh_check = Instant::now();
+        let Some(repo) = &mut self.repo else {
+            return;
+        };
+        let snapshot = repo.snapshot();
+        let head = (snapshot.head, snapshot.head_name.clone());
+        let refresh = (self.active_view, snapshot);
+        // Saving a file touches neither the index nor HEAD, so the Status
+        // view reads the work tree on every check.
+        if self.last_refresh.as_ref() == Some(&refresh) && self.active_view != ActiveView::Status {
+            return;
+        }
+        self.last_refresh = Some(refresh);
+
+        let repo = &mut repo.repo;
         match self.active_view {
-            ActiveView::Status => self.status_view.update(),
-            ActiveView::Log => self.log_view.update(),
-            ActiveView::Branch => self.branch_view.update(),
-            ActiveView::Commit => {}
-            ActiveView::Help => {}
+            ActiveView::Status => self.status_view.update(repo),
+            // Staging doesn't change the history, so only a moved HEAD, a
+            // switched branch, 'r' or the global refresh key reload it.
+            ActiveView::Log => {
+                if self.log_head.as_ref() != Some(&head) {
+                    self.log_head = Some(head);
+                    self.log_view.update(repo);
+                }
+            }
+            ActiveView::Branch => self.branch_view.update(repo),
+            ActiveView::Tag => self.tag_view.update(repo),
+            ActiveView::Remote => self.remote_view.update(repo),
+            ActiveView::Commit => self.commit_view.update(repo),
+            ActiveView::Stash => self.stash_view.update(repo),
+            ActiveView::Reflog => self.reflog_view.update(repo),
+            ActiveView::Config => self.config_view.update(repo),
+            ActiveView::Conflict => self.conflict_view.update(repo),
+            ActiveView::Diff
+            | ActiveView::Blame
+            | ActiveView::Rebase
+            | ActiveView::Clone
+            | ActiveView::Welcome => {}
         }
     }
 }
+
+/// Views that read or change the work tree or index, which a bare
+/// repository doesn't have.
+fn needs_work_tree(view: ActiveView) -> bool {
+    matches!(
+        view,
+        ActiveView::Status | ActiveView::Commit | ActiveView::Stash | ActiveView::Conflict
+    )
+}
+
+/// The Tab-cycle view called `name`, in any case, as `--view` and the
+/// `startup_view` setting name them.
+fn view_named(name: &str) -> Option<ActiveView> {
+    VIEW_CYCLE
+        .iter()
+        .copied()
+        .find(|view| format!("{:?}", view).eq_ignore_ascii_case(name.trim()))
+}
+
+/// The view a `global.go_*` action jumps to.
+fn view_for_action(action: &str) -> Option<ActiveView> {
+    let view = match action {
+        "go_status" => ActiveView::Status,
+        "go_log" => ActiveView::Log,
+        "go_branch" => ActiveView::Branch,
+        "go_commit" => ActiveView::Commit,
+        "go_tag" => ActiveView::Tag,
+        "go_remote" => ActiveView::Remote,
+        "go_stash" => ActiveView::Stash,
+        "go_reflog" => ActiveView::Reflog,
+        "go_config" => ActiveView::Config,
+        _ => return None,
+    };
+    Some(view)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, sync::mpsc};
+
+    #[test]
+    fn status_notices_work_tree_edits_that_leave_the_index_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        GitRepository::init(dir.path()).unwrap();
+        fs::write(dir.path().join("a.txt"), "a\n").unwrap();
+        let (events, _) = mpsc::channel();
+        let path = dir.path().to_string_lossy().into_owned();
+        let mut app = App::new(path, Theme::default(), KeyMap::default(), events, None);
+        app.refresh();
+        assert_eq!(app.status_view.items, ["Changes not staged:", "?? a.txt"]);
+
+        fs::write(dir.path().join("b.txt"), "b\n").unwrap();
+        app.refresh();
+        assert_eq!(
+            app.status_view.items,
+            ["Changes not staged:", "?? a.txt", "?? b.txt"]
+        );
+    }
+}
@@ -0,0 +1,34 @@
+use anyhow::{Context, Result};
+use arboard::Clipboard;
+use std::sync::Mutex;
+
+/// Kept for the life of the process: on X11 and Wayland the copied text is
+/// served by whoever owns the clipboard, so dropping it would lose the text.
+static CLIPBOARD: Mutex<Option<Clipboard>> = Mutex::new(None);
+
+/// Copies `text` to the system clipboard.
+pub fn copy(text: &str) -> Result<()> {
+    let mut clipboard = CLIPBOARD
+        .lock()
+        .map_err(|_| anyhow::anyhow!("Clipboard lock poisoned"))?;
+    if clipboard.is_none() {
+        *clipboard = Some(Clipboard::new().context("No clipboard available")?);
+    }
+    clipboard
+        .as_mut()
+        .expect("clipboard was just opened")
+        .set_text(text)
+        .context("Failed to copy to the clipboard")
+}
+
+/// Copies `text` and reports it as `label`. Without a clipboard (e.g. over
+/// SSH) the text itself goes into the messages so it can still be copied.
+pub fn copy_reporting(text: &str, label: &str, messages: &mut Vec<String>) {
+    match copy(text) {
+        Ok(_) => messages.push(format!("Copied {}", label)),
+        Err(e) => {
+            log::warn!("{:?}", e);
+            messages.push(format!("Could not copy {} ({:#}): {}", label, e, text));
+        }
+    }
+}
@@ -0,0 +1,13 @@
+use crate::git_utils::Transfer;
+use crate::task::{Task, TaskResult};
+
+/// Everything the main loop waits on, from the input thread and from
+/// background tasks.
+pub enum Event<I> {
+    Input(I),
+    Tick,
+    /// A running task received more objects from the remote.
+    Progress(Task, Transfer),
+    /// A background network operation finished.
+    Task(TaskResult),
+}
@@ -1,10 +1,19 @@
-use git2::Repository as GitRepository;
+use git2::{Oid, Repository as GitRepository};
 use std::path::Path;
+use std::time::SystemTime;
 
 pub struct Repository {
     pub repo: GitRepository,
 }
 
+/// Cheap-to-read markers that change whenever the index or HEAD does.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Snapshot {
+    pub index_mtime: Option<SystemTime>,
+    pub head: Option<Oid>,
+    pub head_name: Option<String>,
+}
+
 impl Repository {
     /// Initialize a new Git repository at the given path
     pub fn init<P: AsRef<Path>>(path: P) -> Result<Self, git2::Error> {
@@ -12,9 +21,30 @@ impl Repository {
         Ok(Repository { repo })
     }
 
+    /// Initialize a new bare Git repository at the given path
+    pub fn init_bare<P: AsRef<Path>>(path: P) -> Result<Self, git2::Error> {
+        let repo = GitRepository::init_bare(path)?;
+        Ok(Repository { repo })
+    }
+
     /// Open an existing Git repository
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, git2::Error> {
         let repo = GitRepository::open(path)?;
         Ok(Repository { repo })
     }
+
+    /// Reads the modification time of `.git/index` and where HEAD points.
+    pub fn snapshot(&self) -> Snapshot {
+        let index_mtime = std::fs::metadata(self.repo.path().join("index"))
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        let head = self.repo.head().ok();
+        Snapshot {
+            index_mtime,
+            head: head.as_ref().and_then(|head| head.target()),
+            head_name: head
+                .as_ref()
+                .and_then(|head| head.name().map(str::to_string)),
+        }
+    }
 }
@@ -1,35 +1,88 @@
 // src/git_utils.rs
 
 use anyhow::{Context, Result};
-use git2::{AnnotatedCommit, BranchType, Error, Repository, Signature};
+use git2::{
+    AnnotatedCommit, BranchType, Cred, CredentialType, Delta, DiffFormat, DiffOptions, Error,
+    ErrorClass, ErrorCode, FetchOptions, FetchPrune, ObjectType, Oid, PushOptions, Reference,
+    RemoteCallbacks, Repository, RepositoryState, ResetType, Signature, Status,
+};
+
+/// Finds the repository containing `path`, searching parent directories, and
+/// returns its working directory (or git directory for bare repositories).
+pub fn discover_repo(path: &str) -> Result<String> {
+    let repo = Repository::discover(path)
+        .with_context(|| format!("No git repository found at or above '{}'", path))?;
+
+    let root = repo.workdir().unwrap_or_else(|| repo.path());
+    Ok(root.display().to_string())
+}
+
+/// Returns the repository index, re-reading it first if another process
+/// (such as `git add` in a shell) changed it since the repository was opened.
+fn repo_index(repo: &Repository) -> Result<git2::Index> {
+    let mut index = repo.index().context("Failed to get repository index")?;
+    index
+        .read(false)
+        .context("Failed to read repository index")?;
+    Ok(index)
+}
+
+/// Rejects names Git would refuse, with the reason, before any ref is made.
+pub fn validate_branch_name(branch_name: &str) -> Result<()> {
+    let reason = if branch_name.is_empty() {
+        Some("it is empty")
+    } else if branch_name.chars().any(char::is_whitespace) {
+        Some("it contains spaces")
+    } else if branch_name.starts_with('-') {
+        Some("it starts with '-'")
+    } else if branch_name.contains("..") {
+        Some("it contains '..'")
+    } else if !Reference::is_valid_name(&format!("refs/heads/{}", branch_name)) {
+        Some("it is not a valid Git ref name")
+    } else {
+        None
+    };
+    match reason {
+        Some(reason) => anyhow::bail!("Invalid branch name '{}': {}.", branch_name, reason),
+        None => Ok(()),
+    }
+}
 
-/// Creates a new branch with the given name based on the current HEAD.
-pub fn create_branch(repo_path: &str, branch_name: &str) -> Result<()> {
-    let repo = Repository::open(repo_path)
-        .with_context(|| format!("Failed to open repository at '{}'", repo_path))?;
+/// Creates a new branch with the given name at `start_point` (any commit,
+/// branch or tag), or at the current HEAD when there is none.
+pub fn create_branch(
+    repo: &Repository,
+    branch_name: &str,
+    start_point: Option<&str>,
+) -> Result<()> {
+    validate_branch_name(branch_name)?;
 
     // Check if branch already exists
     if repo.find_branch(branch_name, BranchType::Local).is_ok() {
         anyhow::bail!("Branch '{}' already exists.", branch_name);
     }
 
-    let head = repo
-        .head()
-        .context("Failed to get HEAD")?
-        .peel_to_commit()
-        .context("Failed to peel HEAD to commit")?;
+    let target = match start_point {
+        Some(start_point) => repo
+            .revparse_single(start_point)
+            .with_context(|| format!("Start point '{}' not found.", start_point))?
+            .peel_to_commit()
+            .with_context(|| format!("Start point '{}' is not a commit.", start_point))?,
+        None => repo
+            .head()
+            .context("Failed to get HEAD")?
+            .peel_to_commit()
+            .context("Failed to peel HEAD to commit")?,
+    };
 
-    repo.branch(branch_name, &head, false)
+    repo.branch(branch_name, &target, false)
         .with_context(|| format!("Failed to create branch '{}'", branch_name))?;
 
     Ok(())
 }
 
 /// Deletes the specified branch, ensuring it's not the current branch.
-pub fn delete_branch(repo_path: &str, branch_name: &str) -> Result<()> {
-    let repo = Repository::open(repo_path)
-        .with_context(|| format!("Failed to open repository at '{}'", repo_path))?;
-
+pub fn delete_branch(repo: &Repository, branch_name: &str) -> Result<()> {
     let head = repo
         .head()
         .context("Failed to get HEAD")?
@@ -52,47 +105,105 @@ pub fn delete_branch(repo_path: &str, branch_name: &str) -> Result<()> {
     Ok(())
 }
 
-/// Switches to the specified branch.
-pub fn switch_branch(repo_path: &str, branch_name: &str) -> Result<()> {
-    let repo = Repository::open(repo_path)
-        .with_context(|| format!("Failed to open repository at '{}'", repo_path))?;
+/// Switches to the specified branch. Unless `force` is set, refuses while
+/// tracked files have changes, and stops before overwriting untracked files
+/// the branch has, leaving HEAD where it was.
+pub fn switch_branch(repo: &Repository, branch_name: &str, force: bool) -> Result<()> {
+    if !force && has_uncommitted_changes(repo)? {
+        anyhow::bail!(
+            "You have uncommitted changes that switching to '{}' would overwrite. Stash or commit them first.",
+            branch_name
+        );
+    }
 
-    let annotated = repo
-        .find_annotated_commit(
-            repo.refname_to_id(&format!("refs/heads/{}", branch_name))
-                .with_context(|| format!("Branch '{}' not found.", branch_name))?,
-        )
+    let refname = format!("refs/heads/{}", branch_name);
+    let commit = repo
+        .find_reference(&refname)
+        .and_then(|reference| reference.peel_to_commit())
+        .with_context(|| format!("Branch '{}' not found.", branch_name))?;
+
+    let mut checkout = git2::build::CheckoutBuilder::default();
+    if force {
+        checkout.force();
+    } else {
+        checkout.safe();
+    }
+    // The tree first, so a refused checkout doesn't leave HEAD on the new
+    // branch over the old files.
+    repo.checkout_tree(commit.as_object(), Some(&mut checkout))
         .with_context(|| {
             format!(
-                "Failed to find annotated commit for branch '{}'",
+                "Failed to check out '{}'; move untracked files it would overwrite out of the way",
                 branch_name
             )
         })?;
-
-    repo.set_head(&format!("refs/heads/{}", branch_name))
+    repo.set_head(&refname)
         .with_context(|| format!("Failed to set HEAD to '{}'", branch_name))?;
 
-    repo.checkout_head(Some(
-        git2::build::CheckoutBuilder::default()
-            .allow_conflicts(true)
-            .force(),
-    ))
-    .context("Failed to checkout branch")?;
+    Ok(())
+}
+
+/// Checks out `commit_id` with a detached HEAD. Local changes are carried
+/// over, and the checkout stops if any would be overwritten.
+pub fn checkout_commit(repo: &Repository, commit_id: &str) -> Result<()> {
+    let commit = repo
+        .revparse_single(commit_id)
+        .and_then(|object| object.peel_to_commit())
+        .with_context(|| format!("Failed to find commit '{}'", commit_id))?;
+
+    repo.checkout_tree(
+        commit.as_object(),
+        Some(git2::build::CheckoutBuilder::default().safe()),
+    )
+    .context("Failed to check out commit; local changes would be overwritten")?;
+    repo.set_head_detached(commit.id())
+        .context("Failed to detach HEAD")?;
 
     Ok(())
 }
 
-/// Adds files to the staging area.
-pub fn add_files(repo_path: &str, files: &[String]) -> Result<()> {
-    let repo = Repository::open(repo_path)
-        .with_context(|| format!("Failed to open repository at '{}'", repo_path))?;
+/// Whether tracked files have staged or unstaged changes.
+pub fn has_uncommitted_changes(repo: &Repository) -> Result<bool> {
+    let mut opts = git2::StatusOptions::new();
+    opts.include_untracked(false).include_ignored(false);
+    let statuses = repo
+        .statuses(Some(&mut opts))
+        .context("Failed to get working tree status")?;
+    Ok(!statuses.is_empty())
+}
 
-    let mut index = repo.index().context("Failed to get repository index")?;
+/// How many commits a detached HEAD has that no branch, remote-tracking
+/// branch or tag contains; switching away leaves them only in the reflog.
+pub fn unreferenced_head_commits(repo: &Repository) -> Result<usize> {
+    if !repo.head_detached().unwrap_or(false) {
+        return Ok(0);
+    }
+    let mut revwalk = repo.revwalk().context("Failed to create revwalk")?;
+    revwalk.push_head().context("Failed to push HEAD")?;
+    for glob in ["refs/heads/*", "refs/remotes/*", "refs/tags/*"] {
+        revwalk
+            .hide_glob(glob)
+            .with_context(|| format!("Failed to hide '{}'", glob))?;
+    }
+    Ok(revwalk.count())
+}
+
+/// Adds files to the staging area; files deleted from the working tree
+/// are staged as deletions.
+pub fn add_files(repo: &Repository, files: &[String]) -> Result<()> {
+    let mut index = repo_index(repo)?;
+    let workdir = repo
+        .workdir()
+        .context("Cannot stage files in a bare repository")?;
 
     for file in files {
-        index
-            .add_path(std::path::Path::new(file))
-            .with_context(|| format!("Failed to add file '{}'", file))?;
+        let path = std::path::Path::new(file);
+        if workdir.join(path).symlink_metadata().is_ok() {
+            index.add_path(path)
+        } else {
+            index.remove_path(path)
+        }
+        .with_context(|| format!("Failed to add file '{}'", file))?;
     }
 
     index.write().context("Failed to write to index")?;
@@ -100,12 +211,165 @@ pub fn add_files(repo_path: &str, files: &[String]) -> Result<()> {
     Ok(())
 }
 
-/// Commits staged changes with the provided message.
-pub fn commit_changes(repo_path: &str, message: &str) -> Result<()> {
-    let repo = Repository::open(repo_path)
-        .with_context(|| format!("Failed to open repository at '{}'", repo_path))?;
+/// Removes files from the staging area, restoring their index entries to HEAD.
+pub fn unstage_files(repo: &Repository, files: &[String]) -> Result<()> {
+    match repo.head() {
+        Ok(head) => {
+            let head_obj = head
+                .peel(ObjectType::Commit)
+                .context("Failed to peel HEAD to commit")?;
+            repo.reset_default(Some(&head_obj), files.iter().map(String::as_str))
+                .context("Failed to reset index entries")?;
+        }
+        Err(_) => {
+            // No commits yet, so there is nothing to reset to; drop the entries instead.
+            let mut index = repo_index(repo)?;
+            for file in files {
+                index
+                    .remove_path(std::path::Path::new(file))
+                    .with_context(|| format!("Failed to unstage file '{}'", file))?;
+            }
+            index.write().context("Failed to write to index")?;
+        }
+    }
 
-    let mut index = repo.index().context("Failed to get repository index")?;
+    Ok(())
+}
+
+/// Stages every modified, deleted and untracked path, returning how many
+/// files were staged.
+pub fn stage_all(repo: &Repository) -> Result<usize> {
+    let count = count_statuses(
+        repo,
+        Status::WT_NEW
+            | Status::WT_MODIFIED
+            | Status::WT_DELETED
+            | Status::WT_RENAMED
+            | Status::WT_TYPECHANGE,
+    )?;
+
+    let mut index = repo_index(repo)?;
+    index
+        .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+        .context("Failed to stage files")?;
+    // `add_all` leaves entries for deleted files behind.
+    index
+        .update_all(["*"], None)
+        .context("Failed to stage deleted files")?;
+    index.write().context("Failed to write to index")?;
+
+    Ok(count)
+}
+
+/// Stages every change to tracked files, like `git add -u`, and commits
+/// them with `commit`, like `git commit -a`. The staging is only written
+/// once `commit` succeeds, so a failed commit leaves the index as it was.
+/// Returns how many files were staged on the way.
+pub fn commit_all(
+    repo: &Repository,
+    commit: impl FnOnce(&Repository) -> Result<()>,
+) -> Result<usize> {
+    let count = count_statuses(
+        repo,
+        Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_TYPECHANGE,
+    )?;
+
+    // The repository shares this index, so `commit` sees the staged files.
+    let mut index = repo_index(repo)?;
+    index
+        .update_all(["*"], None)
+        .context("Failed to stage tracked files")?;
+    if let Err(e) = commit(repo) {
+        // Forget the unwritten staging.
+        index.read(true).context("Failed to reread the index")?;
+        return Err(e);
+    }
+    index.write().context("Failed to write to index")?;
+
+    Ok(count)
+}
+
+/// Throws away all staged and unstaged changes to tracked files by checking
+/// out HEAD. Untracked files are only deleted when `include_untracked` is set.
+/// Returns how many files were discarded.
+pub fn discard_all(repo: &Repository, include_untracked: bool) -> Result<usize> {
+    let mut changes = Status::all() - Status::WT_NEW - Status::IGNORED;
+    if include_untracked {
+        changes |= Status::WT_NEW;
+    }
+    let count = count_statuses(repo, changes)?;
+
+    repo.checkout_head(Some(
+        git2::build::CheckoutBuilder::default()
+            .force()
+            .remove_untracked(include_untracked),
+    ))
+    .context("Failed to check out HEAD")?;
+
+    Ok(count)
+}
+
+/// Reverts `path` to its content at HEAD, dropping staged and unstaged edits.
+/// A path unknown to both HEAD and the index is untracked and gets deleted.
+pub fn discard_file(repo: &Repository, path: &str) -> Result<()> {
+    let relative = std::path::Path::new(path.trim_end_matches('/'));
+
+    let index = repo_index(repo)?;
+    let in_head = match repo.head() {
+        Ok(head) => head
+            .peel_to_tree()
+            .context("Failed to peel HEAD to tree")?
+            .get_path(relative)
+            .is_ok(),
+        Err(_) => false,
+    };
+
+    if !in_head && index.get_path(relative, 0).is_none() {
+        let workdir = repo
+            .workdir()
+            .context("Repository has no working directory")?;
+        let full_path = workdir.join(relative);
+        if full_path.is_dir() {
+            std::fs::remove_dir_all(&full_path)
+        } else {
+            std::fs::remove_file(&full_path)
+        }
+        .with_context(|| format!("Failed to delete '{}'", path))?;
+        return Ok(());
+    }
+
+    repo.checkout_head(Some(
+        git2::build::CheckoutBuilder::default()
+            .force()
+            .path(relative),
+    ))
+    .with_context(|| format!("Failed to check out '{}' from HEAD", path))?;
+
+    Ok(())
+}
+
+/// Counts the untracked (not ignored) files in the working tree.
+pub fn count_untracked(repo: &Repository) -> Result<usize> {
+    count_statuses(repo, Status::WT_NEW)
+}
+
+/// Counts the paths whose status intersects `flags`, untracked files included.
+fn count_statuses(repo: &Repository, flags: Status) -> Result<usize> {
+    let mut opts = git2::StatusOptions::new();
+    opts.include_untracked(true).recurse_untracked_dirs(true);
+    let statuses = repo
+        .statuses(Some(&mut opts))
+        .context("Failed to read repository status")?;
+    Ok(statuses
+        .iter()
+        .filter(|entry| entry.status().intersects(flags))
+        .count())
+}
+
+/// Commits staged changes with the provided message.
+pub fn commit_changes(repo: &Repository, message: &str) -> Result<()> {
+    refuse_during_merge(repo)?;
+    let mut index = repo_index(repo)?;
 
     if index.is_empty() {
         anyhow::bail!("No changes to commit.");
@@ -145,11 +409,271 @@ pub fn commit_changes(repo_path: &str, message: &str) -> Result<()> {
     Ok(())
 }
 
-/// Merges the specified branch into the current branch.
-pub fn merge_branch(repo_path: &str, branch_name: &str) -> Result<()> {
-    let repo = Repository::open(repo_path)
-        .with_context(|| format!("Failed to open repository at '{}'", repo_path))?;
+/// Stops an ordinary commit from finishing a merge with only one parent.
+fn refuse_during_merge(repo: &Repository) -> Result<()> {
+    if merge_in_progress(repo) {
+        anyhow::bail!(
+            "A merge is in progress. Continue it instead so the commit keeps both parents."
+        );
+    }
+    Ok(())
+}
+
+/// Which config file `user.name` and `user.email` are read from and written to.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ConfigScope {
+    /// The repository's own `.git/config`.
+    Local,
+    /// The user's `~/.gitconfig`.
+    Global,
+}
+
+/// The identity set in one config scope; `None` where a value is unset.
+pub struct Identity {
+    pub name: Option<String>,
+    pub email: Option<String>,
+}
+
+/// Opens the config file for `scope`, creating `~/.gitconfig` if needed.
+fn scope_config(repo: &Repository, scope: ConfigScope) -> Result<git2::Config> {
+    match scope {
+        ConfigScope::Local => repo
+            .config()
+            .and_then(|config| config.open_level(git2::ConfigLevel::Local))
+            .context("Failed to open the repository config"),
+        ConfigScope::Global => {
+            let path = match git2::Config::find_global() {
+                Ok(path) => path,
+                Err(_) => std::env::var_os("HOME")
+                    .map(|home| std::path::PathBuf::from(home).join(".gitconfig"))
+                    .context("Cannot locate the global config: HOME is not set")?,
+            };
+            git2::Config::open(&path)
+                .with_context(|| format!("Failed to open '{}'", path.display()))
+        }
+    }
+}
+
+/// Reads `user.name` and `user.email` from `scope` only.
+pub fn read_identity(repo: &Repository, scope: ConfigScope) -> Result<Identity> {
+    let config = scope_config(repo, scope)?;
+    Ok(Identity {
+        name: config.get_string("user.name").ok(),
+        email: config.get_string("user.email").ok(),
+    })
+}
+
+/// Writes `key` (`user.name` or `user.email`) to `scope`.
+pub fn set_identity(repo: &Repository, scope: ConfigScope, key: &str, value: &str) -> Result<()> {
+    scope_config(repo, scope)?
+        .set_str(key, value)
+        .with_context(|| format!("Failed to set {}", key))
+}
+
+/// A commit could not be signed; distinct from failures to create the commit.
+#[derive(Debug, thiserror::Error)]
+#[error("GPG signing failed: {0}")]
+pub struct SigningError(pub String);
+
+/// Returns whether `commit.gpgsign` is enabled for the repository.
+pub fn gpg_sign_enabled(repo: &Repository) -> bool {
+    repo.config()
+        .and_then(|config| config.get_bool("commit.gpgsign"))
+        .unwrap_or(false)
+}
+
+/// Commits staged changes with a GPG signature. Without `key_id` the
+/// `user.signingkey` config is used, falling back to gpg's default key.
+pub fn commit_signed(repo: &Repository, message: &str, key_id: Option<&str>) -> Result<()> {
+    refuse_during_merge(repo)?;
+    let mut index = repo_index(repo)?;
+
+    if index.is_empty() {
+        anyhow::bail!("No changes to commit.");
+    }
+
+    let tree_id = index.write_tree().context("Failed to write tree")?;
+    let tree = repo
+        .find_tree(tree_id)
+        .context("Failed to find written tree")?;
+
+    let signature = repo
+        .signature()
+        .context("Failed to get repository signature")?;
+
+    let parent_commit = match repo.head() {
+        Ok(head) => Some(
+            head.peel_to_commit()
+                .context("Failed to peel HEAD to commit")?,
+        ),
+        Err(_) => None,
+    };
+    let parents: Vec<&git2::Commit> = parent_commit.iter().collect();
+
+    let buffer = repo
+        .commit_create_buffer(&signature, &signature, message, &tree, &parents)
+        .context("Failed to create commit buffer")?;
+    let content = buffer
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Commit buffer is not valid UTF-8"))?;
+
+    let config = repo.config().context("Failed to read repository config")?;
+    let key_id = match key_id {
+        Some(key_id) => Some(key_id.to_string()),
+        None => config.get_string("user.signingkey").ok(),
+    };
+    let program = config
+        .get_string("gpg.program")
+        .unwrap_or_else(|_| "gpg".to_string());
+    let gpg_signature = gpg_sign(&program, content, key_id.as_deref())?;
+
+    let oid = repo
+        .commit_signed(content, &gpg_signature, Some("gpgsig"))
+        .context("Failed to write signed commit")?;
+
+    // `commit_signed` only writes the object; move the branch HEAD points at.
+    let reflog = format!("commit: {}", message.lines().next().unwrap_or(""));
+    let head = repo.find_reference("HEAD").context("Failed to get HEAD")?;
+    match head.symbolic_target() {
+        Some(branch) => {
+            repo.reference(branch, oid, true, &reflog)
+                .with_context(|| format!("Failed to update '{}'", branch))?;
+        }
+        None => {
+            repo.set_head_detached(oid)
+                .context("Failed to update detached HEAD")?;
+        }
+    }
 
+    Ok(())
+}
+
+/// Produces an armored detached signature of `content` with `program`.
+fn gpg_sign(program: &str, content: &str, key_id: Option<&str>) -> Result<String> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut command = Command::new(program);
+    command.args(["--status-fd=2", "-bsa"]);
+    if let Some(key_id) = key_id {
+        command.args(["-u", key_id]);
+    }
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| SigningError(format!("could not run '{}': {}", program, e)))?;
+
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(content.as_bytes())
+        .map_err(|e| SigningError(format!("could not write to '{}': {}", program, e)))?;
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| SigningError(format!("'{}' did not finish: {}", program, e)))?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() || !stderr.contains("[GNUPG:] SIG_CREATED ") {
+        let reason = stderr
+            .lines()
+            .rfind(|line| !line.starts_with("[GNUPG:]"))
+            .unwrap_or("no signature was created");
+        return Err(SigningError(reason.trim().to_string()).into());
+    }
+
+    String::from_utf8(output.stdout)
+        .map_err(|_| SigningError("signature is not valid UTF-8".to_string()).into())
+}
+
+/// Commits staged changes like `commit_changes`, adding a `Signed-off-by`
+/// trailer for the repository signature.
+pub fn commit_changes_with_signoff(repo: &Repository, message: &str) -> Result<()> {
+    let message = sign_off(repo, message)?;
+    commit_changes(repo, &message)
+}
+
+/// Returns `message` with a `Signed-off-by` trailer for the repository
+/// signature, unless that exact trailer is already present.
+pub fn sign_off(repo: &Repository, message: &str) -> Result<String> {
+    let signature = repo
+        .signature()
+        .context("Failed to get repository signature")?;
+    let trailer = format!(
+        "Signed-off-by: {} <{}>",
+        signature.name().unwrap_or("Unknown"),
+        signature.email().unwrap_or("")
+    );
+
+    let message = message.trim_end();
+    if message.lines().any(|line| line.trim() == trailer) {
+        return Ok(message.to_string());
+    }
+
+    // Join an existing sign-off block; otherwise start one after a blank line.
+    let last_line = message.lines().last().unwrap_or("");
+    if last_line.starts_with("Signed-off-by:") {
+        Ok(format!("{}\n{}", message, trailer))
+    } else {
+        Ok(format!("{}\n\n{}", message, trailer))
+    }
+}
+
+/// Returns the full message of the HEAD commit.
+pub fn head_commit_message(repo: &Repository) -> Result<String> {
+    let head = match repo.head() {
+        Ok(head) => head
+            .peel_to_commit()
+            .context("Failed to peel HEAD to commit")?,
+        Err(_) => anyhow::bail!("No commits yet."),
+    };
+
+    Ok(head.message().unwrap_or("").to_string())
+}
+
+/// Replaces the HEAD commit with one built from the current index. Passing
+/// `None` keeps the original message.
+pub fn amend_commit(repo: &Repository, message: Option<&str>) -> Result<()> {
+    let head = match repo.head() {
+        Ok(head) => head
+            .peel_to_commit()
+            .context("Failed to peel HEAD to commit")?,
+        Err(_) => anyhow::bail!("No commits yet, nothing to amend."),
+    };
+
+    let mut index = repo_index(repo)?;
+    let tree_id = index.write_tree().context("Failed to write tree")?;
+    let tree = repo
+        .find_tree(tree_id)
+        .context("Failed to find written tree")?;
+
+    // `amend` keeps the original parents, so only the tip commit is replaced.
+    head.amend(Some("HEAD"), None, None, None, message, Some(&tree))
+        .context("Failed to amend commit")?;
+
+    Ok(())
+}
+
+/// Replaces the HEAD commit's message, keeping its tree, so staged changes
+/// stay out of it.
+pub fn reword_head(repo: &Repository, message: &str) -> Result<()> {
+    let head = match repo.head() {
+        Ok(head) => head
+            .peel_to_commit()
+            .context("Failed to peel HEAD to commit")?,
+        Err(_) => anyhow::bail!("No commits yet, nothing to reword."),
+    };
+
+    head.amend(Some("HEAD"), None, None, None, Some(message), None)
+        .context("Failed to reword commit")?;
+
+    Ok(())
+}
+
+/// Merges the specified branch into the current branch.
+pub fn merge_branch(repo: &Repository, branch_name: &str) -> Result<()> {
     let current_branch = repo
         .head()
         .context("Failed to get HEAD")?
@@ -182,7 +706,7 @@ pub fn merge_branch(repo_path: &str, branch_name: &str) -> Result<()> {
     if analysis.0.is_up_to_date() {
         anyhow::bail!("Branch '{}' is already up-to-date.", branch_name);
     } else if analysis.0.is_fast_forward() {
-        let refname = format!("refs/heads/{}", branch_name);
+        let refname = format!("refs/heads/{}", current_branch);
         let mut reference = repo
             .find_reference(&refname)
             .context("Failed to find reference for fast-forward")?;
@@ -196,7 +720,7 @@ pub fn merge_branch(repo_path: &str, branch_name: &str) -> Result<()> {
             .context("Failed to merge branches")?;
 
         if repo.index()?.has_conflicts() {
-            anyhow::bail!("Merge conflicts detected. Please resolve them manually.");
+            anyhow::bail!("Merge conflicts detected. Resolve them in the conflicts view.");
         }
 
         let signature = repo
@@ -230,6 +754,9 @@ pub fn merge_branch(repo_path: &str, branch_name: &str) -> Result<()> {
             &[&head_commit, &merge_commit],
         )
         .context("Failed to create merge commit")?;
+
+        repo.cleanup_state()
+            .context("Failed to clean up merge state")?;
     } else {
         anyhow::bail!("Merge analysis returned unknown status.");
     }
@@ -237,11 +764,277 @@ pub fn merge_branch(repo_path: &str, branch_name: &str) -> Result<()> {
     Ok(())
 }
 
-/// Adds a remote repository.
-pub fn add_remote(repo_path: &str, remote_name: &str, remote_url: &str) -> Result<()> {
-    let repo = Repository::open(repo_path)
-        .with_context(|| format!("Failed to open repository at '{}'", repo_path))?;
+/// Which version of a conflicted file to keep.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ConflictSide {
+    Ours,
+    Theirs,
+}
+
+/// A file with unresolved conflicts and its content on each side; a side
+/// is `None` when it deleted the file.
+#[derive(Clone, Debug)]
+pub struct Conflict {
+    pub path: String,
+    pub base: Option<String>,
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+}
+
+/// Lists the conflicted files in the index with the content of each side.
+pub fn list_conflicts(repo: &Repository) -> Result<Vec<Conflict>> {
+    let index = repo_index(repo)?;
+    let conflicts = index.conflicts().context("Failed to read conflicts")?;
+
+    let mut result = Vec::new();
+    for conflict in conflicts {
+        let conflict = conflict.context("Failed to read conflict")?;
+        let Some(path) = [&conflict.our, &conflict.their, &conflict.ancestor]
+            .into_iter()
+            .flatten()
+            .map(|entry| String::from_utf8_lossy(&entry.path).into_owned())
+            .next()
+        else {
+            continue;
+        };
+        result.push(Conflict {
+            path,
+            base: conflict_side_text(repo, conflict.ancestor.as_ref())?,
+            ours: conflict_side_text(repo, conflict.our.as_ref())?,
+            theirs: conflict_side_text(repo, conflict.their.as_ref())?,
+        });
+    }
+    Ok(result)
+}
+
+/// Reads one side of a conflict for display.
+fn conflict_side_text(
+    repo: &Repository,
+    entry: Option<&git2::IndexEntry>,
+) -> Result<Option<String>> {
+    let Some(entry) = entry else {
+        return Ok(None);
+    };
+    let blob = repo
+        .find_blob(entry.id)
+        .context("Failed to read conflicting blob")?;
+    Ok(Some(if blob.is_binary() {
+        "(binary file)".to_string()
+    } else {
+        String::from_utf8_lossy(blob.content()).into_owned()
+    }))
+}
+
+/// Resolves the conflict on `path` by taking one side's version, writing it
+/// to the working tree and staging it. Taking a side that deleted the file
+/// deletes it.
+pub fn resolve_conflict(repo: &Repository, path: &str, side: ConflictSide) -> Result<()> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow::anyhow!("Cannot resolve conflicts in a bare repository."))?;
+    let mut index = repo_index(repo)?;
+    let conflict = index
+        .conflicts()
+        .context("Failed to read conflicts")?
+        .filter_map(|conflict| conflict.ok())
+        .find(|conflict| {
+            [&conflict.our, &conflict.their, &conflict.ancestor]
+                .into_iter()
+                .flatten()
+                .any(|entry| entry.path == path.as_bytes())
+        })
+        .with_context(|| format!("'{}' has no conflicts.", path))?;
+    let entry = match side {
+        ConflictSide::Ours => conflict.our,
+        ConflictSide::Theirs => conflict.their,
+    };
+
+    let full_path = workdir.join(path);
+    match entry {
+        Some(entry) => {
+            let blob = repo
+                .find_blob(entry.id)
+                .context("Failed to read conflicting blob")?;
+            if let Some(parent) = full_path.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create '{}'", parent.display()))?;
+            }
+            std::fs::write(&full_path, blob.content())
+                .with_context(|| format!("Failed to write '{}'", path))?;
+            // Staging the file also clears its conflict entries.
+            index
+                .add_path(std::path::Path::new(path))
+                .with_context(|| format!("Failed to stage '{}'", path))?;
+        }
+        None => {
+            if full_path.exists() {
+                std::fs::remove_file(&full_path)
+                    .with_context(|| format!("Failed to delete '{}'", path))?;
+            }
+            index
+                .remove_path(std::path::Path::new(path))
+                .with_context(|| format!("Failed to remove '{}' from the index", path))?;
+        }
+    }
+    index.write().context("Failed to write index")?;
+
+    Ok(())
+}
+
+/// Whether a merge stopped for conflicts is waiting to be committed.
+fn merge_in_progress(repo: &Repository) -> bool {
+    repo.state() == RepositoryState::Merge
+}
+
+/// Lists the files with conflict entries in `index`.
+fn conflicted_paths(index: &git2::Index) -> Result<Vec<String>> {
+    Ok(index
+        .conflicts()
+        .context("Failed to read conflicts")?
+        .flatten()
+        .filter_map(|conflict| conflict.our.or(conflict.their).or(conflict.ancestor))
+        .map(|entry| String::from_utf8_lossy(&entry.path).into_owned())
+        .collect())
+}
+
+/// Finishes the merge, rebase, cherry-pick or revert that stopped for
+/// conflicts, once they are resolved. Returns the name of the operation.
+pub fn continue_operation(repo: &mut Repository) -> Result<&'static str> {
+    let remaining = conflicted_paths(&repo_index(repo)?)?;
+    if !remaining.is_empty() {
+        anyhow::bail!(
+            "Still conflicted: {}. Resolve them before continuing.",
+            remaining.join(", ")
+        );
+    }
+
+    match repo.state() {
+        RepositoryState::Clean => anyhow::bail!("No operation in progress."),
+        RepositoryState::Merge => {
+            commit_merge(repo)?;
+            Ok("merge")
+        }
+        RepositoryState::Rebase
+        | RepositoryState::RebaseInteractive
+        | RepositoryState::RebaseMerge => {
+            rebase_continue(repo)?;
+            Ok("rebase")
+        }
+        RepositoryState::CherryPick => {
+            let picked = repo
+                .refname_to_id("CHERRY_PICK_HEAD")
+                .and_then(|id| repo.find_commit(id))
+                .context("Failed to read CHERRY_PICK_HEAD")?;
+            commit_prepared(
+                repo,
+                &[],
+                Some(&picked.author()),
+                picked.message().unwrap_or("Cherry-pick"),
+            )?;
+            Ok("cherry-pick")
+        }
+        RepositoryState::Revert => {
+            commit_prepared(repo, &[], None, "Revert")?;
+            Ok("revert")
+        }
+        state => anyhow::bail!(
+            "Continuing {:?} is not supported; finish it with git.",
+            state
+        ),
+    }
+}
+
+/// Abandons the merge, cherry-pick or revert that stopped for conflicts,
+/// resetting the index and work tree to HEAD. Returns the name of the
+/// operation.
+pub fn abort_operation(repo: &Repository) -> Result<&'static str> {
+    let operation = match repo.state() {
+        RepositoryState::Clean => anyhow::bail!("No operation in progress."),
+        RepositoryState::Merge => "merge",
+        RepositoryState::CherryPick => "cherry-pick",
+        RepositoryState::Revert => "revert",
+        state => anyhow::bail!("Aborting {:?} is not supported here.", state),
+    };
+
+    let head = repo
+        .head()
+        .context("Failed to get HEAD")?
+        .peel_to_commit()
+        .context("Failed to peel HEAD to commit")?;
+    repo.reset(head.as_object(), ResetType::Hard, None)
+        .context("Failed to reset to HEAD")?;
+    repo.cleanup_state()
+        .with_context(|| format!("Failed to clean up {} state", operation))?;
+
+    Ok(operation)
+}
+
+/// Creates the merge commit for a merge whose conflicts have been resolved.
+fn commit_merge(repo: &mut Repository) -> Result<()> {
+    let mut merge_heads = Vec::new();
+    repo.mergehead_foreach(|oid| {
+        merge_heads.push(*oid);
+        true
+    })
+    .context("Failed to read MERGE_HEAD")?;
+    commit_prepared(repo, &merge_heads, None, "Merge")
+}
+
+/// Commits the index on top of HEAD and `extra_parents` with the message git
+/// prepared for the stopped operation, then clears the operation's state.
+fn commit_prepared(
+    repo: &Repository,
+    extra_parents: &[Oid],
+    author: Option<&Signature>,
+    fallback_message: &str,
+) -> Result<()> {
+    let mut parents = vec![repo
+        .head()
+        .context("Failed to get HEAD")?
+        .peel_to_commit()
+        .context("Failed to peel HEAD to commit")?];
+    for oid in extra_parents {
+        parents.push(repo.find_commit(*oid).context("Failed to find parent")?);
+    }
+    let parents: Vec<&git2::Commit> = parents.iter().collect();
+
+    let message = repo.message().unwrap_or_default();
+    let message: Vec<&str> = message
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect();
+    let message = match message.join("\n").trim() {
+        "" => fallback_message.to_string(),
+        message => message.to_string(),
+    };
+
+    let signature = repo
+        .signature()
+        .context("Failed to get repository signature")?;
+    let tree_id = repo_index(repo)?
+        .write_tree()
+        .context("Failed to write tree")?;
+    let tree = repo
+        .find_tree(tree_id)
+        .context("Failed to find written tree")?;
+    repo.commit(
+        Some("HEAD"),
+        author.unwrap_or(&signature),
+        &signature,
+        &message,
+        &tree,
+        &parents,
+    )
+    .context("Failed to create commit")?;
+
+    repo.cleanup_state()
+        .context("Failed to clean up operation state")?;
 
+    Ok(())
+}
+
+/// Adds a remote repository.
+pub fn add_remote(repo: &Repository, remote_name: &str, remote_url: &str) -> Result<()> {
     repo.remote(remote_name, remote_url).with_context(|| {
         format!(
             "Failed to add remote '{}' with URL '{}'",
@@ -253,63 +1046,496 @@ pub fn add_remote(repo_path: &str, remote_name: &str, remote_url: &str) -> Resul
 }
 
 /// Removes a remote repository.
-pub fn remove_remote(repo_path: &str, remote_name: &str) -> Result<()> {
-    let repo = Repository::open(repo_path)
-        .with_context(|| format!("Failed to open repository at '{}'", repo_path))?;
-
+pub fn remove_remote(repo: &Repository, remote_name: &str) -> Result<()> {
     repo.remote_delete(remote_name)
         .with_context(|| format!("Failed to remove remote '{}'", remote_name))?;
 
     Ok(())
 }
 
-/// Pushes the current branch to the specified remote.
-pub fn push_branch(repo_path: &str, remote_name: &str, branch_name: &str) -> Result<()> {
-    let repo = Repository::open(repo_path)
-        .with_context(|| format!("Failed to open repository at '{}'", repo_path))?;
+/// Renames a remote, returning any refspecs that could not be updated.
+pub fn rename_remote(repo: &Repository, old_name: &str, new_name: &str) -> Result<Vec<String>> {
+    let problems = repo
+        .remote_rename(old_name, new_name)
+        .with_context(|| format!("Failed to rename remote '{}' to '{}'", old_name, new_name))?;
 
-    let mut remote = repo
-        .find_remote(remote_name)
-        .with_context(|| format!("Remote '{}' not found.", remote_name))?;
+    Ok(problems.iter().flatten().map(str::to_string).collect())
+}
 
-    let refspec = format!("refs/heads/{}:refs/heads/{}", branch_name, branch_name);
-    remote.push(&[&refspec], None).with_context(|| {
-        format!(
-            "Failed to push branch '{}' to remote '{}'",
-            branch_name, remote_name
-        )
-    })?;
+/// Changes the fetch URL of a remote.
+pub fn set_remote_url(repo: &Repository, remote_name: &str, remote_url: &str) -> Result<()> {
+    repo.find_remote(remote_name)
+        .with_context(|| format!("Remote '{}' not found.", remote_name))?;
+    repo.remote_set_url(remote_name, remote_url)
+        .with_context(|| {
+            format!(
+                "Failed to set URL of remote '{}' to '{}'",
+                remote_name, remote_url
+            )
+        })?;
 
     Ok(())
 }
 
-/// Pulls the latest changes from the specified remote and branch.
-pub fn pull_branch(repo_path: &str, remote_name: &str, branch_name: &str) -> Result<()> {
-    let repo = Repository::open(repo_path)
-        .with_context(|| format!("Failed to open repository at '{}'", repo_path))?;
+/// Lists remotes as `(name, fetch URL)` pairs.
+pub fn list_remotes(repo: &Repository) -> Result<Vec<(String, String)>> {
+    let names = repo.remotes().context("Failed to list remotes")?;
+    let mut remotes = Vec::new();
+    for name in names.iter().flatten() {
+        let remote = repo
+            .find_remote(name)
+            .with_context(|| format!("Remote '{}' not found.", name))?;
+        remotes.push((name.to_string(), remote.url().unwrap_or("").to_string()));
+    }
+
+    Ok(remotes)
+}
+
+/// Builds remote callbacks that authenticate with the SSH agent, then
+/// `~/.ssh/id_rsa`, then a username/password from the git credential helper
+/// or the `GIT_USERNAME`/`GIT_PASSWORD` environment variables.
+pub fn default_callbacks() -> RemoteCallbacks<'static> {
+    let mut callbacks = RemoteCallbacks::new();
+    let mut attempts = 0;
+
+    callbacks.credentials(move |url, username_from_url, allowed| {
+        let username = username_from_url.unwrap_or("git");
+        if allowed.contains(CredentialType::USERNAME) {
+            return Cred::username(username);
+        }
+
+        // libgit2 keeps calling back while credentials are rejected, so each
+        // attempt moves on to the next strategy until none are left.
+        attempts += 1;
+
+        if allowed.contains(CredentialType::SSH_KEY) {
+            if attempts == 1 {
+                return Cred::ssh_key_from_agent(username);
+            }
+            if attempts == 2 {
+                if let Some(home) = std::env::var_os("HOME") {
+                    let key = std::path::Path::new(&home).join(".ssh").join("id_rsa");
+                    if key.exists() {
+                        return Cred::ssh_key(username, None, &key, None);
+                    }
+                }
+            }
+        }
+
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) && attempts <= 3 {
+            if let Ok(config) = git2::Config::open_default() {
+                if let Ok(cred) = Cred::credential_helper(&config, url, username_from_url) {
+                    return Ok(cred);
+                }
+            }
+            if let (Ok(user), Ok(password)) =
+                (std::env::var("GIT_USERNAME"), std::env::var("GIT_PASSWORD"))
+            {
+                return Cred::userpass_plaintext(&user, &password);
+            }
+        }
+
+        Err(Error::new(
+            ErrorCode::Auth,
+            ErrorClass::Net,
+            "no usable credentials",
+        ))
+    });
+
+    callbacks
+}
+
+/// Counters from a fetch or clone's transfer progress, copied out of the
+/// callback so they can be sent to the UI thread.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transfer {
+    pub received_objects: usize,
+    pub total_objects: usize,
+    pub received_bytes: usize,
+}
+
+impl From<git2::Progress<'_>> for Transfer {
+    fn from(progress: git2::Progress<'_>) -> Transfer {
+        Transfer {
+            received_objects: progress.received_objects(),
+            total_objects: progress.total_objects(),
+            received_bytes: progress.received_bytes(),
+        }
+    }
+}
+
+/// Turns authentication failures into a clear message instead of libgit2's.
+fn remote_error(e: Error, remote_name: &str) -> anyhow::Error {
+    if e.code() == ErrorCode::Auth || e.class() == ErrorClass::Ssh {
+        anyhow::anyhow!(
+            "Authentication failed for remote '{}': {}",
+            remote_name,
+            e.message()
+        )
+    } else {
+        anyhow::Error::new(e)
+    }
+}
 
+/// Outcome of `fetch_remote`.
+pub struct FetchSummary {
+    pub remote: String,
+    /// Number of remote-tracking refs that were created or moved.
+    pub updated_refs: usize,
+    /// Remote-tracking branch matching the current branch, if any.
+    pub branch: Option<String>,
+    /// New tip of `branch`.
+    pub tip: Option<String>,
+    /// Commits on `branch` that were not there before the fetch.
+    pub new_commits: usize,
+    /// Remote-tracking refs deleted because they are gone from the remote.
+    pub pruned_refs: usize,
+}
+
+/// Fetches from a remote without merging anything. An empty `refspecs`
+/// fetches the remote's configured refspecs. With `prune`, remote-tracking
+/// refs for branches deleted on the remote are removed.
+pub fn fetch_remote(
+    repo: &Repository,
+    remote_name: &str,
+    refspecs: &[String],
+    prune: bool,
+    callbacks: RemoteCallbacks,
+) -> Result<FetchSummary> {
     let mut remote = repo
         .find_remote(remote_name)
         .with_context(|| format!("Remote '{}' not found.", remote_name))?;
 
-    let annotated = repo
-        .find_annotated_commit(
-            repo.refname_to_id(&format!("refs/heads/{}", branch_name))
-                .with_context(|| format!("Branch '{}' not found.", branch_name))?,
-        )
+    let prefix = format!("refs/remotes/{}/", remote_name);
+    let tracking_tips = |repo: &Repository| -> Result<Vec<(String, Oid)>> {
+        let mut tips = Vec::new();
+        for reference in repo
+            .references_glob(&format!("{}*", prefix))
+            .context("Failed to list remote-tracking refs")?
+        {
+            let reference = reference.context("Failed to read reference")?;
+            if let (Some(name), Some(oid)) = (reference.name(), reference.target()) {
+                tips.push((name.to_string(), oid));
+            }
+        }
+        Ok(tips)
+    };
+
+    let before = tracking_tips(repo)?;
+
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(callbacks);
+    if prune {
+        fetch_options.prune(FetchPrune::On);
+    }
+    remote
+        .fetch(refspecs, Some(&mut fetch_options), None)
+        .map_err(|e| remote_error(e, remote_name))
+        .with_context(|| format!("Failed to fetch from remote '{}'", remote_name))?;
+
+    let after = tracking_tips(repo)?;
+    let updated_refs = after
+        .iter()
+        .filter(|(name, oid)| !before.contains(&(name.clone(), *oid)))
+        .count();
+    let pruned_refs = before
+        .iter()
+        .filter(|(name, _)| !after.iter().any(|(after_name, _)| after_name == name))
+        .count();
+
+    let current = repo
+        .head()
+        .ok()
+        .and_then(|head| head.shorthand().map(str::to_string));
+    let mut summary = FetchSummary {
+        remote: remote_name.to_string(),
+        updated_refs,
+        branch: None,
+        tip: None,
+        new_commits: 0,
+        pruned_refs,
+    };
+
+    if let Some(branch) = current {
+        let refname = format!("{}{}", prefix, branch);
+        if let Some((_, new_tip)) = after.iter().find(|(name, _)| *name == refname) {
+            let old_tip = before
+                .iter()
+                .find(|(name, _)| *name == refname)
+                .map(|(_, oid)| *oid);
+            summary.new_commits = match old_tip {
+                Some(old_tip) => repo
+                    .graph_ahead_behind(*new_tip, old_tip)
+                    .map(|(ahead, _)| ahead)
+                    .unwrap_or(0),
+                None => unique_commits(repo, *new_tip, None)?.len(),
+            };
+            summary.branch = Some(branch);
+            summary.tip = Some(new_tip.to_string());
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Fetches every configured remote in turn, returning each one's outcome so
+/// that one unreachable remote doesn't hide the others. `callbacks` builds
+/// fresh credential callbacks for each fetch.
+pub fn fetch_all<'a>(
+    repo: &Repository,
+    prune: bool,
+    callbacks: &mut dyn FnMut() -> RemoteCallbacks<'a>,
+) -> Result<Vec<(String, Result<FetchSummary>)>> {
+    let remotes = repo.remotes().context("Failed to list remotes")?;
+    Ok(remotes
+        .iter()
+        .flatten()
+        .map(|name| {
+            let result = fetch_remote(repo, name, &[], prune, callbacks());
+            (name.to_string(), result)
+        })
+        .collect())
+}
+
+/// Pushes the current branch to the specified remote. With `set_upstream`,
+/// the branch is then configured to track `remote_name/branch_name`.
+pub fn push_branch(
+    repo: &Repository,
+    remote_name: &str,
+    branch_name: &str,
+    set_upstream: bool,
+    force: bool,
+) -> Result<()> {
+    let mut remote = repo
+        .find_remote(remote_name)
+        .with_context(|| format!("Remote '{}' not found.", remote_name))?;
+
+    // The server reports rejected refs here rather than failing the push.
+    let mut callbacks = default_callbacks();
+    callbacks.push_update_reference(|refname, status| match status {
+        Some(reason) => Err(Error::from_str(&format!(
+            "'{}' was rejected: {}",
+            refname, reason
+        ))),
+        None => Ok(()),
+    });
+    let mut push_options = PushOptions::new();
+    push_options.remote_callbacks(callbacks);
+
+    // A leading '+' lets the push replace history the remote has.
+    let refspec = format!(
+        "{}refs/heads/{}:refs/heads/{}",
+        if force { "+" } else { "" },
+        branch_name,
+        branch_name
+    );
+    remote
+        .push(&[&refspec], Some(&mut push_options))
+        .map_err(|e| remote_error(e, remote_name))
         .with_context(|| {
             format!(
-                "Failed to find annotated commit for branch '{}'",
-                branch_name
+                "Failed to push branch '{}' to remote '{}'",
+                branch_name, remote_name
             )
         })?;
 
-    remote.fetch(&[branch_name], None, None).with_context(|| {
-        format!(
-            "Failed to fetch branch '{}' from remote '{}'",
-            branch_name, remote_name
-        )
-    })?;
+    if set_upstream {
+        let mut branch = repo
+            .find_branch(branch_name, BranchType::Local)
+            .with_context(|| format!("Branch '{}' not found.", branch_name))?;
+        let upstream = format!("{}/{}", remote_name, branch_name);
+        branch
+            .set_upstream(Some(&upstream))
+            .with_context(|| format!("Failed to set upstream to '{}'", upstream))?;
+    }
+
+    Ok(())
+}
+
+/// Deletes `branch_name` on the remote by pushing an empty source to it,
+/// then drops the matching remote-tracking branch.
+pub fn delete_remote_branch(repo: &Repository, remote_name: &str, branch_name: &str) -> Result<()> {
+    let mut remote = repo
+        .find_remote(remote_name)
+        .with_context(|| format!("Remote '{}' not found.", remote_name))?;
+
+    let mut callbacks = default_callbacks();
+    callbacks.push_update_reference(|refname, status| match status {
+        Some(reason) => Err(Error::from_str(&format!(
+            "'{}' was rejected: {}",
+            refname, reason
+        ))),
+        None => Ok(()),
+    });
+    let mut push_options = PushOptions::new();
+    push_options.remote_callbacks(callbacks);
+
+    let refspec = format!(":refs/heads/{}", branch_name);
+    remote
+        .push(&[&refspec], Some(&mut push_options))
+        .map_err(|e| remote_error(e, remote_name))
+        .with_context(|| {
+            format!(
+                "Failed to delete branch '{}' on remote '{}'",
+                branch_name, remote_name
+            )
+        })?;
+
+    // Gone upstream, so the next fetch would prune it anyway.
+    if let Ok(mut tracking) = repo.find_branch(
+        &format!("{}/{}", remote_name, branch_name),
+        BranchType::Remote,
+    ) {
+        tracking.delete().with_context(|| {
+            format!(
+                "Deleted upstream, but failed to remove '{}/{}' locally",
+                remote_name, branch_name
+            )
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Splits a remote-tracking branch such as `origin/feature/x` into its
+/// remote and the branch name on that remote.
+pub fn split_remote_branch(repo: &Repository, name: &str) -> Result<(String, String)> {
+    let refname = format!("refs/remotes/{}", name);
+    let remote = repo
+        .branch_remote_name(&refname)
+        .with_context(|| format!("No remote owns '{}'.", name))?;
+    let remote = remote
+        .as_str()
+        .with_context(|| format!("Remote name for '{}' is not valid UTF-8.", name))?
+        .to_string();
+    let branch = name[remote.len() + 1..].to_string();
+    Ok((remote, branch))
+}
+
+/// Returns the upstream of a local branch as `remote/branch`, if configured.
+pub fn branch_upstream(repo: &Repository, branch_name: &str) -> Result<Option<String>> {
+    let branch = repo
+        .find_branch(branch_name, BranchType::Local)
+        .with_context(|| format!("Branch '{}' not found.", branch_name))?;
+
+    let upstream = match branch.upstream() {
+        Ok(upstream) => upstream,
+        Err(e) if e.code() == ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e).context("Failed to resolve upstream"),
+    };
+
+    Ok(upstream.name().ok().flatten().map(str::to_string))
+}
+
+/// How many commits a local branch is ahead of and behind its upstream, or
+/// `None` when it has no upstream.
+pub fn branch_ahead_behind(repo: &Repository, branch_name: &str) -> Result<Option<(usize, usize)>> {
+    let branch = repo
+        .find_branch(branch_name, BranchType::Local)
+        .with_context(|| format!("Branch '{}' not found.", branch_name))?;
+
+    let upstream = match branch.upstream() {
+        Ok(upstream) => upstream,
+        Err(e) if e.code() == ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e).context("Failed to resolve upstream"),
+    };
+
+    let (Some(local), Some(remote)) = (branch.get().target(), upstream.get().target()) else {
+        return Ok(None);
+    };
+    let counts = repo
+        .graph_ahead_behind(local, remote)
+        .with_context(|| format!("Failed to compare '{}' with its upstream", branch_name))?;
+    Ok(Some(counts))
+}
+
+/// Makes a local branch track the remote-tracking branch `upstream`
+/// (`remote/branch`), or stops it tracking anything with `None`.
+pub fn set_upstream(repo: &Repository, branch_name: &str, upstream: Option<&str>) -> Result<()> {
+    let mut branch = repo
+        .find_branch(branch_name, BranchType::Local)
+        .with_context(|| format!("Branch '{}' not found.", branch_name))?;
+    if let Some(upstream) = upstream {
+        repo.find_branch(upstream, BranchType::Remote)
+            .with_context(|| {
+                format!(
+                    "No remote-tracking branch '{}'; fetch it first or check the name.",
+                    upstream
+                )
+            })?;
+    } else if branch.upstream().is_err() {
+        // Nothing to clear.
+        return Ok(());
+    }
+    branch
+        .set_upstream(upstream)
+        .with_context(|| format!("Failed to set the upstream of '{}'", branch_name))?;
+    Ok(())
+}
+
+/// Pulls the current branch from its configured upstream. With `rebase`,
+/// local commits are replayed on top of the upstream instead of merged.
+pub fn pull_upstream(repo: &Repository, rebase: bool, callbacks: RemoteCallbacks) -> Result<()> {
+    let head = repo.head().context("Failed to get HEAD")?;
+    let refname = head
+        .name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid HEAD"))?
+        .to_string();
+    let branch_name = head
+        .shorthand()
+        .ok_or_else(|| anyhow::anyhow!("Invalid HEAD"))?
+        .to_string();
+
+    let remote = repo
+        .branch_upstream_remote(&refname)
+        .map_err(|_| anyhow::anyhow!("Branch '{}' has no upstream.", branch_name))?;
+    let remote = remote
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Invalid upstream remote name"))?;
+    let merge = repo
+        .config()
+        .context("Failed to read repository config")?
+        .get_string(&format!("branch.{}.merge", branch_name))
+        .map_err(|_| anyhow::anyhow!("Branch '{}' has no upstream.", branch_name))?;
+    let upstream_branch = merge.trim_start_matches("refs/heads/");
+
+    pull_branch(repo, remote, upstream_branch, rebase, callbacks)
+}
+
+/// Pulls the latest changes from the specified remote and branch. When the
+/// branches have diverged, `rebase` rebases local commits onto the fetched
+/// ones like `git pull --rebase`; otherwise a merge commit is created.
+pub fn pull_branch(
+    repo: &Repository,
+    remote_name: &str,
+    branch_name: &str,
+    rebase: bool,
+    callbacks: RemoteCallbacks,
+) -> Result<()> {
+    let mut remote = repo
+        .find_remote(remote_name)
+        .with_context(|| format!("Remote '{}' not found.", remote_name))?;
+
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(callbacks);
+
+    remote
+        .fetch(&[branch_name], Some(&mut fetch_options), None)
+        .map_err(|e| remote_error(e, remote_name))
+        .with_context(|| {
+            format!(
+                "Failed to fetch branch '{}' from remote '{}'",
+                branch_name, remote_name
+            )
+        })?;
+
+    // Merge what was just fetched, not the local branch itself.
+    let fetch_head = repo
+        .find_reference("FETCH_HEAD")
+        .context("Failed to find FETCH_HEAD")?;
+    let annotated = repo
+        .reference_to_annotated_commit(&fetch_head)
+        .context("Failed to find annotated commit for FETCH_HEAD")?;
 
     let analysis = repo
         .merge_analysis(&[&annotated])
@@ -318,21 +1544,29 @@ pub fn pull_branch(repo_path: &str, remote_name: &str, branch_name: &str) -> Res
     if analysis.0.is_up_to_date() {
         anyhow::bail!("Branch '{}' is already up-to-date.", branch_name);
     } else if analysis.0.is_fast_forward() {
-        let refname = format!("refs/heads/{}", branch_name);
+        // Pulling always updates the checked-out branch.
         let mut reference = repo
-            .find_reference(&refname)
+            .head()
             .context("Failed to find reference for fast-forward")?;
         reference
             .set_target(annotated.id(), "Fast-Forward Merge")
             .context("Failed to set target for fast-forward")?;
         repo.checkout_head(Some(git2::build::CheckoutBuilder::default().force()))
             .context("Failed to checkout head after fast-forward")?;
+    } else if analysis.0.is_normal() && rebase {
+        rebase_onto_commit(
+            repo,
+            &annotated,
+            &format!("{}/{}", remote_name, branch_name),
+        )?;
     } else if analysis.0.is_normal() {
         repo.merge(&[&annotated], None, None)
             .context("Failed to merge fetched changes")?;
 
         if repo.index()?.has_conflicts() {
-            anyhow::bail!("Merge conflicts detected during pull. Please resolve them manually.");
+            anyhow::bail!(
+                "Merge conflicts detected during pull. Resolve them in the conflicts view."
+            );
         }
 
         let signature = repo
@@ -372,3 +1606,2076 @@ pub fn pull_branch(repo_path: &str, remote_name: &str, branch_name: &str) -> Res
 
     Ok(())
 }
+
+/// Lists the files in `diff` as `"<status> <path>"` lines.
+fn delta_lines(diff: &git2::Diff) -> Vec<String> {
+    diff.deltas()
+        .map(|delta| {
+            let status = match delta.status() {
+                Delta::Added => "A",
+                Delta::Deleted => "D",
+                Delta::Modified => "M",
+                Delta::Renamed => "R",
+                Delta::Copied => "C",
+                Delta::Typechange => "T",
+                _ => " ",
+            };
+            let path = delta
+                .new_file()
+                .path()
+                .or_else(|| delta.old_file().path())
+                .map(|p| p.display().to_string())
+                .unwrap_or_else(|| "Unknown".to_string());
+            format!("{} {}", status, path)
+        })
+        .collect()
+}
+
+/// What the next commit would contain.
+#[derive(Debug)]
+pub struct StagedSummary {
+    /// Staged files as `"<status> <path>"` lines.
+    pub files: Vec<String>,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+/// Summarizes the changes staged in the index relative to HEAD.
+pub fn staged_summary(repo: &Repository) -> Result<StagedSummary> {
+    let head_tree = match repo.head() {
+        Ok(head) => Some(head.peel_to_tree().context("Failed to peel HEAD to tree")?),
+        Err(_) => None,
+    };
+    let diff = repo
+        .diff_tree_to_index(head_tree.as_ref(), None, None)
+        .context("Failed to diff HEAD against index")?;
+    let stats = diff.stats().context("Failed to compute diff stats")?;
+
+    Ok(StagedSummary {
+        files: delta_lines(&diff),
+        insertions: stats.insertions(),
+        deletions: stats.deletions(),
+    })
+}
+
+/// What a commit changed relative to its first parent.
+pub struct CommitStats {
+    /// Changed files as `"<status> <path>  +<insertions> -<deletions>"` lines.
+    pub files: Vec<String>,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+/// Diffs `commit` against its first parent, or against an empty tree for a root commit.
+pub fn commit_stats(repo: &Repository, commit: &git2::Commit) -> Result<CommitStats> {
+    let tree = commit.tree().context("Failed to get commit tree")?;
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree().context("Failed to get parent tree")?),
+        Err(_) => None,
+    };
+    let mut diff = repo
+        .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)
+        .context("Failed to diff commit against its parent")?;
+    diff.find_similar(None)
+        .context("Failed to detect renamed files")?;
+    let stats = diff.stats().context("Failed to compute diff stats")?;
+
+    let mut files = Vec::new();
+    for (index, line) in delta_lines(&diff).into_iter().enumerate() {
+        let (_, insertions, deletions) =
+            match git2::Patch::from_diff(&diff, index).context("Failed to compute file stats")? {
+                Some(patch) => patch.line_stats().context("Failed to compute file stats")?,
+                None => (0, 0, 0),
+            };
+        files.push(format!("{}  +{} -{}", line, insertions, deletions));
+    }
+
+    Ok(CommitStats {
+        files,
+        insertions: stats.insertions(),
+        deletions: stats.deletions(),
+    })
+}
+
+/// Lines inserted and deleted by `commit` against its first parent, without
+/// the per-file breakdown `commit_stats` builds.
+pub fn commit_diffstat(repo: &Repository, commit: &git2::Commit) -> Result<(usize, usize)> {
+    let tree = commit.tree().context("Failed to get commit tree")?;
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree().context("Failed to get parent tree")?),
+        Err(_) => None,
+    };
+    let diff = repo
+        .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)
+        .context("Failed to diff commit against its parent")?;
+    let stats = diff.stats().context("Failed to compute diff stats")?;
+    Ok((stats.insertions(), stats.deletions()))
+}
+
+/// Writes `commit_id` as a `git format-patch` style email, ready for
+/// `git am`, to `out_dir` and returns the file's path.
+pub fn format_patch(
+    repo: &Repository,
+    commit_id: &str,
+    out_dir: &std::path::Path,
+) -> Result<std::path::PathBuf> {
+    let oid =
+        Oid::from_str(commit_id).with_context(|| format!("Invalid commit id '{}'", commit_id))?;
+    let commit = repo
+        .find_commit(oid)
+        .with_context(|| format!("Commit '{}' not found.", commit_id))?;
+    if commit.parent_count() > 1 {
+        anyhow::bail!(
+            "{:.7} is a merge commit; it has no single diff to export.",
+            commit_id
+        );
+    }
+
+    let email = git2::Email::from_commit(&commit, &mut git2::EmailCreateOptions::new())
+        .with_context(|| format!("Failed to format {:.7} as a patch", commit_id))?;
+    let path = out_dir.join(format!(
+        "0001-{}.patch",
+        patch_file_slug(commit.summary().unwrap_or(""))
+    ));
+    std::fs::write(&path, email.as_slice())
+        .with_context(|| format!("Failed to write '{}'", path.display()))?;
+    Ok(path)
+}
+
+/// Applies the patch at `patch_path` to the work tree or, with `commit`, to
+/// the index as well and commits it with the patch's author and message.
+/// Nothing is touched unless every hunk applies. Returns the commit message
+/// used, or `None` when the patch wasn't committed.
+pub fn apply_patch(
+    repo: &Repository,
+    patch_path: &std::path::Path,
+    commit: bool,
+) -> Result<Option<String>> {
+    let text = std::fs::read(patch_path)
+        .with_context(|| format!("Failed to read '{}'", patch_path.display()))?;
+    let diff = git2::Diff::from_buffer(&text)
+        .with_context(|| format!("'{}' is not a patch", patch_path.display()))?;
+    if commit && !staged_summary(repo)?.files.is_empty() {
+        anyhow::bail!("You have staged changes that would be committed with the patch. Commit or unstage them first.");
+    }
+
+    let location = if commit {
+        git2::ApplyLocation::Both
+    } else {
+        git2::ApplyLocation::WorkDir
+    };
+    // A dry run first, so a hunk that doesn't fit leaves the tree untouched.
+    let mut check = git2::ApplyOptions::new();
+    check.check(true);
+    let does_not_apply = |e: Error| anyhow::anyhow!("The patch does not apply: {}", e.message());
+    repo.apply(&diff, location, Some(&mut check))
+        .map_err(does_not_apply)?;
+    // Clashes with the work tree only show up now, but libgit2 still backs
+    // out without writing anything.
+    repo.apply(&diff, location, None).map_err(does_not_apply)?;
+    if !commit {
+        return Ok(None);
+    }
+
+    let mail = PatchMail::parse(&String::from_utf8_lossy(&text));
+    let message = mail.message.unwrap_or_else(|| {
+        let name = patch_path.file_name().unwrap_or_default();
+        format!("Apply {}", name.to_string_lossy())
+    });
+    let committer = repo
+        .signature()
+        .context("Failed to get repository signature")?;
+    let author = match (&mail.author, mail.time) {
+        (Some((name, email)), Some(time)) => Signature::new(name, email, &time).ok(),
+        (Some((name, email)), None) => Signature::now(name, email).ok(),
+        _ => None,
+    };
+    let tree_id = repo_index(repo)?
+        .write_tree()
+        .context("Failed to write tree")?;
+    let tree = repo
+        .find_tree(tree_id)
+        .context("Failed to find written tree")?;
+    let parent = repo
+        .head()
+        .context("Failed to get HEAD")?
+        .peel_to_commit()
+        .context("Failed to peel HEAD to commit")?;
+    repo.commit(
+        Some("HEAD"),
+        author.as_ref().unwrap_or(&committer),
+        &committer,
+        &message,
+        &tree,
+        &[&parent],
+    )
+    .context("Applied the patch, but failed to commit it")?;
+    Ok(Some(message))
+}
+
+/// The headers of a `git format-patch` email that a commit is made from.
+struct PatchMail {
+    author: Option<(String, String)>,
+    time: Option<git2::Time>,
+    message: Option<String>,
+}
+
+impl PatchMail {
+    fn parse(text: &str) -> PatchMail {
+        let mut mail = PatchMail {
+            author: None,
+            time: None,
+            message: None,
+        };
+        let mut lines = text.lines().peekable();
+        let mut subject: Option<String> = None;
+        while let Some(line) = lines.next() {
+            if line.is_empty() {
+                break;
+            }
+            if let Some(from) = line.strip_prefix("From: ") {
+                if let Some((name, email)) = from.rsplit_once(" <") {
+                    mail.author = Some((
+                        name.trim().to_string(),
+                        email.trim_end_matches('>').to_string(),
+                    ));
+                }
+            } else if let Some(date) = line.strip_prefix("Date: ") {
+                mail.time = chrono::DateTime::parse_from_rfc2822(date.trim())
+                    .ok()
+                    .map(|date| {
+                        git2::Time::new(date.timestamp(), date.offset().local_minus_utc() / 60)
+                    });
+            } else if let Some(text) = line.strip_prefix("Subject: ") {
+                let mut text = text.to_string();
+                // Long subjects are folded onto indented lines.
+                while let Some(next) = lines.peek().filter(|l| l.starts_with([' ', '\t'])) {
+                    text.push_str(next);
+                    lines.next();
+                }
+                let text = match text.strip_prefix('[') {
+                    Some(rest) => rest.split_once(']').map_or(text.as_str(), |(_, s)| s),
+                    None => text.as_str(),
+                };
+                subject = Some(text.trim().to_string());
+            }
+        }
+        let Some(subject) = subject else {
+            return mail;
+        };
+
+        let body: Vec<&str> = lines
+            .take_while(|line| *line != "---" && !line.starts_with("diff --git"))
+            .collect();
+        let body = body.join("\n");
+        mail.message = Some(match body.trim() {
+            "" => subject,
+            body => format!("{}\n\n{}", subject, body),
+        });
+        mail
+    }
+}
+
+/// Turns a commit subject into a file name the way `git format-patch` does.
+fn patch_file_slug(subject: &str) -> String {
+    let mut slug = String::new();
+    for c in subject.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+        if slug.len() >= 52 {
+            break;
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "patch".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// Resolves a full or abbreviated hash (or any revision) to a commit id.
+pub fn resolve_commit(repo: &Repository, spec: &str) -> Result<Oid> {
+    let object = repo.revparse_single(spec).map_err(|e| match e.code() {
+        ErrorCode::Ambiguous => {
+            anyhow::anyhow!(
+                "'{}' matches more than one object; type more of the hash.",
+                spec
+            )
+        }
+        ErrorCode::NotFound => anyhow::anyhow!("No commit matches '{}'.", spec),
+        _ => anyhow::Error::new(e).context(format!("Failed to resolve '{}'", spec)),
+    })?;
+    let commit = object
+        .peel_to_commit()
+        .with_context(|| format!("'{}' is not a commit.", spec))?;
+    Ok(commit.id())
+}
+
+/// Whether `commit` changed anything at or under `path` compared with its
+/// first parent; a root commit counts if it added the path.
+pub fn commit_touches_path(repo: &Repository, commit: &git2::Commit, path: &str) -> Result<bool> {
+    let tree = commit.tree().context("Failed to get commit tree")?;
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree().context("Failed to get parent tree")?),
+        Err(_) => None,
+    };
+    let mut opts = DiffOptions::new();
+    opts.pathspec(path);
+    let diff = repo
+        .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut opts))
+        .context("Failed to diff commit against its parent")?;
+    Ok(diff.deltas().len() > 0)
+}
+
+/// Summary of how two local branches have diverged.
+pub struct BranchComparison {
+    pub base: String,
+    pub head: String,
+    pub merge_base: Option<String>,
+    /// Commits reachable from `head` but not from `base`.
+    pub ahead: Vec<String>,
+    /// Commits reachable from `base` but not from `head`.
+    pub behind: Vec<String>,
+    /// Files that differ between the two branch tips.
+    pub files: Vec<String>,
+}
+
+/// Compares two local branches: merge base, commits unique to each and changed files.
+pub fn compare_branches(repo: &Repository, base: &str, head: &str) -> Result<BranchComparison> {
+    let base_commit = repo
+        .find_branch(base, BranchType::Local)
+        .with_context(|| format!("Branch '{}' not found.", base))?
+        .get()
+        .peel_to_commit()
+        .context("Failed to peel branch to commit")?;
+    let head_commit = repo
+        .find_branch(head, BranchType::Local)
+        .with_context(|| format!("Branch '{}' not found.", head))?
+        .get()
+        .peel_to_commit()
+        .context("Failed to peel branch to commit")?;
+
+    // Unrelated histories have no merge base; every commit is then unique.
+    let merge_base = repo.merge_base(base_commit.id(), head_commit.id()).ok();
+
+    let ahead = unique_commits(repo, head_commit.id(), merge_base)?;
+    let behind = unique_commits(repo, base_commit.id(), merge_base)?;
+
+    let base_tree = base_commit.tree().context("Failed to get base tree")?;
+    let head_tree = head_commit.tree().context("Failed to get head tree")?;
+    let diff = repo
+        .diff_tree_to_tree(Some(&base_tree), Some(&head_tree), None)
+        .context("Failed to diff branch tips")?;
+
+    let files = delta_lines(&diff);
+
+    Ok(BranchComparison {
+        base: base.to_string(),
+        head: head.to_string(),
+        merge_base: merge_base.map(|oid| oid.to_string()),
+        ahead,
+        behind,
+        files,
+    })
+}
+
+/// Lists `short-id summary` for commits reachable from `tip` but not from `hide`.
+fn unique_commits(repo: &Repository, tip: Oid, hide: Option<Oid>) -> Result<Vec<String>> {
+    let mut revwalk = repo.revwalk().context("Failed to create revwalk")?;
+    revwalk
+        .push(tip)
+        .context("Failed to push commit to revwalk")?;
+    if let Some(hide) = hide {
+        revwalk.hide(hide).context("Failed to hide merge base")?;
+    }
+    commit_summaries(repo, revwalk)
+}
+
+/// Fetches `remote_name`, then lists (as `short-id summary`) the commits a
+/// push of `branch_name` there would send, without pushing anything.
+pub fn push_dry_run(
+    repo: &Repository,
+    remote_name: &str,
+    branch_name: &str,
+    callbacks: RemoteCallbacks,
+) -> Result<Vec<String>> {
+    fetch_remote(repo, remote_name, &[], false, callbacks)?;
+    let tip = repo
+        .refname_to_id(&format!("refs/heads/{}", branch_name))
+        .with_context(|| format!("Branch '{}' not found.", branch_name))?;
+    let tracking = format!("refs/remotes/{}/{}", remote_name, branch_name);
+    if let Ok(remote_tip) = repo.refname_to_id(&tracking) {
+        return unique_commits(repo, tip, Some(remote_tip));
+    }
+
+    // A new branch sends whatever no branch on the remote has yet.
+    let mut revwalk = repo.revwalk().context("Failed to create revwalk")?;
+    revwalk
+        .push(tip)
+        .context("Failed to push commit to revwalk")?;
+    revwalk
+        .hide_glob(&format!("refs/remotes/{}/*", remote_name))
+        .context("Failed to hide remote branches")?;
+    commit_summaries(repo, revwalk)
+}
+
+/// Lists `short-id summary` for every commit `revwalk` yields.
+fn commit_summaries(repo: &Repository, revwalk: git2::Revwalk) -> Result<Vec<String>> {
+    let mut commits = Vec::new();
+    for oid in revwalk {
+        let oid = oid.context("Failed to iterate revwalk")?;
+        let commit = repo
+            .find_commit(oid)
+            .with_context(|| format!("Failed to find commit '{}'", oid))?;
+        commits.push(format!(
+            "{} {}",
+            &oid.to_string()[..7],
+            commit.summary().unwrap_or("")
+        ));
+    }
+
+    Ok(commits)
+}
+
+/// Saves the working tree and index to a new stash entry.
+pub fn stash_save(repo: &mut Repository, message: Option<&str>) -> Result<()> {
+    let signature = repo
+        .signature()
+        .context("Failed to get repository signature")?;
+
+    repo.stash_save(&signature, message.unwrap_or(""), None)
+        .map_err(|e| match e.code() {
+            ErrorCode::NotFound => anyhow::anyhow!("No local changes to stash."),
+            _ => anyhow::Error::new(e).context("Failed to save stash"),
+        })?;
+
+    Ok(())
+}
+
+/// Applies the stash at `index` and removes it from the stash list.
+pub fn stash_pop(repo: &mut Repository, index: usize) -> Result<()> {
+    check_stash_conflicts(repo, index)?;
+
+    repo.stash_pop(index, None)
+        .with_context(|| format!("Failed to pop stash@{{{}}}", index))?;
+
+    Ok(())
+}
+
+/// Applies the stash at `index`, keeping it in the stash list.
+pub fn stash_apply(repo: &mut Repository, index: usize) -> Result<()> {
+    check_stash_conflicts(repo, index)?;
+
+    repo.stash_apply(index, None)
+        .with_context(|| format!("Failed to apply stash@{{{}}}", index))?;
+
+    Ok(())
+}
+
+/// Removes the stash at `index` without applying it.
+pub fn stash_drop(repo: &mut Repository, index: usize) -> Result<()> {
+    repo.stash_drop(index)
+        .with_context(|| format!("Failed to drop stash@{{{}}}", index))?;
+
+    Ok(())
+}
+
+/// One entry of the stash list.
+pub struct StashEntry {
+    pub index: usize,
+    /// Branch the stash was saved on, when its message names one.
+    pub branch: Option<String>,
+    pub message: String,
+}
+
+/// Lists stash entries, newest first.
+pub fn stash_list(repo: &mut Repository) -> Result<Vec<StashEntry>> {
+    let mut stashes = Vec::new();
+    repo.stash_foreach(|index, message, _oid| {
+        // Messages read "WIP on <branch>: <commit>" or "On <branch>: <message>".
+        let (branch, message) = match message
+            .strip_prefix("WIP on ")
+            .or_else(|| message.strip_prefix("On "))
+            .and_then(|rest| rest.split_once(": "))
+        {
+            Some((branch, rest)) => (Some(branch.to_string()), rest.to_string()),
+            None => (None, message.to_string()),
+        };
+        stashes.push(StashEntry {
+            index,
+            branch,
+            message,
+        });
+        true
+    })
+    .context("Failed to list stashes")?;
+
+    Ok(stashes)
+}
+
+/// Returns the changes saved in the stash at `index` as unified diff lines.
+pub fn stash_diff(repo: &mut Repository, index: usize) -> Result<Vec<DiffLine>> {
+    let stash_oid = find_stash(repo, index)?;
+    let diff = stash_tree_diff(repo, stash_oid)?;
+
+    let mut lines = Vec::new();
+    collect_diff_lines(&diff, false, &mut lines)?;
+    Ok(lines)
+}
+
+/// Looks up the commit of the stash at `index`.
+fn find_stash(repo: &mut Repository, index: usize) -> Result<Oid> {
+    let mut stash_oid = None;
+    repo.stash_foreach(|i, _message, oid| {
+        if i == index {
+            stash_oid = Some(*oid);
+        }
+        true
+    })
+    .context("Failed to list stashes")?;
+    stash_oid.with_context(|| format!("stash@{{{}}} not found.", index))
+}
+
+/// Diffs a stash commit against the commit it was saved on top of.
+fn stash_tree_diff(repo: &Repository, stash_oid: Oid) -> Result<git2::Diff<'_>> {
+    let stash_commit = repo
+        .find_commit(stash_oid)
+        .context("Failed to find stash commit")?;
+    let base_commit = stash_commit
+        .parent(0)
+        .context("Failed to find stash base commit")?;
+    repo.diff_tree_to_tree(
+        Some(
+            &base_commit
+                .tree()
+                .context("Failed to get stash base tree")?,
+        ),
+        Some(&stash_commit.tree().context("Failed to get stash tree")?),
+        None,
+    )
+    .context("Failed to diff stash")
+}
+
+/// Refuses to apply a stash whose files also have uncommitted changes in the
+/// working tree, since applying it would clobber or conflict with them.
+fn check_stash_conflicts(repo: &mut Repository, index: usize) -> Result<()> {
+    let stash_oid = find_stash(repo, index)?;
+    let diff = stash_tree_diff(repo, stash_oid)?;
+    let stashed: Vec<String> = diff
+        .deltas()
+        .filter_map(|delta| delta.new_file().path())
+        .map(|path| path.display().to_string())
+        .collect();
+
+    let statuses = repo
+        .statuses(None)
+        .context("Failed to get working tree status")?;
+    let conflicting: Vec<String> = statuses
+        .iter()
+        .filter(|entry| entry.status() != Status::CURRENT && !entry.status().is_ignored())
+        .filter_map(|entry| entry.path().map(str::to_string))
+        .filter(|path| stashed.contains(path))
+        .collect();
+
+    if !conflicting.is_empty() {
+        anyhow::bail!(
+            "Local changes to {} would be overwritten by stash@{{{}}}. Commit or stash them first.",
+            conflicting.join(", "),
+            index
+        );
+    }
+
+    Ok(())
+}
+
+/// Classifies a line of a unified diff for rendering.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DiffLineKind {
+    FileHeader,
+    HunkHeader,
+    Added,
+    Removed,
+    Context,
+}
+
+/// A single line of a unified diff.
+#[derive(Clone, Debug)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub content: String,
+    /// Whether the line belongs to the staged (HEAD to index) part of the diff.
+    pub staged: bool,
+    /// Byte ranges of `content` that differ from the paired line, filled in
+    /// by `highlight_words`.
+    pub highlights: Vec<(usize, usize)>,
+}
+
+/// Token pairs compared at most when diffing a line pair; longer lines are
+/// left without word highlights.
+const WORD_DIFF_LIMIT: usize = 250_000;
+
+/// Pairs each run of removed lines with the added lines that follow it and
+/// marks the words that changed between the two sides of each pair.
+pub fn highlight_words(lines: &mut [DiffLine]) {
+    for line in lines.iter_mut() {
+        line.highlights.clear();
+    }
+    let mut i = 0;
+    while i < lines.len() {
+        let removed = lines[i..]
+            .iter()
+            .take_while(|line| line.kind == DiffLineKind::Removed)
+            .count();
+        if removed == 0 {
+            i += 1;
+            continue;
+        }
+        let added = lines[i + removed..]
+            .iter()
+            .take_while(|line| line.kind == DiffLineKind::Added)
+            .count();
+        for pair in 0..removed.min(added) {
+            let (old, new) = lines.split_at_mut(i + removed + pair);
+            word_diff(&mut old[i + pair], &mut new[0]);
+        }
+        i += removed + added;
+    }
+}
+
+/// Highlights the tokens of `old` and `new` outside their longest common
+/// subsequence, skipping the leading `-`/`+`.
+fn word_diff(old: &mut DiffLine, new: &mut DiffLine) {
+    let old_tokens = tokenize(&old.content, 1);
+    let new_tokens = tokenize(&new.content, 1);
+    if old_tokens.len() * new_tokens.len() > WORD_DIFF_LIMIT {
+        return;
+    }
+    let old_text = |&(start, end): &(usize, usize)| &old.content[start..end];
+    let new_text = |&(start, end): &(usize, usize)| &new.content[start..end];
+
+    // lcs[i][j] is the common subsequence length of old_tokens[i..] and new_tokens[j..].
+    let mut lcs = vec![vec![0usize; new_tokens.len() + 1]; old_tokens.len() + 1];
+    for i in (0..old_tokens.len()).rev() {
+        for j in (0..new_tokens.len()).rev() {
+            lcs[i][j] = if old_text(&old_tokens[i]) == new_text(&new_tokens[j]) {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut old_changed, mut new_changed) = (Vec::new(), Vec::new());
+    let (mut i, mut j) = (0, 0);
+    while i < old_tokens.len() || j < new_tokens.len() {
+        if i < old_tokens.len()
+            && j < new_tokens.len()
+            && old_text(&old_tokens[i]) == new_text(&new_tokens[j])
+        {
+            i += 1;
+            j += 1;
+        } else if j == new_tokens.len() || (i < old_tokens.len() && lcs[i + 1][j] >= lcs[i][j + 1])
+        {
+            old_changed.push(old_tokens[i]);
+            i += 1;
+        } else {
+            new_changed.push(new_tokens[j]);
+            j += 1;
+        }
+    }
+    old.highlights = merge_ranges(old_changed);
+    new.highlights = merge_ranges(new_changed);
+}
+
+/// Splits `text` from byte `start` into words, runs of whitespace and single
+/// other characters, as byte ranges.
+fn tokenize(text: &str, start: usize) -> Vec<(usize, usize)> {
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            0
+        } else if c.is_whitespace() {
+            1
+        } else {
+            2
+        }
+    };
+    let mut tokens: Vec<(usize, usize)> = Vec::new();
+    let mut previous = None;
+    for (offset, c) in text.get(start..).unwrap_or("").char_indices() {
+        let (begin, end) = (start + offset, start + offset + c.len_utf8());
+        match tokens.last_mut() {
+            Some(last) if previous == Some(class(c)) && class(c) != 2 => last.1 = end,
+            _ => tokens.push((begin, end)),
+        }
+        previous = Some(class(c));
+    }
+    tokens
+}
+
+/// Joins adjacent ranges so a changed phrase is highlighted as one span.
+fn merge_ranges(ranges: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if last.1 == start => last.1 = end,
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Returns the staged and unstaged changes to `path` as unified diff lines.
+pub fn file_diff(repo: &Repository, path: &str) -> Result<Vec<DiffLine>> {
+    let head_tree = match repo.head() {
+        Ok(head) => Some(head.peel_to_tree().context("Failed to peel HEAD to tree")?),
+        Err(_) => None,
+    };
+
+    let mut opts = path_diff_options(path);
+    let staged = repo
+        .diff_tree_to_index(head_tree.as_ref(), None, Some(&mut opts))
+        .context("Failed to diff HEAD against index")?;
+    let unstaged = repo
+        .diff_index_to_workdir(None, Some(&mut opts))
+        .context("Failed to diff index against working tree")?;
+
+    let mut lines = Vec::new();
+    collect_diff_lines(&staged, true, &mut lines)?;
+    collect_diff_lines(&unstaged, false, &mut lines)?;
+    Ok(lines)
+}
+
+/// Returns the changes between the trees of commits `a` and `b` as unified
+/// diff lines, `a` being the old side.
+pub fn diff_commits(repo: &Repository, a: &str, b: &str) -> Result<Vec<DiffLine>> {
+    let tree_of = |rev: &str| {
+        repo.revparse_single(rev)
+            .and_then(|object| object.peel_to_tree())
+            .with_context(|| format!("Failed to find the tree of '{}'", rev))
+    };
+    let mut diff = repo
+        .diff_tree_to_tree(Some(&tree_of(a)?), Some(&tree_of(b)?), None)
+        .context("Failed to diff commits")?;
+    diff.find_similar(None)
+        .context("Failed to detect renamed files")?;
+
+    let mut lines = Vec::new();
+    collect_diff_lines(&diff, false, &mut lines)?;
+    Ok(lines)
+}
+
+/// Options limiting a diff to `path`, untracked content included. Hunk
+/// indexes passed to `stage_hunk`/`unstage_hunk` count hunks in diffs built
+/// with these options, in the order `file_diff` lists them.
+fn path_diff_options(path: &str) -> DiffOptions {
+    let mut opts = DiffOptions::new();
+    opts.pathspec(path)
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .show_untracked_content(true);
+    opts
+}
+
+/// Stages the `hunk_index`th unstaged hunk of `path`, leaving the rest of
+/// the working tree changes unstaged.
+pub fn stage_hunk(repo: &Repository, path: &str, hunk_index: usize) -> Result<()> {
+    let mut opts = path_diff_options(path);
+    let diff = repo
+        .diff_index_to_workdir(None, Some(&mut opts))
+        .context("Failed to diff index against working tree")?;
+
+    apply_hunk(repo, &diff, hunk_index)
+        .with_context(|| format!("Failed to stage hunk {} of '{}'", hunk_index + 1, path))
+}
+
+/// Unstages the `hunk_index`th staged hunk of `path` by applying it in
+/// reverse to the index. The working tree is left untouched.
+pub fn unstage_hunk(repo: &Repository, path: &str, hunk_index: usize) -> Result<()> {
+    let head_tree = match repo.head() {
+        Ok(head) => Some(head.peel_to_tree().context("Failed to peel HEAD to tree")?),
+        Err(_) => None,
+    };
+
+    // Reversed, the diff goes from the index back to HEAD, so its old side
+    // matches what is currently staged.
+    let mut opts = path_diff_options(path);
+    opts.reverse(true);
+    let diff = repo
+        .diff_tree_to_index(head_tree.as_ref(), None, Some(&mut opts))
+        .context("Failed to diff HEAD against index")?;
+
+    apply_hunk(repo, &diff, hunk_index)
+        .with_context(|| format!("Failed to unstage hunk {} of '{}'", hunk_index + 1, path))
+}
+
+/// Applies only the `hunk_index`th hunk of `diff` to the index.
+fn apply_hunk(repo: &Repository, diff: &git2::Diff, hunk_index: usize) -> Result<()> {
+    let mut hunk_count = 0;
+    let mut delta = None;
+    for i in 0..diff.deltas().len() {
+        let hunks = git2::Patch::from_diff(diff, i)?.map_or(0, |patch| patch.num_hunks());
+        if delta.is_none() && hunk_index < hunk_count + hunks {
+            delta = diff.get_delta(i);
+        }
+        hunk_count += hunks;
+    }
+    let Some(delta) = delta else {
+        anyhow::bail!("There is no hunk {} (found {})", hunk_index + 1, hunk_count);
+    };
+
+    // libgit2 cannot apply a patch that creates a file in the index, but a
+    // new file is a single hunk, so staging it means adding the whole file.
+    if delta.status() == Delta::Untracked {
+        let path = delta.new_file().path().context("Diff entry has no path")?;
+        let mut index = repo_index(repo)?;
+        index.add_path(path)?;
+        index.write().context("Failed to write to index")?;
+        return Ok(());
+    }
+
+    // The callback sees every hunk in order; keep only the selected one.
+    let mut seen = 0;
+    let mut apply_opts = git2::ApplyOptions::new();
+    apply_opts.hunk_callback(|_hunk| {
+        let keep = seen == hunk_index;
+        seen += 1;
+        keep
+    });
+    repo.apply(diff, git2::ApplyLocation::Index, Some(&mut apply_opts))?;
+
+    Ok(())
+}
+
+/// Appends the patch text of `diff` to `lines`.
+fn collect_diff_lines(diff: &git2::Diff, staged: bool, lines: &mut Vec<DiffLine>) -> Result<()> {
+    diff.print(DiffFormat::Patch, |_delta, _hunk, line| {
+        let kind = match line.origin() {
+            '+' | '>' => DiffLineKind::Added,
+            '-' | '<' => DiffLineKind::Removed,
+            'F' => DiffLineKind::FileHeader,
+            'H' => DiffLineKind::HunkHeader,
+            _ => DiffLineKind::Context,
+        };
+        let text = String::from_utf8_lossy(line.content());
+        let prefix = match line.origin() {
+            '+' | '-' | ' ' => line.origin().to_string(),
+            _ => String::new(),
+        };
+        // File headers arrive as one multi-line chunk.
+        for text_line in text.trim_end_matches('\n').split('\n') {
+            lines.push(DiffLine {
+                kind,
+                content: format!("{}{}", prefix, text_line),
+                staged,
+                highlights: Vec::new(),
+            });
+        }
+        true
+    })
+    .context("Failed to render diff")?;
+
+    Ok(())
+}
+
+/// Creates a tag at `target` (HEAD when `None`). A `message` makes it an
+/// annotated tag signed with the repository signature; otherwise it is lightweight.
+pub fn create_tag(
+    repo: &Repository,
+    name: &str,
+    message: Option<&str>,
+    target: Option<&str>,
+) -> Result<()> {
+    if repo.refname_to_id(&format!("refs/tags/{}", name)).is_ok() {
+        anyhow::bail!("Tag '{}' already exists.", name);
+    }
+
+    let target = target.unwrap_or("HEAD");
+    let object = repo
+        .revparse_single(target)
+        .with_context(|| format!("Failed to resolve '{}'", target))?;
+
+    match message {
+        Some(message) => {
+            let signature = repo
+                .signature()
+                .context("Failed to get repository signature")?;
+            repo.tag(name, &object, &signature, message, false)
+                .with_context(|| format!("Failed to create tag '{}'", name))?;
+        }
+        None => {
+            repo.tag_lightweight(name, &object, false)
+                .with_context(|| format!("Failed to create tag '{}'", name))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Lists the names of all tags in the repository.
+pub fn list_tags(repo: &Repository) -> Result<Vec<String>> {
+    let tags = repo.tag_names(None).context("Failed to list tags")?;
+    Ok(tags.iter().flatten().map(str::to_string).collect())
+}
+
+/// How far `reset` rewinds: the branch only, the index too, or everything.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ResetMode {
+    Soft,
+    Mixed,
+    Hard,
+}
+
+impl From<ResetMode> for ResetType {
+    fn from(mode: ResetMode) -> ResetType {
+        match mode {
+            ResetMode::Soft => ResetType::Soft,
+            ResetMode::Mixed => ResetType::Mixed,
+            ResetMode::Hard => ResetType::Hard,
+        }
+    }
+}
+
+/// Resets the current branch to `target` (any revision `revparse` understands).
+pub fn reset(repo: &Repository, target: &str, mode: ResetMode) -> Result<()> {
+    let object = repo
+        .revparse_single(target)
+        .with_context(|| format!("Failed to resolve '{}'", target))?;
+
+    repo.reset(&object, mode.into(), None)
+        .with_context(|| format!("Failed to reset to '{}'", target))?;
+
+    Ok(())
+}
+
+/// Undoes the last commit on the current branch, keeping its changes staged.
+/// Undoing the root commit leaves the branch unborn. Returns the undone
+/// commit's summary.
+pub fn undo_last_commit(repo: &Repository) -> Result<String> {
+    let mut head = repo.head().context("There is no commit to undo.")?;
+    let commit = head
+        .peel_to_commit()
+        .context("Failed to peel HEAD to commit")?;
+    let summary = commit.summary().unwrap_or("").to_string();
+
+    match commit.parent(0) {
+        Ok(parent) => {
+            repo.reset(parent.as_object(), ResetType::Soft, None)
+                .context("Failed to reset to the parent commit")?;
+        }
+        Err(_) if head.is_branch() => {
+            // Without a branch ref HEAD is unborn again; the index is kept.
+            head.delete().context("Failed to delete the branch ref")?;
+        }
+        Err(_) => anyhow::bail!("Cannot undo the root commit with a detached HEAD."),
+    }
+
+    Ok(summary)
+}
+
+/// How many commits `commit_id` and the ones after it make up, following
+/// first parents back from HEAD.
+pub fn commits_since(repo: &Repository, commit_id: &str) -> Result<usize> {
+    let target =
+        Oid::from_str(commit_id).with_context(|| format!("Invalid commit id '{}'", commit_id))?;
+    let mut commit = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .context("There are no commits yet.")?;
+    let mut count = 1;
+    while commit.id() != target {
+        commit = commit
+            .parent(0)
+            .map_err(|_| anyhow::anyhow!("{:.7} is not on the current branch.", commit_id))?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Replaces the last `n` commits on the current branch with one commit of
+/// their combined tree, authored like the oldest of them. Staged and
+/// unstaged changes are left alone. Returns the new commit's id.
+pub fn squash_last(repo: &Repository, n: usize, message: &str) -> Result<Oid> {
+    if n < 2 {
+        anyhow::bail!("Squashing needs at least two commits.");
+    }
+    if repo.state() != RepositoryState::Clean {
+        anyhow::bail!("Finish the merge, rebase or revert in progress first.");
+    }
+    let head = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .context("There are no commits to squash.")?;
+
+    let mut oldest = head.clone();
+    for count in 1..=n {
+        if oldest.parent_count() > 1 {
+            anyhow::bail!("Cannot squash across the merge commit {:.7}.", oldest.id());
+        }
+        if count == n {
+            break;
+        }
+        oldest = oldest
+            .parent(0)
+            .map_err(|_| anyhow::anyhow!("The branch only has {} commit(s), not {}.", count, n))?;
+    }
+
+    let parents: Vec<git2::Commit> = oldest.parents().collect();
+    let parents: Vec<&git2::Commit> = parents.iter().collect();
+    let committer = repo
+        .signature()
+        .context("Failed to get repository signature")?;
+    let tree = head.tree().context("Failed to read the HEAD tree")?;
+    let id = repo
+        .commit(None, &oldest.author(), &committer, message, &tree, &parents)
+        .context("Failed to create the squashed commit")?;
+
+    // A soft reset moves the branch without touching the index or files.
+    let squashed = repo.find_commit(id).context("Failed to find new commit")?;
+    repo.reset(squashed.as_object(), ResetType::Soft, None)
+        .context("Failed to move the branch to the squashed commit")?;
+
+    Ok(id)
+}
+
+/// Creates a new commit that undoes the changes introduced by `commit_id`.
+pub fn revert_commit(repo: &Repository, commit_id: &str) -> Result<()> {
+    let commit = repo
+        .revparse_single(commit_id)
+        .and_then(|object| object.peel_to_commit())
+        .with_context(|| format!("Failed to find commit '{}'", commit_id))?;
+
+    if commit.parent_count() > 1 {
+        anyhow::bail!(
+            "Commit {} is a merge; reverting it requires choosing a mainline parent (-m), which is not supported.",
+            &commit.id().to_string()[..7]
+        );
+    }
+
+    repo.revert(&commit, None)
+        .with_context(|| format!("Failed to revert commit '{}'", commit_id))?;
+
+    let mut index = repo_index(repo)?;
+    if index.has_conflicts() {
+        anyhow::bail!(
+            "Revert produced conflicts in: {}. Resolve them and continue.",
+            conflicted_paths(&index)?.join(", ")
+        );
+    }
+
+    let tree_id = index.write_tree().context("Failed to write tree")?;
+    let tree = repo
+        .find_tree(tree_id)
+        .context("Failed to find written tree")?;
+    let signature = repo
+        .signature()
+        .context("Failed to get repository signature")?;
+    let head_commit = repo
+        .head()
+        .context("Failed to get HEAD")?
+        .peel_to_commit()
+        .context("Failed to peel HEAD to commit")?;
+
+    let message = format!(
+        "Revert \"{}\"\n\nThis reverts commit {}.\n",
+        commit.summary().unwrap_or(""),
+        commit.id()
+    );
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        &message,
+        &tree,
+        &[&head_commit],
+    )
+    .context("Failed to create revert commit")?;
+
+    repo.cleanup_state()
+        .context("Failed to clean up revert state")?;
+
+    Ok(())
+}
+
+/// Rebases the current branch onto the local branch `upstream`, committing
+/// each step. Stops at the first conflicting commit and leaves the rebase in
+/// progress so it can be resolved or aborted with `rebase_abort`.
+pub fn rebase_onto(repo: &Repository, upstream: &str) -> Result<()> {
+    let upstream_id = repo
+        .refname_to_id(&format!("refs/heads/{}", upstream))
+        .with_context(|| format!("Branch '{}' not found.", upstream))?;
+    let upstream_commit = repo
+        .find_annotated_commit(upstream_id)
+        .context("Failed to find annotated commit for upstream")?;
+    rebase_onto_commit(repo, &upstream_commit, upstream)
+}
+
+/// Rebases the current branch onto `upstream_commit`, naming it `upstream`
+/// in the conflict message.
+fn rebase_onto_commit(
+    repo: &Repository,
+    upstream_commit: &AnnotatedCommit,
+    upstream: &str,
+) -> Result<()> {
+    let head = repo.head().context("Failed to get HEAD")?;
+    let head_commit = repo
+        .reference_to_annotated_commit(&head)
+        .context("Failed to find annotated commit for HEAD")?;
+
+    // Checked first so a missing identity doesn't leave a rebase started.
+    let signature = repo
+        .signature()
+        .context("Failed to get repository signature")?;
+    let mut rebase = repo
+        .rebase(Some(&head_commit), Some(upstream_commit), None, None)
+        .context("Failed to start rebase")?;
+
+    run_rebase(repo, &mut rebase, &signature, &format!("'{}'", upstream))
+}
+
+/// Commits the rebase step that stopped for conflicts, now resolved, and
+/// applies the remaining steps.
+fn rebase_continue(repo: &Repository) -> Result<()> {
+    let signature = repo
+        .signature()
+        .context("Failed to get repository signature")?;
+    let mut rebase = repo.open_rebase(None).context("No rebase in progress.")?;
+    commit_rebase_step(&mut rebase, &signature)?;
+    run_rebase(repo, &mut rebase, &signature, "the new base")
+}
+
+/// Applies and commits the remaining steps of `rebase`, stopping at the
+/// first one that conflicts with `onto`.
+fn run_rebase(
+    repo: &Repository,
+    rebase: &mut git2::Rebase,
+    signature: &Signature,
+    onto: &str,
+) -> Result<()> {
+    while let Some(operation) = rebase.next() {
+        let operation = operation.context("Failed to apply rebase step")?;
+        if repo_index(repo)?.has_conflicts() {
+            anyhow::bail!(
+                "Rebase stopped: commit {} conflicts with {}. Resolve the conflicts and continue, or abort the rebase.",
+                &operation.id().to_string()[..7],
+                onto
+            );
+        }
+        commit_rebase_step(rebase, signature)?;
+    }
+
+    rebase
+        .finish(Some(signature))
+        .context("Failed to finish rebase")?;
+
+    Ok(())
+}
+
+fn commit_rebase_step(rebase: &mut git2::Rebase, signature: &Signature) -> Result<()> {
+    match rebase.commit(None, signature, None) {
+        Ok(_) => Ok(()),
+        // The change is already upstream; git skips such commits too.
+        Err(e) if e.code() == ErrorCode::Applied => Ok(()),
+        Err(e) => Err(e).context("Failed to commit rebase step"),
+    }
+}
+
+/// Aborts an in-progress rebase, restoring the original branch.
+pub fn rebase_abort(repo: &Repository) -> Result<()> {
+    let mut rebase = repo.open_rebase(None).context("No rebase in progress.")?;
+    rebase.abort().context("Failed to abort rebase")?;
+
+    Ok(())
+}
+
+/// What an interactive rebase does with one commit.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RebaseAction {
+    Pick,
+    /// Pick with the message in the step.
+    Reword,
+    /// Meld into the commit before, keeping both messages.
+    Squash,
+    /// Meld into the commit before, keeping only its message.
+    Fixup,
+    Drop,
+}
+
+impl RebaseAction {
+    pub fn name(self) -> &'static str {
+        match self {
+            RebaseAction::Pick => "pick",
+            RebaseAction::Reword => "reword",
+            RebaseAction::Squash => "squash",
+            RebaseAction::Fixup => "fixup",
+            RebaseAction::Drop => "drop",
+        }
+    }
+}
+
+/// One line of an interactive rebase's todo list.
+pub struct RebaseStep {
+    pub id: Oid,
+    pub summary: String,
+    pub action: RebaseAction,
+    /// The full message; only used as the new message by `Reword`.
+    pub message: String,
+}
+
+/// The commits after `base` up to HEAD, oldest first, each picked as is.
+pub fn rebase_todo(repo: &Repository, base: &str) -> Result<Vec<RebaseStep>> {
+    let base = repo
+        .revparse_single(base)
+        .and_then(|object| object.peel_to_commit())
+        .with_context(|| format!("Failed to resolve '{}'", base))?;
+    let mut commit = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .context("There are no commits yet.")?;
+
+    let mut steps = Vec::new();
+    while commit.id() != base.id() {
+        if commit.parent_count() > 1 {
+            anyhow::bail!("Cannot rebase across the merge commit {:.7}.", commit.id());
+        }
+        steps.push(RebaseStep {
+            id: commit.id(),
+            summary: commit.summary().unwrap_or("").to_string(),
+            action: RebaseAction::Pick,
+            message: commit.message().unwrap_or("").to_string(),
+        });
+        commit = commit
+            .parent(0)
+            .map_err(|_| anyhow::anyhow!("{:.7} is not on the current branch.", base.id()))?;
+    }
+    if steps.is_empty() {
+        anyhow::bail!("Nothing to rebase: select a commit older than HEAD.");
+    }
+    steps.reverse();
+    Ok(steps)
+}
+
+/// Rejects a todo that would drop every commit, which is a hard reset to the
+/// base rather than a rebase, or that starts by melding a commit into
+/// nothing.
+pub fn check_rebase_todo(steps: &[RebaseStep]) -> Result<()> {
+    let Some(first) = steps.iter().find(|step| step.action != RebaseAction::Drop) else {
+        anyhow::bail!(
+            "Every commit is dropped. Pick at least one, or reset to the base from the log instead."
+        );
+    };
+    if matches!(first.action, RebaseAction::Squash | RebaseAction::Fixup) {
+        anyhow::bail!(
+            "Cannot {} {:.7}: there is no earlier commit to meld it into. Pick it, or move it after a picked commit.",
+            first.action.name(),
+            first.id
+        );
+    }
+    Ok(())
+}
+
+/// Replays `steps` onto `base` and moves the current branch to the result.
+/// Every commit is built before anything is changed, so a conflicting step
+/// leaves the branch and work tree as they were. Leading picks that keep
+/// their parent are reused rather than rewritten. Returns the number of
+/// commits on top of `base` afterwards.
+pub fn interactive_rebase(repo: &Repository, base: &str, steps: &[RebaseStep]) -> Result<usize> {
+    if repo.state() != RepositoryState::Clean {
+        anyhow::bail!("Finish the merge, rebase or revert in progress first.");
+    }
+    if has_uncommitted_changes(repo)? {
+        anyhow::bail!("Commit or stash your changes before rebasing.");
+    }
+    check_rebase_todo(steps)?;
+    let committer = repo
+        .signature()
+        .context("Failed to get repository signature")?;
+    let mut tip = repo
+        .revparse_single(base)
+        .and_then(|object| object.peel_to_commit())
+        .with_context(|| format!("Failed to resolve '{}'", base))?;
+
+    let mut count = 0;
+    for step in steps {
+        if step.action == RebaseAction::Drop {
+            continue;
+        }
+        let commit = repo
+            .find_commit(step.id)
+            .with_context(|| format!("Failed to find commit {}", step.id))?;
+        if step.action == RebaseAction::Pick && commit.parent_id(0).ok() == Some(tip.id()) {
+            count += 1;
+            tip = commit;
+            continue;
+        }
+        let mut index = repo
+            .cherrypick_commit(&commit, &tip, 0, None)
+            .with_context(|| format!("Failed to apply {:.7}", step.id))?;
+        if index.has_conflicts() {
+            anyhow::bail!(
+                "{:.7} '{}' conflicts with the commits before it. Nothing was changed; reorder or drop commits and try again.",
+                step.id,
+                step.summary
+            );
+        }
+        let tree_id = index.write_tree_to(repo).context("Failed to write tree")?;
+        let tree = repo.find_tree(tree_id).context("Failed to find tree")?;
+
+        let id = match step.action {
+            RebaseAction::Squash | RebaseAction::Fixup => {
+                let message = match step.action {
+                    RebaseAction::Squash => format!(
+                        "{}\n\n{}",
+                        tip.message().unwrap_or("").trim_end(),
+                        commit.message().unwrap_or("")
+                    ),
+                    _ => tip.message().unwrap_or("").to_string(),
+                };
+                tip.amend(
+                    None,
+                    None,
+                    Some(&committer),
+                    None,
+                    Some(&message),
+                    Some(&tree),
+                )
+            }
+            _ => {
+                let message = match step.action {
+                    RebaseAction::Reword => step.message.as_str(),
+                    _ => commit.message().unwrap_or(""),
+                };
+                count += 1;
+                repo.commit(None, &commit.author(), &committer, message, &tree, &[&tip])
+            }
+        }
+        .with_context(|| format!("Failed to commit {:.7}", step.id))?;
+        tip = repo.find_commit(id).context("Failed to find new commit")?;
+    }
+
+    // Tracked files are clean, and a safe checkout refuses to replace
+    // untracked files that the new history adds.
+    repo.checkout_tree(
+        tip.as_object(),
+        Some(git2::build::CheckoutBuilder::default().safe()),
+    )
+    .context("Failed to check out the rebased commits; move untracked files they would overwrite out of the way")?;
+    let head = repo.head().context("Failed to read HEAD")?;
+    match head.name().filter(|_| head.is_branch()) {
+        Some(name) => {
+            repo.reference(name, tip.id(), true, "rebase -i (finish)")
+                .context("Failed to move the branch to the rebased commits")?;
+        }
+        None => repo
+            .set_head_detached(tip.id())
+            .context("Failed to move HEAD to the rebased commits")?,
+    }
+
+    Ok(count)
+}
+
+/// One entry of a reference's reflog.
+pub struct ReflogEntry {
+    pub oid: String,
+    pub committer: String,
+    pub message: String,
+}
+
+/// Reads the reflog of `reference` (e.g. "HEAD"), newest entry first.
+pub fn read_reflog(repo: &Repository, reference: &str) -> Result<Vec<ReflogEntry>> {
+    let reflog = repo
+        .reflog(reference)
+        .with_context(|| format!("Failed to read reflog for '{}'", reference))?;
+
+    Ok(reflog
+        .iter()
+        .map(|entry| ReflogEntry {
+            oid: entry.id_new().to_string(),
+            committer: entry.committer().name().unwrap_or("Unknown").to_string(),
+            message: entry.message().unwrap_or("").to_string(),
+        })
+        .collect())
+}
+
+/// A line of a file annotated with the commit that last changed it.
+pub struct BlameLine {
+    pub commit: String,
+    pub author: String,
+    pub date: String,
+    pub content: String,
+}
+
+/// Blames `path` as of HEAD, returning one entry per line of the file.
+pub fn blame_file(repo: &Repository, path: &str) -> Result<Vec<BlameLine>> {
+    let tree = repo
+        .head()
+        .context("Failed to get HEAD")?
+        .peel_to_tree()
+        .context("Failed to peel HEAD to tree")?;
+    let blob = tree
+        .get_path(std::path::Path::new(path))
+        .with_context(|| format!("'{}' is not tracked at HEAD", path))?
+        .to_object(repo)
+        .context("Failed to load file")?
+        .peel_to_blob()
+        .with_context(|| format!("'{}' is not a file", path))?;
+
+    if blob.is_binary() {
+        anyhow::bail!("binary file — blame unavailable");
+    }
+
+    let blame = repo
+        .blame_file(std::path::Path::new(path), None)
+        .with_context(|| format!("Failed to blame '{}'", path))?;
+
+    let content = String::from_utf8_lossy(blob.content());
+    let lines = content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| match blame.get_line(i + 1) {
+            Some(hunk) => {
+                let signature = hunk.final_signature();
+                let date = chrono::DateTime::from_timestamp(signature.when().seconds(), 0)
+                    .map(|dt| dt.format("%Y-%m-%d").to_string())
+                    .unwrap_or_default();
+                BlameLine {
+                    commit: hunk.final_commit_id().to_string()[..7].to_string(),
+                    author: signature.name().unwrap_or("Unknown").to_string(),
+                    date,
+                    content: line.to_string(),
+                }
+            }
+            None => BlameLine {
+                commit: "-------".to_string(),
+                author: String::new(),
+                date: String::new(),
+                content: line.to_string(),
+            },
+        })
+        .collect();
+
+    Ok(lines)
+}
+
+/// Clones `url` into `dest`, which must not exist or be an empty directory.
+pub fn clone_repo(url: &str, dest: &str, callbacks: RemoteCallbacks) -> Result<()> {
+    let dest_path = std::path::Path::new(dest);
+    if dest_path.exists() {
+        let mut entries = std::fs::read_dir(dest_path)
+            .with_context(|| format!("'{}' exists and is not a directory.", dest))?;
+        if entries.next().is_some() {
+            anyhow::bail!("Destination '{}' already exists and is not empty.", dest);
+        }
+    }
+
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(callbacks);
+
+    git2::build::RepoBuilder::new()
+        .fetch_options(fetch_options)
+        .clone(url, dest_path)
+        .map_err(|e| remote_error(e, url))
+        .with_context(|| format!("Failed to clone '{}' into '{}'", url, dest))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Signature;
+    use std::{fs, path::Path};
+
+    /// Twenty numbered lines, far enough apart for two edits to make two hunks.
+    fn numbered_lines() -> Vec<String> {
+        (1..=20).map(|n| format!("line {}", n)).collect()
+    }
+
+    fn text(lines: &[String]) -> String {
+        lines.iter().map(|line| format!("{}\n", line)).collect()
+    }
+
+    /// What `file.txt` holds in the index.
+    fn staged_text(repo: &Repository) -> String {
+        let index = repo_index(repo).unwrap();
+        let entry = index.get_path(Path::new("file.txt"), 0).unwrap();
+        let blob = repo.find_blob(entry.id).unwrap();
+        String::from_utf8(blob.content().to_vec()).unwrap()
+    }
+
+    /// A repository with the numbered lines committed, then a line added at
+    /// the top and one changed at the bottom, each its own hunk.
+    fn repo_with_two_hunks(dir: &Path) -> (Repository, Vec<String>) {
+        let repo = Repository::init(dir).unwrap();
+        let lines = numbered_lines();
+        fs::write(dir.join("file.txt"), text(&lines)).unwrap();
+        {
+            let mut index = repo.index().unwrap();
+            index.add_path(Path::new("file.txt")).unwrap();
+            index.write().unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let signature = Signature::now("Test", "test@example.com").unwrap();
+            repo.commit(Some("HEAD"), &signature, &signature, "initial", &tree, &[])
+                .unwrap();
+        }
+        let mut changed = lines;
+        changed.insert(1, "inserted".to_string());
+        changed[18] = "changed".to_string();
+        fs::write(dir.join("file.txt"), text(&changed)).unwrap();
+        (repo, changed)
+    }
+
+    /// Commits `content` as `name`, or deletes it when `content` is `None`.
+    fn commit_file(repo: &Repository, name: &str, content: Option<&str>, message: &str) -> Oid {
+        let path = repo.workdir().unwrap().join(name);
+        let mut index = repo.index().unwrap();
+        match content {
+            Some(content) => {
+                fs::write(&path, content).unwrap();
+                index.add_path(Path::new(name)).unwrap();
+            }
+            None => {
+                fs::remove_file(&path).unwrap();
+                index.remove_path(Path::new(name)).unwrap();
+            }
+        }
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("Test", "test@example.com").unwrap();
+        let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap()
+    }
+
+    /// An empty repository with an identity to commit as.
+    fn init_repo(dir: &Path) -> Repository {
+        let repo = Repository::init(dir).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        repo
+    }
+
+    /// A repository whose commits `c1` to `c4` each add the file `fN`.
+    /// Returns it with the todo of the commits after `c1`.
+    fn repo_to_rebase(dir: &Path) -> (Repository, Oid, Vec<RebaseStep>) {
+        let repo = init_repo(dir);
+        let base = commit_file(&repo, "f1", Some("1\n"), "c1");
+        for n in 2..=4 {
+            commit_file(&repo, &format!("f{}", n), Some("x\n"), &format!("c{}", n));
+        }
+        let steps = rebase_todo(&repo, &base.to_string()).unwrap();
+        (repo, base, steps)
+    }
+
+    /// Subjects from HEAD down to, but not including, `base`.
+    fn subjects_since(repo: &Repository, base: Oid) -> Vec<String> {
+        let mut commit = repo.head().unwrap().peel_to_commit().unwrap();
+        let mut subjects = Vec::new();
+        while commit.id() != base {
+            subjects.push(commit.message().unwrap().to_string());
+            commit = commit.parent(0).unwrap();
+        }
+        subjects
+    }
+
+    fn head_has(repo: &Repository, name: &str) -> bool {
+        let tree = repo.head().unwrap().peel_to_tree().unwrap();
+        tree.get_path(Path::new(name)).is_ok()
+    }
+
+    /// A repository whose branch `other` is one commit ahead with `f2`,
+    /// which the checked-out branch doesn't have.
+    fn repo_with_other_branch(dir: &Path) -> Repository {
+        let repo = init_repo(dir);
+        let base = commit_file(&repo, "f1", Some("1\n"), "c1");
+        let ahead = commit_file(&repo, "f2", Some("theirs\n"), "c2");
+        repo.branch("other", &repo.find_commit(ahead).unwrap(), false)
+            .unwrap();
+        {
+            let base = repo.find_object(base, None).unwrap();
+            repo.reset(&base, ResetType::Hard, None).unwrap();
+        }
+        repo
+    }
+
+    #[test]
+    fn switching_branches_moves_head_and_the_work_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = repo_with_other_branch(dir.path());
+
+        switch_branch(&repo, "other", false).unwrap();
+        assert_eq!(repo.head().unwrap().shorthand(), Some("other"));
+        let content = fs::read_to_string(dir.path().join("f2")).unwrap();
+        assert_eq!(content, "theirs\n");
+    }
+
+    #[test]
+    fn switching_branches_keeps_untracked_files_the_branch_would_overwrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = repo_with_other_branch(dir.path());
+        let head = repo.head().unwrap().name().unwrap().to_string();
+        fs::write(dir.path().join("f2"), "mine\n").unwrap();
+
+        assert!(switch_branch(&repo, "other", false).is_err());
+        assert_eq!(fs::read_to_string(dir.path().join("f2")).unwrap(), "mine\n");
+        assert_eq!(repo.head().unwrap().name(), Some(head.as_str()));
+    }
+
+    #[test]
+    fn aborting_a_conflicted_merge_restores_head() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = repo_with_other_branch(dir.path());
+        let head = commit_file(&repo, "f2", Some("ours\n"), "c3");
+
+        assert!(merge_branch(&repo, "other").is_err());
+        assert_eq!(repo.state(), RepositoryState::Merge);
+
+        assert_eq!(abort_operation(&repo).unwrap(), "merge");
+        assert_eq!(repo.state(), RepositoryState::Clean);
+        assert_eq!(repo.head().unwrap().target(), Some(head));
+        assert!(!repo.index().unwrap().has_conflicts());
+        assert_eq!(fs::read_to_string(dir.path().join("f2")).unwrap(), "ours\n");
+        assert!(abort_operation(&repo).is_err());
+    }
+
+    #[test]
+    fn commit_all_stages_and_commits_tracked_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = repo_with_other_branch(dir.path());
+        fs::write(dir.path().join("f1"), "2\n").unwrap();
+        fs::write(dir.path().join("new"), "untracked\n").unwrap();
+
+        let staged = commit_all(&repo, |repo| commit_changes(repo, "all")).unwrap();
+        assert_eq!(staged, 1);
+        let status = repo.status_file(Path::new("f1")).unwrap();
+        assert!(status.is_empty(), "status: {:?}", status);
+        assert!(repo.status_file(Path::new("new")).unwrap().is_wt_new());
+    }
+
+    #[test]
+    fn a_failed_commit_all_leaves_nothing_staged() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = repo_with_other_branch(dir.path());
+        let head = repo.head().unwrap().target().unwrap();
+        fs::write(dir.path().join("f1"), "2\n").unwrap();
+        // A merge in progress refuses plain commits.
+        fs::write(repo.path().join("MERGE_HEAD"), format!("{}\n", head)).unwrap();
+
+        assert!(commit_all(&repo, |repo| commit_changes(repo, "all")).is_err());
+        let status = repo.status_file(Path::new("f1")).unwrap();
+        assert_eq!(status, Status::WT_MODIFIED);
+        assert_eq!(repo.head().unwrap().target(), Some(head));
+    }
+
+    #[test]
+    fn rebase_todo_check_refuses_dropping_everything_and_leading_melds() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_repo, _, mut steps) = repo_to_rebase(dir.path());
+        assert!(check_rebase_todo(&steps).is_ok());
+
+        for step in &mut steps {
+            step.action = RebaseAction::Drop;
+        }
+        assert!(check_rebase_todo(&steps).is_err());
+
+        // A meld after only dropped commits has nothing to meld into.
+        steps[1].action = RebaseAction::Fixup;
+        assert!(check_rebase_todo(&steps).is_err());
+        steps[0].action = RebaseAction::Pick;
+        assert!(check_rebase_todo(&steps).is_ok());
+    }
+
+    #[test]
+    fn rebase_reorders_and_rewords() {
+        let dir = tempfile::tempdir().unwrap();
+        let (repo, base, mut steps) = repo_to_rebase(dir.path());
+        steps.swap(1, 2);
+        steps[0].action = RebaseAction::Reword;
+        steps[0].message = "two\n".to_string();
+
+        assert_eq!(
+            interactive_rebase(&repo, &base.to_string(), &steps).unwrap(),
+            3
+        );
+        assert_eq!(subjects_since(&repo, base), ["c3", "c4", "two\n"]);
+        assert!(["f2", "f3", "f4"].iter().all(|name| head_has(&repo, name)));
+    }
+
+    #[test]
+    fn rebase_squashes_and_drops() {
+        let dir = tempfile::tempdir().unwrap();
+        let (repo, base, mut steps) = repo_to_rebase(dir.path());
+        steps[0].action = RebaseAction::Drop;
+        steps[2].action = RebaseAction::Squash;
+
+        assert_eq!(
+            interactive_rebase(&repo, &base.to_string(), &steps).unwrap(),
+            1
+        );
+        assert_eq!(subjects_since(&repo, base), ["c3\n\nc4"]);
+        assert!(!head_has(&repo, "f2") && head_has(&repo, "f4"));
+        assert!(!dir.path().join("f2").exists());
+    }
+
+    #[test]
+    fn rebase_fixup_keeps_only_the_earlier_message() {
+        let dir = tempfile::tempdir().unwrap();
+        let (repo, base, mut steps) = repo_to_rebase(dir.path());
+        steps[1].action = RebaseAction::Fixup;
+
+        assert_eq!(
+            interactive_rebase(&repo, &base.to_string(), &steps).unwrap(),
+            2
+        );
+        assert_eq!(subjects_since(&repo, base), ["c4", "c2"]);
+        assert!(head_has(&repo, "f3"));
+    }
+
+    #[test]
+    fn rebase_keeps_leading_picks_that_are_unchanged() {
+        let dir = tempfile::tempdir().unwrap();
+        let (repo, base, mut steps) = repo_to_rebase(dir.path());
+        steps[2].action = RebaseAction::Drop;
+        let c3 = steps[1].id;
+
+        interactive_rebase(&repo, &base.to_string(), &steps).unwrap();
+        assert_eq!(repo.head().unwrap().target(), Some(c3));
+    }
+
+    #[test]
+    fn rebase_stops_before_overwriting_untracked_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let (repo, base, _) = repo_to_rebase(dir.path());
+        commit_file(&repo, "f2", None, "remove f2");
+        let head = repo.head().unwrap().target();
+        // Dropping the removal brings f2 back over the untracked copy.
+        fs::write(dir.path().join("f2"), "mine\n").unwrap();
+        let mut steps = rebase_todo(&repo, &base.to_string()).unwrap();
+        steps[3].action = RebaseAction::Drop;
+
+        let error = interactive_rebase(&repo, &base.to_string(), &steps).unwrap_err();
+        assert!(error.to_string().contains("untracked"), "{:#}", error);
+        assert_eq!(fs::read_to_string(dir.path().join("f2")).unwrap(), "mine\n");
+        assert_eq!(repo.head().unwrap().target(), head);
+    }
+
+    #[test]
+    fn staging_a_later_hunk_after_an_earlier_one_follows_the_shifted_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let (repo, changed) = repo_with_two_hunks(dir.path());
+
+        stage_hunk(&repo, "file.txt", 0).unwrap();
+        let mut expected = numbered_lines();
+        expected.insert(1, "inserted".to_string());
+        assert_eq!(staged_text(&repo), text(&expected));
+
+        // The remaining hunk is now the first, a line further down.
+        stage_hunk(&repo, "file.txt", 0).unwrap();
+        assert_eq!(staged_text(&repo), text(&changed));
+        assert!(stage_hunk(&repo, "file.txt", 0).is_err());
+    }
+
+    #[test]
+    fn unstaging_the_later_hunk_keeps_the_earlier_one_staged() {
+        let dir = tempfile::tempdir().unwrap();
+        let (repo, _) = repo_with_two_hunks(dir.path());
+        stage_hunk(&repo, "file.txt", 1).unwrap();
+        stage_hunk(&repo, "file.txt", 0).unwrap();
+
+        unstage_hunk(&repo, "file.txt", 1).unwrap();
+        let mut expected = numbered_lines();
+        expected.insert(1, "inserted".to_string());
+        assert_eq!(staged_text(&repo), text(&expected));
+    }
+
+    fn diff_line(kind: DiffLineKind, content: &str) -> DiffLine {
+        DiffLine {
+            kind,
+            content: content.to_string(),
+            staged: false,
+            highlights: vec![],
+        }
+    }
+
+    /// The highlighted parts of a line.
+    fn highlighted(line: &DiffLine) -> Vec<&str> {
+        line.highlights
+            .iter()
+            .map(|&(start, end)| &line.content[start..end])
+            .collect()
+    }
+
+    #[test]
+    fn tokenize_splits_words_spaces_and_punctuation_by_byte() {
+        let text = "+héllo,  wörld()";
+        let tokens: Vec<&str> = tokenize(text, 1)
+            .iter()
+            .map(|&(start, end)| &text[start..end])
+            .collect();
+        assert_eq!(tokens, ["héllo", ",", "  ", "wörld", "(", ")"]);
+        assert_eq!(tokenize(text, 1)[0], (1, 7));
+        assert!(tokenize("+", 1).is_empty());
+    }
+
+    #[test]
+    fn word_diff_marks_only_the_changed_words() {
+        let mut lines = vec![
+            diff_line(DiffLineKind::Removed, "-let total = count + 1;"),
+            diff_line(DiffLineKind::Added, "+let total = counted + 2;"),
+        ];
+        highlight_words(&mut lines);
+        assert_eq!(highlighted(&lines[0]), ["count", "1"]);
+        assert_eq!(highlighted(&lines[1]), ["counted", "2"]);
+    }
+
+    #[test]
+    fn word_diff_follows_the_longest_common_subsequence() {
+        let mut lines = vec![
+            diff_line(DiffLineKind::Removed, "-a b c"),
+            diff_line(DiffLineKind::Added, "+a x b c"),
+        ];
+        highlight_words(&mut lines);
+        assert!(lines[0].highlights.is_empty());
+        // The inserted word and its space merge into one range.
+        assert_eq!(lines[1].highlights, [(3, 5)]);
+    }
+
+    #[test]
+    fn word_diff_ranges_fall_on_multibyte_boundaries() {
+        let mut lines = vec![
+            diff_line(DiffLineKind::Removed, "-naïve café"),
+            diff_line(DiffLineKind::Added, "+naïve cafés ✓"),
+        ];
+        highlight_words(&mut lines);
+        assert_eq!(lines[0].highlights, [(8, 13)]);
+        assert_eq!(highlighted(&lines[0]), ["café"]);
+        assert_eq!(highlighted(&lines[1]), ["cafés ✓"]);
+    }
+
+    #[test]
+    fn highlight_words_pairs_removed_lines_with_the_added_ones_after_them() {
+        let mut lines = vec![
+            diff_line(DiffLineKind::Context, " same"),
+            diff_line(DiffLineKind::Removed, "-one"),
+            diff_line(DiffLineKind::Removed, "-two"),
+            diff_line(DiffLineKind::Added, "+uno"),
+            diff_line(DiffLineKind::Context, " same"),
+            diff_line(DiffLineKind::Added, "+three"),
+        ];
+        lines[5].highlights = vec![(1, 2)];
+        highlight_words(&mut lines);
+        assert_eq!(highlighted(&lines[1]), ["one"]);
+        assert_eq!(highlighted(&lines[3]), ["uno"]);
+        // Unpaired lines have nothing to compare with, and stale ranges go.
+        for i in [0, 2, 4, 5] {
+            assert!(lines[i].highlights.is_empty(), "line {}", i);
+        }
+    }
+
+    #[test]
+    fn word_diff_skips_line_pairs_over_the_limit() {
+        // Each repetition is a word and a space: two tokens.
+        let line = |kind, sign, word: &str, words: usize| {
+            diff_line(
+                kind,
+                &format!("{}{}", sign, format!("{} ", word).repeat(words)),
+            )
+        };
+        let side = (WORD_DIFF_LIMIT as f64).sqrt() as usize / 2;
+
+        let mut lines = vec![
+            line(DiffLineKind::Removed, "-", "a", side),
+            line(DiffLineKind::Added, "+", "b", side),
+        ];
+        highlight_words(&mut lines);
+        assert!(!lines[0].highlights.is_empty());
+
+        let mut lines = vec![
+            line(DiffLineKind::Removed, "-", "a", side + 1),
+            line(DiffLineKind::Added, "+", "b", side + 1),
+        ];
+        highlight_words(&mut lines);
+        assert!(lines[0].highlights.is_empty());
+        assert!(lines[1].highlights.is_empty());
+    }
+
+    /// A repository with `f1` committed, and a patch of a later commit by
+    /// another author that edits it. HEAD is left on the first commit.
+    fn repo_with_patch(dir: &Path, out: &Path) -> (Repository, Oid, std::path::PathBuf) {
+        let repo = init_repo(dir);
+        let base = commit_file(&repo, "f1", Some(&text(&numbered_lines())), "c1");
+        let mut lines = numbered_lines();
+        lines[9] = "line ten".to_string();
+        let edit = commit_file(&repo, "f1", Some(&text(&lines)), "c2");
+        let author = Signature::new(
+            "Ada Lovelace",
+            "ada@example.com",
+            &git2::Time::new(1_700_000_000, 90),
+        )
+        .unwrap();
+        let edit = repo
+            .find_commit(edit)
+            .unwrap()
+            .amend(
+                Some("HEAD"),
+                Some(&author),
+                None,
+                None,
+                Some("Spell out line ten\n\nNumbers read badly here.\n"),
+                None,
+            )
+            .unwrap();
+        let patch = format_patch(&repo, &edit.to_string(), out).unwrap();
+        {
+            let base = repo.find_object(base, None).unwrap();
+            repo.reset(&base, ResetType::Hard, None).unwrap();
+        }
+        (repo, edit, patch)
+    }
+
+    #[test]
+    fn a_formatted_patch_applies_as_the_same_commit() {
+        let (dir, out) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let (repo, edit, patch) = repo_with_patch(dir.path(), out.path());
+        let base = repo.head().unwrap().target().unwrap();
+
+        let message = apply_patch(&repo, &patch, true).unwrap();
+        assert_eq!(
+            message.as_deref(),
+            Some("Spell out line ten\n\nNumbers read badly here.")
+        );
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let edit = repo.find_commit(edit).unwrap();
+        assert_eq!(head.parent_id(0).unwrap(), base);
+        assert_eq!(head.tree_id(), edit.tree_id());
+        assert_eq!(head.author().name(), Some("Ada Lovelace"));
+        assert_eq!(head.author().email(), Some("ada@example.com"));
+        assert_eq!(head.author().when(), edit.author().when());
+        assert_eq!(head.committer().name(), Some("Test"));
+    }
+
+    #[test]
+    fn a_patch_that_does_not_apply_changes_nothing() {
+        let (dir, out) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let (repo, _, patch) = repo_with_patch(dir.path(), out.path());
+        let mut lines = numbered_lines();
+        lines[9] = "line 10, moved on".to_string();
+        let head = commit_file(&repo, "f1", Some(&text(&lines)), "c3");
+        // An unrelated edit the failed apply must not disturb either.
+        fs::write(dir.path().join("f2"), "untracked\n").unwrap();
+
+        assert!(apply_patch(&repo, &patch, true).is_err());
+        assert!(apply_patch(&repo, &patch, false).is_err());
+        assert_eq!(repo.head().unwrap().target(), Some(head));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("f1")).unwrap(),
+            text(&lines)
+        );
+        assert!(staged_summary(&repo).unwrap().files.is_empty());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("f2")).unwrap(),
+            "untracked\n"
+        );
+    }
+
+    #[test]
+    fn patch_mail_reads_folded_subjects_and_skips_bad_dates() {
+        let mail = PatchMail::parse(
+            "From 1234 Mon Sep 17 00:00:00 2001\n\
+             From: Grace Hopper <grace@example.com>\n\
+             Date: not a date\n\
+             Subject: [PATCH 2/3] A subject long enough\n\
+             \tto be folded\n\
+             \n\
+             ---\n\
+             diff --git a/f b/f\n",
+        );
+        assert_eq!(
+            mail.author,
+            Some(("Grace Hopper".to_string(), "grace@example.com".to_string()))
+        );
+        assert!(mail.time.is_none());
+        assert_eq!(
+            mail.message.as_deref(),
+            Some("A subject long enough\tto be folded")
+        );
+    }
+
+    #[test]
+    fn squash_last_combines_the_commits_as_the_oldest_author() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repo(dir.path());
+        let base = commit_file(&repo, "f1", Some("1\n"), "c1");
+        let c2 = commit_file(&repo, "f2", Some("2\n"), "c2");
+        // c2, the oldest commit squashed, has an author of its own.
+        let author = Signature::new(
+            "Ada Lovelace",
+            "ada@example.com",
+            &git2::Time::new(1_700_000_000, 0),
+        )
+        .unwrap();
+        repo.find_commit(c2)
+            .unwrap()
+            .amend(Some("HEAD"), Some(&author), None, None, None, None)
+            .unwrap();
+        commit_file(&repo, "f3", Some("3\n"), "c3");
+        let head = commit_file(&repo, "f2", None, "c4");
+        fs::write(dir.path().join("f1"), "unstaged\n").unwrap();
+
+        let id = squash_last(&repo, 3, "c2 to c4").unwrap();
+        let squashed = repo.find_commit(id).unwrap();
+        assert_eq!(repo.head().unwrap().target(), Some(id));
+        assert_eq!(subjects_since(&repo, base), ["c2 to c4"]);
+        assert_eq!(
+            squashed.tree_id(),
+            repo.find_commit(head).unwrap().tree_id()
+        );
+        assert_eq!(squashed.author().name(), Some("Ada Lovelace"));
+        assert_eq!(squashed.author().when(), author.when());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("f1")).unwrap(),
+            "unstaged\n"
+        );
+    }
+
+    #[test]
+    fn squash_last_refuses_a_merge_in_the_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = repo_with_other_branch(dir.path());
+        commit_file(&repo, "f3", Some("ours\n"), "c3");
+        merge_branch(&repo, "other").unwrap();
+        let head = commit_file(&repo, "f4", Some("after\n"), "c4");
+
+        let error = squash_last(&repo, 2, "squashed").unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Cannot squash across the merge commit"));
+        assert!(squash_last(&repo, 3, "squashed").is_err());
+        assert_eq!(repo.head().unwrap().target(), Some(head));
+    }
+
+    #[test]
+    fn squash_last_refuses_more_commits_than_the_branch_has() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repo(dir.path());
+        commit_file(&repo, "f1", Some("1\n"), "c1");
+        let head = commit_file(&repo, "f2", Some("2\n"), "c2");
+
+        let error = squash_last(&repo, 3, "squashed").unwrap_err();
+        assert_eq!(error.to_string(), "The branch only has 2 commit(s), not 3.");
+        assert!(squash_last(&repo, 1, "squashed").is_err());
+        assert_eq!(repo.head().unwrap().target(), Some(head));
+    }
+}
@@ -0,0 +1,649 @@
+// src/keymap.rs
+
+use crate::utils::config_dir;
+use anyhow::{Context, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::path::PathBuf;
+
+/// Every configurable action as `(view.action, default key, description)`.
+/// Keys in the "global" group are checked before any view sees the event.
+const DEFAULT_BINDINGS: &[(&str, &str, &str)] = &[
+    ("global.quit", "q", "Exit application"),
+    ("global.next_view", "Tab", "Switch between views"),
+    (
+        "global.prev_view",
+        "BackTab",
+        "Switch between views in reverse",
+    ),
+    ("global.go_status", "1", "Go to the Status view"),
+    ("global.go_log", "2", "Go to the Log view"),
+    ("global.go_branch", "3", "Go to the Branch view"),
+    ("global.go_commit", "4", "Go to the Commit view"),
+    ("global.go_help", "5", "Show this help"),
+    ("global.go_tag", "6", "Go to the Tag view"),
+    ("global.go_remote", "7", "Go to the Remote view"),
+    ("global.go_stash", "8", "Go to the Stash view"),
+    ("global.go_reflog", "9", "Go to the Reflog view"),
+    ("global.go_config", "0", "Go to the Config view"),
+    ("global.help", "?", "Show or hide this help"),
+    (
+        "global.refresh",
+        "F5",
+        "Reload the current view from the repository",
+    ),
+    (
+        "global.continue",
+        "F6",
+        "Continue a merge, rebase, cherry-pick or revert once conflicts are resolved",
+    ),
+    (
+        "global.abort",
+        "F7",
+        "Abort a merge, cherry-pick or revert, discarding its changes",
+    ),
+    (
+        "global.focus_messages",
+        "F2",
+        "Focus the messages pane to scroll back through it",
+    ),
+    ("nav.up", "k", "Move the selection up (also Up)"),
+    ("nav.down", "j", "Move the selection down (also Down)"),
+    ("nav.top", "g", "Jump to the top (also Home)"),
+    ("nav.bottom", "G", "Jump to the bottom (also End)"),
+    ("nav.half_page_down", "Ctrl+d", "Move half a page down"),
+    ("nav.half_page_up", "Ctrl+u", "Move half a page up"),
+    ("messages.clear", "c", "Clear the message log"),
+    (
+        "welcome.init",
+        "i",
+        "Initialize a repository in this directory",
+    ),
+    ("welcome.bare", "b", "Toggle initializing a bare repository"),
+    ("welcome.clone", "c", "Clone a repository instead"),
+    ("welcome.open", "o", "Open another directory"),
+    ("status.diff", "Enter", "Show the diff of the selected file"),
+    ("status.blame", "b", "Blame the selected file"),
+    (
+        "status.history",
+        "h",
+        "Show the commits that changed the selected file",
+    ),
+    (
+        "status.toggle",
+        "Space",
+        "Check or uncheck the selected file",
+    ),
+    (
+        "status.stage",
+        "a",
+        "Stage the checked files, or the selected one",
+    ),
+    ("status.conflicts", "C", "Resolve merge conflicts"),
+    ("status.ignored", "i", "Show or hide ignored files"),
+    ("status.unstage", "u", "Unstage the selected file"),
+    ("status.edit", "e", "Open the selected file in $EDITOR"),
+    ("status.stage_all", "A", "Stage all changes"),
+    (
+        "status.discard",
+        "x",
+        "Discard changes to the selected file (asks to confirm)",
+    ),
+    (
+        "status.discard_all",
+        "X",
+        "Discard all changes (asks to confirm)",
+    ),
+    ("log.refresh", "r", "Refresh commit logs"),
+    ("log.details", "Enter", "Show commit details"),
+    ("log.search", "/", "Search by message, author or id"),
+    ("log.next_match", "n", "Jump to the next match"),
+    ("log.prev_match", "N", "Jump to the previous match"),
+    ("log.filter_author", "f", "Filter by author name or email"),
+    ("log.toggle_date", "t", "Toggle relative and absolute dates"),
+    ("log.graph", "l", "Toggle the commit graph"),
+    (
+        "log.first_parent",
+        "m",
+        "Show only the mainline (first parents), or every commit",
+    ),
+    ("log.email", "e", "Show or hide author emails"),
+    (
+        "log.diffstat",
+        "d",
+        "Show or hide lines added and removed per commit",
+    ),
+    ("log.revert", "v", "Revert the selected commit"),
+    (
+        "log.rebase",
+        "i",
+        "Interactively rebase the commits after the selected one",
+    ),
+    ("log.reword", "w", "Edit the message of the HEAD commit"),
+    (
+        "log.squash",
+        "s",
+        "Squash the selected commit and all later ones into one",
+    ),
+    (
+        "log.undo",
+        "u",
+        "Undo the last commit, keeping its changes staged",
+    ),
+    (
+        "log.checkout",
+        "c",
+        "Check out the selected commit (detached HEAD)",
+    ),
+    ("log.branch", "b", "Create a branch at the selected commit"),
+    ("log.goto", ":", "Jump to a commit by hash"),
+    (
+        "log.export_patch",
+        "p",
+        "Save the selected commit as a .patch file",
+    ),
+    (
+        "log.apply_patch",
+        "A",
+        "Apply a patch file, optionally committing it",
+    ),
+    ("log.copy", "y", "Copy the selected commit's id"),
+    ("log.wrap", "W", "Toggle line wrapping in commit details"),
+    (
+        "log.mark",
+        "Space",
+        "Mark a commit, then mark another to diff them",
+    ),
+    (
+        "log.reset",
+        "R",
+        "Reset to the selected commit (soft/mixed/hard)",
+    ),
+    ("branch.switch", "Enter", "Switch to the selected branch"),
+    ("branch.create", "c", "Create a new branch"),
+    ("branch.copy", "y", "Copy the selected branch name"),
+    ("branch.delete", "d", "Delete a branch (asks to confirm)"),
+    (
+        "branch.remotes",
+        "a",
+        "Show or hide remote-tracking branches",
+    ),
+    (
+        "branch.delete_remote",
+        "D",
+        "Delete the selected remote branch upstream (asks to confirm)",
+    ),
+    (
+        "branch.push",
+        "P",
+        "Push the current branch and track it upstream",
+    ),
+    (
+        "branch.force_push",
+        "F",
+        "Force-push the current branch (press twice)",
+    ),
+    (
+        "branch.pull",
+        "p",
+        "Pull the current branch from its upstream",
+    ),
+    (
+        "branch.pull_rebase",
+        "R",
+        "Pull and rebase local commits onto the upstream",
+    ),
+    (
+        "branch.rebase",
+        "r",
+        "Rebase the current branch onto the selected one",
+    ),
+    ("branch.abort_rebase", "A", "Abort an in-progress rebase"),
+    (
+        "branch.upstream",
+        "u",
+        "Set or clear the selected branch's upstream",
+    ),
+    (
+        "branch.mark",
+        "m",
+        "Mark a branch, then mark another to compare",
+    ),
+    (
+        "branch.compare.merge",
+        "M",
+        "In a comparison: merge the compared branch into the current one",
+    ),
+    (
+        "branch.compare.rebase",
+        "R",
+        "In a comparison: rebase the current branch onto the base",
+    ),
+    (
+        "branch.compare.diff",
+        "d",
+        "In a comparison: show the combined diff",
+    ),
+    ("tag.create", "c", "Tag the commit selected in the log"),
+    ("remote.add", "a", "Add a remote"),
+    ("remote.delete", "d", "Remove the selected remote"),
+    (
+        "remote.fetch",
+        "f",
+        "Fetch from the selected remote (no merge)",
+    ),
+    (
+        "remote.fetch_all",
+        "F",
+        "Fetch all remotes and prune deleted branches",
+    ),
+    ("remote.rename", "n", "Rename the selected remote"),
+    ("remote.set_url", "u", "Change the selected remote's URL"),
+    ("commit.write", "c", "Write a commit message"),
+    ("commit.amend", "a", "Amend the last commit"),
+    (
+        "commit.all",
+        "A",
+        "Stage all changes to tracked files and commit them",
+    ),
+    ("commit.signoff", "s", "Toggle the Signed-off-by trailer"),
+    ("commit.gpg_sign", "g", "Toggle GPG signing of new commits"),
+    (
+        "conflict.ours",
+        "o",
+        "Resolve the selected file with our side",
+    ),
+    (
+        "conflict.theirs",
+        "t",
+        "Resolve the selected file with their side",
+    ),
+    ("conflict.scroll_down", "J", "Scroll the file contents down"),
+    ("conflict.scroll_up", "K", "Scroll the file contents up"),
+    ("rebase.pick", "p", "Pick the selected commit as is"),
+    (
+        "rebase.reword",
+        "r",
+        "Pick the selected commit with a new subject",
+    ),
+    (
+        "rebase.squash",
+        "s",
+        "Meld into the commit above, keeping both messages",
+    ),
+    (
+        "rebase.fixup",
+        "f",
+        "Meld into the commit above, dropping this message",
+    ),
+    ("rebase.drop", "d", "Leave the selected commit out"),
+    ("rebase.move_down", "J", "Move the selected commit later"),
+    ("rebase.move_up", "K", "Move the selected commit earlier"),
+    ("rebase.start", "Enter", "Start the rebase"),
+    ("stash.save", "s", "Stash working changes"),
+    ("stash.pop", "p", "Pop the selected stash"),
+    ("stash.apply", "a", "Apply the selected stash"),
+    ("stash.drop", "d", "Drop the selected stash"),
+    ("stash.scroll_down", "J", "Scroll the stash diff down"),
+    ("stash.scroll_up", "K", "Scroll the stash diff up"),
+    ("config.edit", "Enter", "Edit the selected value"),
+    (
+        "config.scope",
+        "s",
+        "Switch between the repository and global config",
+    ),
+    (
+        "reflog.reset",
+        "Enter",
+        "Reset HEAD to the selected entry (asks to confirm)",
+    ),
+    ("diff.blame", "b", "Blame this file"),
+    (
+        "diff.history",
+        "h",
+        "Show the commits that changed this file",
+    ),
+    ("diff.next_hunk", "]", "Select the next hunk"),
+    ("diff.prev_hunk", "[", "Select the previous hunk"),
+    ("diff.stage_hunk", "s", "Stage the selected hunk"),
+    ("diff.unstage_hunk", "u", "Unstage the selected hunk"),
+    ("diff.edit", "e", "Open the file in $EDITOR"),
+    (
+        "diff.word_diff",
+        "w",
+        "Toggle highlighting of changed words",
+    ),
+    (
+        "diff.wrap",
+        "W",
+        "Toggle line wrapping (Left/Right scroll when off)",
+    ),
+];
+
+/// Views whose lists move with the `nav` keys, which can clash with theirs.
+const NAV_VIEWS: &[&str] = &[
+    "status", "log", "branch", "stash", "config", "conflict", "rebase", "messages",
+];
+
+/// A key bound to a named action.
+#[derive(Clone, Debug)]
+pub struct Binding {
+    pub action: &'static str,
+    pub key: KeyCode,
+    /// Whether Ctrl is held with `key`.
+    pub ctrl: bool,
+    pub description: &'static str,
+}
+
+impl Binding {
+    /// Whether `key` is this binding, Ctrl included.
+    fn matches(&self, key: &KeyEvent) -> bool {
+        self.key == key.code && self.ctrl == key.modifiers.contains(KeyModifiers::CONTROL)
+    }
+
+    /// The key as written in `keys.toml`, such as `Ctrl+d`.
+    pub fn key_name(&self) -> String {
+        if self.ctrl {
+            format!("Ctrl+{}", key_name(self.key))
+        } else {
+            key_name(self.key)
+        }
+    }
+}
+
+/// Maps action names such as `branch.create` to the keys that trigger them.
+#[derive(Clone, Debug)]
+pub struct KeyMap {
+    pub bindings: Vec<Binding>,
+}
+
+impl Default for KeyMap {
+    fn default() -> KeyMap {
+        KeyMap {
+            bindings: DEFAULT_BINDINGS
+                .iter()
+                .map(|&(action, key, description)| {
+                    let (key, ctrl) = parse_binding(key).expect("default keys are valid");
+                    Binding {
+                        action,
+                        key,
+                        ctrl,
+                        description,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl KeyMap {
+    /// Loads `~/.config/rugit/keys.toml` over the defaults. Bindings can be
+    /// written flat (`"branch.create" = "n"`) or grouped in `[branch]` tables.
+    pub fn load() -> Result<KeyMap> {
+        match config_path() {
+            Some(path) if path.exists() => {
+                let text = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read '{}'", path.display()))?;
+                KeyMap::parse(&text)
+                    .with_context(|| format!("Invalid key bindings '{}'", path.display()))
+            }
+            _ => Ok(KeyMap::default()),
+        }
+    }
+
+    fn parse(text: &str) -> Result<KeyMap> {
+        let table: toml::Table = toml::from_str(text)?;
+        let mut entries = Vec::new();
+        flatten(&table, "", &mut entries)?;
+
+        let mut keymap = KeyMap::default();
+        for (action, key) in entries {
+            let binding = keymap
+                .bindings
+                .iter_mut()
+                .find(|binding| binding.action == action)
+                .ok_or_else(|| anyhow::anyhow!("Unknown action '{}'", action))?;
+            (binding.key, binding.ctrl) = parse_binding(&key)
+                .ok_or_else(|| anyhow::anyhow!("Unknown key '{}' for '{}'", key, action))?;
+        }
+
+        Ok(keymap)
+    }
+
+    /// Returns the action (without the view prefix) bound to `key` in `view`,
+    /// which can be a mode of a view such as `branch.compare`. Actions of
+    /// that mode don't count as the view's own.
+    pub fn action(&self, view: &str, key: &KeyEvent) -> Option<&'static str> {
+        self.bindings
+            .iter()
+            .filter(|binding| binding.matches(key))
+            .find_map(|binding| {
+                let name = binding.action.strip_prefix(view)?.strip_prefix('.')?;
+                (!name.contains('.')).then_some(name)
+            })
+    }
+
+    /// Whether `key` triggers `action` (a full name such as `global.quit`).
+    pub fn is(&self, action: &str, key: &KeyEvent) -> bool {
+        self.bindings
+            .iter()
+            .any(|binding| binding.action == action && binding.matches(key))
+    }
+
+    /// Names the key bound to `action`, for hints in titles and banners.
+    pub fn key_for(&self, action: &str) -> String {
+        self.bindings
+            .iter()
+            .find(|binding| binding.action == action)
+            .map(Binding::key_name)
+            .unwrap_or_else(|| "unbound".to_string())
+    }
+
+    /// Describes keys bound to more than one action in the same view or mode,
+    /// where global keys count as part of every view and `nav` keys as part
+    /// of every view with a list.
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut conflicts = Vec::new();
+        for (i, first) in self.bindings.iter().enumerate() {
+            for second in &self.bindings[i + 1..] {
+                let (first_view, _) = first.action.rsplit_once('.').unwrap_or_default();
+                let (second_view, _) = second.action.rsplit_once('.').unwrap_or_default();
+                let nav_clash = |nav: &str, view: &str| nav == "nav" && NAV_VIEWS.contains(&view);
+                let same_view = first_view == second_view
+                    || first_view == "global"
+                    || second_view == "global"
+                    || nav_clash(first_view, second_view)
+                    || nav_clash(second_view, first_view);
+                if same_view && first.key == second.key && first.ctrl == second.ctrl {
+                    conflicts.push(format!(
+                        "'{}' is bound to both '{}' and '{}'",
+                        first.key_name(),
+                        first.action,
+                        second.action
+                    ));
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+/// Collects `dotted.action = "key"` pairs from nested tables.
+fn flatten(table: &toml::Table, prefix: &str, entries: &mut Vec<(String, String)>) -> Result<()> {
+    for (name, value) in table {
+        let action = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+        match value {
+            toml::Value::String(key) => entries.push((action, key.clone())),
+            toml::Value::Table(table) => flatten(table, &action, entries)?,
+            _ => anyhow::bail!("'{}' must be a key name or a table", action),
+        }
+    }
+    Ok(())
+}
+
+/// `$XDG_CONFIG_HOME/rugit/keys.toml`, defaulting to `~/.config`.
+fn config_path() -> Option<PathBuf> {
+    Some(config_dir()?.join("keys.toml"))
+}
+
+/// Parses a key as `parse_key` does, optionally after `Ctrl+`; returns the
+/// key and whether Ctrl is held.
+fn parse_binding(value: &str) -> Option<(KeyCode, bool)> {
+    match value.get(..5) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("ctrl+") => {
+            Some((parse_key(&value[5..])?, true))
+        }
+        _ => Some((parse_key(value)?, false)),
+    }
+}
+
+/// Parses a single character ("c", "/") or a named key ("Enter", "F5").
+pub fn parse_key(value: &str) -> Option<KeyCode> {
+    let mut chars = value.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+
+    let key = match value.to_ascii_lowercase().as_str() {
+        "enter" | "return" => KeyCode::Enter,
+        "esc" | "escape" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "space" => KeyCode::Char(' '),
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" | "pgup" => KeyCode::PageUp,
+        "pagedown" | "pgdn" => KeyCode::PageDown,
+        name => KeyCode::F(name.strip_prefix('f')?.parse().ok()?),
+    };
+    Some(key)
+}
+
+/// A short human-readable name for `key`, as accepted by `parse_key`.
+pub fn key_name(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        KeyCode::PageUp => "PgUp".to_string(),
+        KeyCode::PageDown => "PgDn".to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn ctrl(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    #[test]
+    fn the_default_bindings_do_not_conflict() {
+        assert_eq!(KeyMap::default().conflicts(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn bindings_parse_flat_and_grouped() {
+        let keys = KeyMap::parse(
+            r#"
+            "status.stage" = "S"
+            [branch]
+            create = "c"
+            compare.diff = "F3"
+            "#,
+        )
+        .unwrap();
+        assert!(keys.is("status.stage", &key(KeyCode::Char('S'))));
+        assert!(!keys.is("status.stage", &key(KeyCode::Char('a'))));
+        assert_eq!(
+            keys.action("branch", &key(KeyCode::Char('c'))),
+            Some("create")
+        );
+        assert_eq!(keys.key_for("branch.compare.diff"), "F3");
+        // Untouched actions keep their defaults.
+        assert_eq!(keys.key_for("global.quit"), "q");
+    }
+
+    #[test]
+    fn unknown_actions_and_keys_are_rejected() {
+        let error = KeyMap::parse(r#""status.nope" = "x""#).unwrap_err();
+        assert_eq!(error.to_string(), "Unknown action 'status.nope'");
+        let error = KeyMap::parse(r#""status.stage" = "Hyper+x""#).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown key 'Hyper+x' for 'status.stage'"
+        );
+        assert!(KeyMap::parse(r#""status.stage" = 1"#).is_err());
+    }
+
+    #[test]
+    fn ctrl_bindings_only_match_with_ctrl() {
+        assert_eq!(parse_binding("Ctrl+d"), Some((KeyCode::Char('d'), true)));
+        assert_eq!(parse_binding("ctrl+Enter"), Some((KeyCode::Enter, true)));
+        assert_eq!(parse_binding("d"), Some((KeyCode::Char('d'), false)));
+        assert_eq!(parse_binding("Ctrl+"), None);
+
+        let keys = KeyMap::parse(r#""status.stage" = "Ctrl+s""#).unwrap();
+        assert!(keys.is("status.stage", &ctrl('s')));
+        assert!(!keys.is("status.stage", &key(KeyCode::Char('s'))));
+        assert_eq!(keys.key_for("status.stage"), "Ctrl+s");
+    }
+
+    #[test]
+    fn a_mode_keeps_its_actions_apart_from_its_view() {
+        let keys = KeyMap::default();
+        let d = key(KeyCode::Char('d'));
+        assert_eq!(keys.action("branch", &d), Some("delete"));
+        assert_eq!(keys.action("branch.compare", &d), Some("diff"));
+        assert_eq!(keys.action("branch", &key(KeyCode::Char('M'))), None);
+    }
+
+    #[test]
+    fn global_keys_conflict_with_every_view() {
+        let keys = KeyMap::parse(r#""global.refresh" = "]""#).unwrap();
+        assert_eq!(
+            keys.conflicts(),
+            vec!["']' is bound to both 'global.refresh' and 'diff.next_hunk'"]
+        );
+    }
+
+    #[test]
+    fn nav_keys_conflict_only_with_list_views() {
+        // The diff view has no list, so `]` is free there.
+        let keys = KeyMap::parse(r#""nav.up" = "]""#).unwrap();
+        assert!(keys.conflicts().is_empty());
+
+        let keys = KeyMap::parse(r#""nav.up" = "a""#).unwrap();
+        assert!(keys
+            .conflicts()
+            .contains(&"'a' is bound to both 'nav.up' and 'status.stage'".to_string()));
+    }
+
+    #[test]
+    fn keys_conflict_within_a_view_but_not_across_views() {
+        let keys = KeyMap::parse(r#""status.unstage" = "a""#).unwrap();
+        assert_eq!(
+            keys.conflicts(),
+            vec!["'a' is bound to both 'status.stage' and 'status.unstage'"]
+        );
+
+        let keys = KeyMap::parse(r#""diff.blame" = "a""#).unwrap();
+        assert!(keys.conflicts().is_empty());
+    }
+}
@@ -0,0 +1,70 @@
+// src/logger.rs
+
+use log::{LevelFilter, Log, Metadata, Record};
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    panic,
+    path::Path,
+    sync::Mutex,
+};
+
+/// Appends log records to a file, since stderr is hidden behind the TUI.
+struct FileLogger {
+    file: Mutex<File>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if let Ok(mut file) = self.file.lock() {
+            let _ = writeln!(
+                file,
+                "{} {:<5} [{}] {}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+                record.level(),
+                record.target(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+/// Installs the file logger. `verbose` enables debug/trace records such as
+/// key events and operation timings.
+pub fn init(path: &Path, verbose: bool) -> io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+    log::set_boxed_logger(Box::new(FileLogger {
+        file: Mutex::new(file),
+    }))
+    .map_err(io::Error::other)?;
+    log::set_max_level(if verbose {
+        LevelFilter::Trace
+    } else {
+        LevelFilter::Info
+    });
+
+    // Make sure the panic message and everything before it reach the file.
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        log::error!("panic: {}", info);
+        log::logger().flush();
+        default_hook(info);
+    }));
+
+    log::info!("Logging started (verbose: {})", verbose);
+    Ok(())
+}
@@ -1,83 +1,330 @@
-
-
+use clap::Parser;
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event as CEvent},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use std::{
+    env,
     error::Error,
     io,
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread,
     time::{Duration, Instant},
 };
 use tui::{
     backend::{Backend, CrosstermBackend},
+    layout::Rect,
     Terminal,
 };
 
 mod app;
-mod help_view;
+mod clipboard;
+mod events;
 mod git;
 mod git_utils;
+mod keymap;
+mod logger;
+mod settings;
+mod task;
+mod theme;
 mod tui_module;
 mod utils;
 
 use app::App;
+use events::Event;
+use keymap::KeyMap;
+use settings::Settings;
+use theme::Theme;
+use tui_module::message_log::{self, MessageLog};
+
+/// Command-line options.
+#[derive(Parser)]
+#[command(name = "rugit", about = "A terminal UI for Git")]
+struct Cli {
+    /// Path to the repository, or any directory inside it.
+    #[arg(default_value = ".")]
+    path: PathBuf,
+
+    /// Write internal diagnostics to this file.
+    #[arg(long, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+
+    /// Include key events and operation timings in the log file.
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Number of messages kept in the messages pane.
+    #[arg(long, value_name = "COUNT", default_value_t = message_log::DEFAULT_LIMIT)]
+    max_messages: usize,
+
+    /// Milliseconds between ticks, which redraw spinners and progress.
+    #[arg(
+        long,
+        value_name = "MS",
+        default_value_t = DEFAULT_TICK_RATE_MS,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    tick_rate: u64,
+
+    /// Milliseconds between checks of the repository for changes.
+    #[arg(
+        long,
+        value_name = "MS",
+        default_value_t = app::DEFAULT_REFRESH_INTERVAL_MS,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    refresh_interval: u64,
 
-enum Event<I> {
-    Input(I),
-    Tick,
+    /// View to open on (status, log, branch, ...), instead of the
+    /// `startup_view` setting or the view remembered from the last run.
+    #[arg(long, value_name = "NAME")]
+    view: Option<String>,
 }
 
+/// Milliseconds between ticks when no `--tick-rate` is given.
+const DEFAULT_TICK_RATE_MS: u64 = 250;
+
 fn main() -> Result<(), Box<dyn Error>> {
-  
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
+    let cli = Cli::parse();
+    if let Some(path) = &cli.log_file {
+        logger::init(path, cli.verbose)?;
+    }
+
+    setup_terminal()?;
+    let backend = CrosstermBackend::new(io::stdout());
     let mut terminal = Terminal::new(backend)?;
 
- 
-    let mut app = App::new();
+    let repo_path = git_utils::discover_repo(&cli.path.to_string_lossy())
+        .unwrap_or_else(|_| cli.path.to_string_lossy().into_owned());
+    log::info!("Using repository at '{}'", repo_path);
+    let (theme, theme_error) = match Theme::load() {
+        Ok(theme) => (theme, None),
+        Err(e) => {
+            log::error!("{:?}", e);
+            (Theme::default(), Some(e))
+        }
+    };
+    let (keys, keys_error) = match KeyMap::load() {
+        Ok(keys) => (keys, None),
+        Err(e) => {
+            log::error!("{:?}", e);
+            (KeyMap::default(), Some(e))
+        }
+    };
+    let conflicts = keys.conflicts();
+    let (settings, settings_error) = match Settings::load() {
+        Ok(settings) => (settings, None),
+        Err(e) => {
+            log::error!("{:?}", e);
+            (Settings::default(), Some(e))
+        }
+    };
 
+    // Input and ticks come from their own thread so that results from
+    // background tasks can wake the loop through the same channel.
+    let (tx, rx) = mpsc::channel();
+    let tick_rate = Duration::from_millis(cli.tick_rate);
+    let input_tx = tx.clone();
+    let paused = Arc::new(AtomicBool::new(false));
+    let (acknowledge, acknowledged) = mpsc::channel();
+    let (resume, resumed) = mpsc::channel();
+    let input_pause = InputPause {
+        paused: Arc::clone(&paused),
+        acknowledged,
+        resume,
+    };
+    thread::spawn(move || {
+        let mut last_tick = Instant::now();
+        loop {
+            if paused.load(Ordering::SeqCst) {
+                // Out of `poll`, so every key goes to the editor until resumed.
+                if acknowledge.send(()).is_err() || resumed.recv().is_err() {
+                    return;
+                }
+                continue;
+            }
+            let timeout = tick_rate
+                .checked_sub(last_tick.elapsed())
+                .unwrap_or_else(|| Duration::from_secs(0));
+
+            if event::poll(timeout).unwrap_or(false) {
+                if let Ok(event) = event::read() {
+                    if input_tx.send(Event::Input(event)).is_err() {
+                        return;
+                    }
+                }
+            }
+
+            if last_tick.elapsed() >= tick_rate {
+                if input_tx.send(Event::Tick).is_err() {
+                    return;
+                }
+                last_tick = Instant::now();
+            }
+        }
+    });
 
-    let tick_rate = Duration::from_millis(250);
-    let mut last_tick = Instant::now();
+    let startup_view = cli.view.as_deref().or(settings.startup_view.as_deref());
+    let mut app = App::new(repo_path, theme, keys, tx, startup_view);
+    app.message_log = MessageLog::new(cli.max_messages);
+    app.refresh_interval = Duration::from_millis(cli.refresh_interval);
+    if let Some(e) = theme_error {
+        app.messages
+            .push(format!("Using the default theme: {:#}", e));
+    }
+    if let Some(e) = keys_error {
+        app.messages
+            .push(format!("Using the default key bindings: {:#}", e));
+    }
+    if let Some(e) = settings_error {
+        app.messages
+            .push(format!("Using the default settings: {:#}", e));
+    }
+    if settings.remember_view && cli.view.is_none() {
+        if let Some(view) = settings::last_view() {
+            app.restore_view(&view);
+        }
+    }
+    for conflict in conflicts {
+        log::warn!("Key binding conflict: {}", conflict);
+        app.messages
+            .push(format!("Key binding conflict: {}", conflict));
+    }
 
     loop {
         terminal.draw(|f| {
             app.render(f);
         })?;
 
-        let timeout = tick_rate
-            .checked_sub(last_tick.elapsed())
-            .unwrap_or_else(|| Duration::from_secs(0));
+        // Slow operations wait for a frame showing the spinner.
+        if app.run_deferred() {
+            app.flush_messages();
+            continue;
+        }
 
-        if event::poll(timeout)? {
-            match event::read()? {
-                CEvent::Key(key) => {
-                    if app.handle_input(key) {
-                        break;
-                    }
+        match rx.recv()? {
+            Event::Input(CEvent::Key(key)) => {
+                log::trace!("Key event: {:?}", key);
+                let started = Instant::now();
+                let quit = app.handle_input(key);
+                log::debug!("Handled {:?} in {:?}", key.code, started.elapsed());
+                if quit {
+                    break;
                 }
-                _ => {}
+                if let Some(path) = app.edit_requested.take() {
+                    let result = edit_file(&mut terminal, &path, &input_pause)?;
+                    app.finish_edit(&path, result);
+                }
+            }
+            Event::Input(CEvent::Mouse(mouse)) => app.handle_mouse(mouse),
+            // Resizing clears the screen, so the loop redraws everything
+            // straight away instead of leaving stale cells until the next tick.
+            Event::Input(CEvent::Resize(width, height)) => {
+                log::debug!("Resized to {}x{}", width, height);
+                terminal.resize(Rect::new(0, 0, width, height))?;
             }
+            Event::Input(_) => {}
+            Event::Tick => {
+                let started = Instant::now();
+                app.on_tick();
+                log::trace!("Tick refresh took {:?}", started.elapsed());
+            }
+            Event::Progress(task, transfer) => app.task_progress(task, transfer),
+            Event::Task(result) => app.finish_task(result),
         }
+        app.flush_messages();
+    }
 
-        if last_tick.elapsed() >= tick_rate {
-            last_tick = Instant::now();
-            app.on_tick();
+    restore_terminal()?;
+    terminal.show_cursor()?;
+
+    if settings.remember_view {
+        if let Err(e) = settings::save_last_view(&app.view_to_remember()) {
+            log::error!("{:?}", e);
         }
     }
 
+    log::info!("Exiting");
+    log::logger().flush();
 
+    Ok(())
+}
+
+/// Puts the terminal in the raw, alternate-screen mode the UI draws in.
+fn setup_terminal() -> io::Result<()> {
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)
+}
+
+/// Gives the terminal back to the shell, or to a program run from the UI.
+fn restore_terminal() -> io::Result<()> {
     disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
+    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)
+}
+
+/// The main loop's side of stopping the input thread from reading the
+/// terminal while another program owns it.
+struct InputPause {
+    paused: Arc<AtomicBool>,
+    /// Sent by the input thread once it has stopped reading.
+    acknowledged: mpsc::Receiver<()>,
+    resume: mpsc::Sender<()>,
+}
+
+impl InputPause {
+    /// Returns once the input thread has finished its current `poll`.
+    fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+        // An error means the thread is gone and reads nothing anyway.
+        let _ = self.acknowledged.recv();
+    }
+
+    fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+        let _ = self.resume.send(());
+    }
+}
+
+/// Suspends the UI and input while `$EDITOR` edits `path`, then redraws
+/// it from scratch.
+fn edit_file<B: Backend>(
+    terminal: &mut Terminal<B>,
+    path: &Path,
+    input: &InputPause,
+) -> io::Result<io::Result<ExitStatus>> {
+    input.pause();
+    let result = run_editor(terminal, path);
+    input.resume();
+    result
+}
+
+/// Runs `$EDITOR` (`vi` if unset) on `path` in the restored terminal. The
+/// outer error is the terminal failing; the inner one is the editor failing
+/// to start.
+fn run_editor<B: Backend>(
+    terminal: &mut Terminal<B>,
+    path: &Path,
+) -> io::Result<io::Result<ExitStatus>> {
+    restore_terminal()?;
     terminal.show_cursor()?;
 
-    Ok(())
+    let editor = env::var("EDITOR")
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    // Allows editors given with flags, such as `code --wait`.
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+    let status = Command::new(program).args(words).arg(path).status();
+    log::info!("Ran '{}' on '{}': {:?}", editor, path.display(), status);
+
+    setup_terminal()?;
+    terminal.clear()?;
+    Ok(status)
 }
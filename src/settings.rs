@@ -0,0 +1,58 @@
+// src/settings.rs
+
+use crate::utils::config_dir;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Options from `~/.config/rugit/config.toml`; every field is optional.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Reopen on the view that was active at exit. Off by default so that
+    /// scripted runs always start the same way.
+    pub remember_view: bool,
+    /// View to open on, such as `"log"`, in any case; unknown names open
+    /// Status. A remembered view takes precedence.
+    pub startup_view: Option<String>,
+}
+
+/// What rugit remembers between runs, in `~/.config/rugit/state.toml`.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+struct State {
+    view: Option<String>,
+}
+
+impl Settings {
+    /// Loads the config file, or the defaults if it does not exist.
+    pub fn load() -> Result<Settings> {
+        match config_dir().map(|dir| dir.join("config.toml")) {
+            Some(path) if path.exists() => {
+                let text = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read '{}'", path.display()))?;
+                toml::from_str(&text)
+                    .with_context(|| format!("Invalid config '{}'", path.display()))
+            }
+            _ => Ok(Settings::default()),
+        }
+    }
+}
+
+/// The view saved by `save_last_view`, if any.
+pub fn last_view() -> Option<String> {
+    let text = std::fs::read_to_string(config_dir()?.join("state.toml")).ok()?;
+    toml::from_str::<State>(&text).ok()?.view
+}
+
+/// Saves `view` for `last_view` to return on the next run.
+pub fn save_last_view(view: &str) -> Result<()> {
+    let dir = config_dir().context("No config directory; HOME is not set")?;
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create '{}'", dir.display()))?;
+    let state = State {
+        view: Some(view.to_string()),
+    };
+    let path = dir.join("state.toml");
+    std::fs::write(&path, toml::to_string(&state)?)
+        .with_context(|| format!("Failed to write '{}'", path.display()))
+}
@@ -0,0 +1,186 @@
+use crate::events::Event;
+use crate::git_utils::{
+    default_callbacks, delete_remote_branch, fetch_all, fetch_remote, pull_upstream, push_branch,
+    push_dry_run, Transfer,
+};
+use crate::tui_module::remote_view::describe_fetch;
+use git2::{RemoteCallbacks, Repository};
+use std::{
+    sync::mpsc::Sender,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How often a task reports transfer progress, so the UI isn't redrawn for
+/// every object received.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A network operation requested by a view. The app runs it on a background
+/// thread so the UI keeps responding while it waits on the remote.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Task {
+    Push {
+        remote: String,
+        branch: String,
+        set_upstream: bool,
+        /// Overwrite the remote branch even if it has commits we don't.
+        force: bool,
+    },
+    /// Fetch, then list what a push would send so it can be confirmed.
+    PushPreview {
+        remote: String,
+        branch: String,
+    },
+    Pull {
+        rebase: bool,
+    },
+    /// Delete a branch on the remote itself.
+    DeleteRemoteBranch {
+        remote: String,
+        branch: String,
+    },
+    Fetch {
+        remote: String,
+    },
+    /// Fetch every remote, optionally pruning stale remote-tracking refs.
+    FetchAll {
+        prune: bool,
+    },
+}
+
+/// The outcome of a task, sent back to the main loop.
+#[derive(Debug)]
+pub struct TaskResult {
+    pub task: Task,
+    pub messages: Vec<String>,
+    /// Commits a push preview found to send.
+    pub outgoing: Option<Vec<String>>,
+}
+
+/// A task in flight and the latest transfer progress it reported.
+pub struct RunningTask {
+    pub task: Task,
+    pub started: Instant,
+    pub transfer: Option<Transfer>,
+}
+
+impl Task {
+    /// Shown next to the spinner while the task runs.
+    pub fn describe(&self) -> String {
+        match self {
+            Task::Push {
+                remote,
+                branch,
+                force: true,
+                ..
+            } => format!("Force-pushing '{}' to {}", branch, remote),
+            Task::Push { remote, branch, .. } => format!("Pushing '{}' to {}", branch, remote),
+            Task::PushPreview { remote, .. } => format!("Checking what {} is missing", remote),
+            Task::DeleteRemoteBranch { remote, branch } => {
+                format!("Deleting '{}' from {}", branch, remote)
+            }
+            Task::Pull { rebase: false } => "Pulling from upstream".to_string(),
+            Task::Pull { rebase: true } => "Pulling from upstream with rebase".to_string(),
+            Task::Fetch { remote } => format!("Fetching {}", remote),
+            Task::FetchAll { prune: false } => "Fetching all remotes".to_string(),
+            Task::FetchAll { prune: true } => "Fetching and pruning all remotes".to_string(),
+        }
+    }
+
+    /// Runs the task on its own thread, opening the repository there since
+    /// a `git2::Repository` can't be shared with the UI thread.
+    pub fn spawn<I: Send + 'static>(self, repo_path: String, events: Sender<Event<I>>) {
+        thread::spawn(move || {
+            // Each fetch consumes its callbacks, so they are built per remote.
+            let mut callbacks = || {
+                let mut callbacks = default_callbacks();
+                let progress_events = events.clone();
+                let task = self.clone();
+                let mut last_sent: Option<Instant> = None;
+                callbacks.transfer_progress(move |progress| {
+                    let transfer = Transfer::from(progress);
+                    let done = transfer.received_objects == transfer.total_objects;
+                    if done || last_sent.is_none_or(|sent| sent.elapsed() >= PROGRESS_INTERVAL) {
+                        last_sent = Some(Instant::now());
+                        let _ = progress_events.send(Event::Progress(task.clone(), transfer));
+                    }
+                    true
+                });
+                callbacks
+            };
+
+            let (messages, outgoing) = match Repository::open(&repo_path) {
+                Ok(repo) => self.run(&repo, &mut callbacks),
+                Err(e) => (vec![format!("Failed to open repository: {}", e)], None),
+            };
+            let _ = events.send(Event::Task(TaskResult {
+                task: self,
+                messages,
+                outgoing,
+            }));
+        });
+    }
+
+    fn run(
+        &self,
+        repo: &Repository,
+        callbacks: &mut dyn FnMut() -> RemoteCallbacks<'static>,
+    ) -> (Vec<String>, Option<Vec<String>>) {
+        let message = match self {
+            Task::PushPreview { remote, branch } => {
+                return match push_dry_run(repo, remote, branch, callbacks()) {
+                    Ok(commits) => (vec![], Some(commits)),
+                    Err(e) => (vec![format!("Failed to preview the push: {:#}", e)], None),
+                }
+            }
+            Task::Push {
+                remote,
+                branch,
+                set_upstream,
+                force,
+            } => match push_branch(repo, remote, branch, *set_upstream, *force) {
+                Ok(_) if *force => format!(
+                    "Force-pushed '{}' to {}; the remote branch's history was replaced.",
+                    branch, remote
+                ),
+                Ok(_) if *set_upstream => format!(
+                    "Pushed '{}' and set upstream to '{}/{}'.",
+                    branch, remote, branch
+                ),
+                Ok(_) => format!("Pushed '{}' to {}.", branch, remote),
+                Err(e) if *force => format!("Failed to force-push: {:#}", e),
+                Err(e) => format!("Failed to push: {}", e),
+            },
+            Task::DeleteRemoteBranch { remote, branch } => {
+                match delete_remote_branch(repo, remote, branch) {
+                    Ok(_) => format!("Deleted branch '{}' from remote '{}'.", branch, remote),
+                    Err(e) => format!("Failed to delete '{}' from {}: {:#}", branch, remote, e),
+                }
+            }
+            Task::Pull { rebase } => match pull_upstream(repo, *rebase, callbacks()) {
+                Ok(_) if *rebase => "Pulled from upstream and rebased local commits.".to_string(),
+                Ok(_) => "Pulled from upstream.".to_string(),
+                Err(e) => format!("Failed to pull: {}", e),
+            },
+            Task::Fetch { remote } => match fetch_remote(repo, remote, &[], false, callbacks()) {
+                Ok(summary) => describe_fetch(&summary),
+                Err(e) => format!("Failed to fetch: {}", e),
+            },
+            Task::FetchAll { prune } => {
+                let messages = match fetch_all(repo, *prune, callbacks) {
+                    Ok(results) if results.is_empty() => vec!["No remotes to fetch.".to_string()],
+                    Ok(results) => results
+                        .into_iter()
+                        .map(|(remote, result)| match result {
+                            Ok(summary) => describe_fetch(&summary),
+                            Err(e) => format!("Failed to fetch {}: {}", remote, e),
+                        })
+                        .collect(),
+                    Err(e) => vec![format!("Failed to fetch: {}", e)],
+                };
+                return (messages, None);
+            }
+        };
+        (vec![message], None)
+    }
+}
@@ -0,0 +1,202 @@
+// src/theme.rs
+
+use crate::utils::config_dir;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::PathBuf;
+use tui::style::{Color, Modifier, Style};
+
+/// Colors used across all views.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    /// Application title and prompts.
+    pub title: Color,
+    /// Selected list rows.
+    pub highlight: Color,
+    /// Message pane and footer.
+    pub messages: Color,
+    /// Block borders.
+    pub border: Color,
+    /// Added lines and confirmations.
+    pub added: Color,
+    /// Removed lines and destructive prompts.
+    pub removed: Color,
+    /// Body text of editors, details and popups.
+    pub text: Color,
+    /// Secondary labels such as hunk headers and blame annotations.
+    pub accent: Color,
+    /// Merge commits in the log.
+    pub merge: Color,
+    /// Commits and branches marked for comparison.
+    pub marked: Color,
+    /// Warnings such as a detached HEAD.
+    pub warning: Color,
+    /// The commit graph gutter in the log.
+    pub graph: Color,
+}
+
+/// The on-disk form of a theme; every field is optional.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ThemeFile {
+    /// "dark" (the default) or "light", applied before the overrides below.
+    preset: Option<String>,
+    title: Option<String>,
+    highlight: Option<String>,
+    messages: Option<String>,
+    border: Option<String>,
+    added: Option<String>,
+    removed: Option<String>,
+    text: Option<String>,
+    accent: Option<String>,
+    merge: Option<String>,
+    marked: Option<String>,
+    warning: Option<String>,
+    graph: Option<String>,
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Theme {
+        Theme {
+            title: Color::Yellow,
+            highlight: Color::Yellow,
+            messages: Color::Magenta,
+            border: Color::Reset,
+            added: Color::Green,
+            removed: Color::Red,
+            text: Color::Reset,
+            accent: Color::Cyan,
+            merge: Color::Magenta,
+            marked: Color::Cyan,
+            warning: Color::Yellow,
+            graph: Color::Yellow,
+        }
+    }
+
+    pub fn light() -> Theme {
+        Theme {
+            title: Color::Blue,
+            highlight: Color::Blue,
+            messages: Color::Magenta,
+            border: Color::DarkGray,
+            added: Color::Green,
+            removed: Color::Red,
+            text: Color::Reset,
+            // A dark cyan that stays readable on a light background.
+            accent: Color::Indexed(30),
+            merge: Color::Magenta,
+            marked: Color::Indexed(30),
+            // A dark orange that stays readable on a light background.
+            warning: Color::Indexed(130),
+            graph: Color::DarkGray,
+        }
+    }
+
+    /// Style for the selected row of a list.
+    pub fn highlight_style(&self) -> Style {
+        Style::default()
+            .fg(self.highlight)
+            .add_modifier(Modifier::BOLD)
+    }
+
+    /// Style for block borders.
+    pub fn border_style(&self) -> Style {
+        Style::default().fg(self.border)
+    }
+
+    /// Loads `~/.config/rugit/theme.toml`, or the default theme if it does
+    /// not exist.
+    pub fn load() -> Result<Theme> {
+        match config_path() {
+            Some(path) if path.exists() => {
+                let text = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read '{}'", path.display()))?;
+                Theme::parse(&text).with_context(|| format!("Invalid theme '{}'", path.display()))
+            }
+            _ => Ok(Theme::default()),
+        }
+    }
+
+    fn parse(text: &str) -> Result<Theme> {
+        let file: ThemeFile = toml::from_str(text)?;
+        let mut theme = match file.preset.as_deref() {
+            None | Some("dark") => Theme::dark(),
+            Some("light") => Theme::light(),
+            Some(other) => anyhow::bail!("Unknown preset '{}'", other),
+        };
+
+        let overrides = [
+            (&file.title, &mut theme.title),
+            (&file.highlight, &mut theme.highlight),
+            (&file.messages, &mut theme.messages),
+            (&file.border, &mut theme.border),
+            (&file.added, &mut theme.added),
+            (&file.removed, &mut theme.removed),
+            (&file.text, &mut theme.text),
+            (&file.accent, &mut theme.accent),
+            (&file.merge, &mut theme.merge),
+            (&file.marked, &mut theme.marked),
+            (&file.warning, &mut theme.warning),
+            (&file.graph, &mut theme.graph),
+        ];
+        for (value, color) in overrides {
+            if let Some(value) = value {
+                *color = parse_color(value)?;
+            }
+        }
+
+        Ok(theme)
+    }
+}
+
+/// `$XDG_CONFIG_HOME/rugit/theme.toml`, defaulting to `~/.config`.
+fn config_path() -> Option<PathBuf> {
+    Some(config_dir()?.join("theme.toml"))
+}
+
+/// Parses a color name ("light-blue"), a palette index ("208") or "#rrggbb".
+fn parse_color(value: &str) -> Result<Color> {
+    if let Some(hex) = value.strip_prefix('#') {
+        if hex.len() == 6 {
+            if let Ok(rgb) = u32::from_str_radix(hex, 16) {
+                return Ok(Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8));
+            }
+        }
+        anyhow::bail!("Invalid hex color '{}'", value);
+    }
+    if let Ok(index) = value.parse::<u8>() {
+        return Ok(Color::Indexed(index));
+    }
+
+    let color = match value
+        .to_ascii_lowercase()
+        .replace(['-', '_', ' '], "")
+        .as_str()
+    {
+        "reset" | "default" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => anyhow::bail!("Unknown color '{}'", value),
+    };
+    Ok(color)
+}
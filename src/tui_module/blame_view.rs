@@ -0,0 +1,118 @@
+use crate::git_utils::{blame_file, BlameLine};
+use crate::theme::Theme;
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use git2::Repository as GitRepo;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::Style,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+pub struct BlameView {
+    pub path: Option<String>,
+    pub lines: Vec<BlameLine>,
+    pub error: Option<String>,
+    pub scroll: u16,
+}
+
+impl BlameView {
+    pub fn new() -> BlameView {
+        BlameView {
+            path: None,
+            lines: vec![],
+            error: None,
+            scroll: 0,
+        }
+    }
+
+    /// Loads blame for `path`. Failures such as binary files are shown in
+    /// place of the content rather than reported as errors.
+    pub fn open(&mut self, path: &str, repo: &GitRepo) {
+        self.path = Some(path.to_string());
+        self.scroll = 0;
+        match blame_file(repo, path) {
+            Ok(lines) => {
+                self.lines = lines;
+                self.error = None;
+            }
+            Err(e) => {
+                self.lines.clear();
+                self.error = Some(e.to_string());
+            }
+        }
+    }
+
+    pub fn render<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect, theme: &Theme) {
+        let title = match &self.path {
+            Some(path) => format!("Blame: {}", path),
+            None => "Blame".to_string(),
+        };
+
+        let text: Vec<Spans> = match &self.error {
+            Some(error) => vec![Spans::from(error.clone())],
+            None => {
+                let author_width = self
+                    .lines
+                    .iter()
+                    .map(|line| line.author.chars().count())
+                    .max()
+                    .unwrap_or(0)
+                    .min(20);
+                self.lines
+                    .iter()
+                    .map(|line| {
+                        let author: String = line.author.chars().take(author_width).collect();
+                        Spans::from(vec![
+                            Span::styled(
+                                format!(
+                                    "{} {:<width$} {} ",
+                                    line.commit,
+                                    author,
+                                    line.date,
+                                    width = author_width
+                                ),
+                                Style::default().fg(theme.accent),
+                            ),
+                            Span::raw("│ "),
+                            Span::raw(line.content.clone()),
+                        ])
+                    })
+                    .collect()
+            }
+        };
+
+        let paragraph = Paragraph::new(text)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(theme.border_style())
+                    .title(title),
+            )
+            .scroll((self.scroll, 0));
+        f.render_widget(paragraph, area);
+    }
+
+    pub fn handle_input(&mut self, key: KeyEvent, _messages: &mut Vec<String>) -> Result<()> {
+        let max_scroll = self.lines.len().saturating_sub(1) as u16;
+        match key.code {
+            KeyCode::Down => {
+                self.scroll = self.scroll.saturating_add(1).min(max_scroll);
+            }
+            KeyCode::Up => {
+                self.scroll = self.scroll.saturating_sub(1);
+            }
+            KeyCode::PageDown => {
+                self.scroll = self.scroll.saturating_add(20).min(max_scroll);
+            }
+            KeyCode::PageUp => {
+                self.scroll = self.scroll.saturating_sub(20);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
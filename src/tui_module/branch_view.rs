@@ -1,14 +1,25 @@
-
-
-use crate::git_utils::{create_branch, delete_branch, switch_branch};
+use crate::clipboard::copy_reporting;
+use crate::git_utils::{
+    branch_ahead_behind, branch_upstream, compare_branches, create_branch, delete_branch,
+    has_uncommitted_changes, merge_branch, rebase_abort, rebase_onto, set_upstream,
+    split_remote_branch, stash_save, switch_branch, unreferenced_head_commits, BranchComparison,
+};
+use crate::keymap::KeyMap;
+use crate::task::Task;
+use crate::theme::Theme;
+use crate::tui_module::confirm_dialog::{ConfirmDialog, DialogResult};
+use crate::tui_module::diff_view::DiffRequest;
+use crate::tui_module::mouse::clicked_row;
+use crate::tui_module::navigation::{scroll_offset, Motion};
 use crate::utils::{print_error, print_info};
 use anyhow::Result;
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
 use git2::{BranchType, Error as GitError, Repository as GitRepo};
+use std::collections::HashMap;
 use tui::{
     backend::Backend,
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::Style,
     widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
     Frame,
 };
@@ -18,6 +29,52 @@ pub struct BranchView {
     pub input_mode: InputMode,
     pub input: String,
     pub selected: usize, // Index of the selected branch
+    pub marked: Option<String>,
+    pub comparison: Option<BranchComparison>,
+    /// Lines scrolled past at the top of the comparison.
+    pub comparison_scroll: u16,
+    /// Combined diff of the compared branches for the app to open.
+    pub diff_requested: Option<DiffRequest>,
+    /// Upstream (`remote/branch`) of the checked-out branch, if any.
+    pub upstream: Option<String>,
+    /// Upstream of every local branch, `None` for those without one.
+    pub upstreams: HashMap<String, Option<String>>,
+    /// Commits ahead of and behind the upstream, for branches that have one.
+    pub ahead_behind: HashMap<String, (usize, usize)>,
+    pub confirm: Option<ConfirmDialog<BranchAction>>,
+    /// Index of the first row drawn, so the selection stays in view.
+    pub offset: usize,
+    /// Where the branch list was last drawn, for mouse clicks.
+    pub list_area: Rect,
+    /// Push or pull for the app to run in the background.
+    pub task: Option<Task>,
+    /// List remote-tracking branches after the local ones.
+    pub show_remotes: bool,
+    /// Index in `items` where the remote-tracking branches start.
+    pub remote_start: usize,
+    /// Set by the first press of the force-push key; only a second press
+    /// straight after it pushes.
+    pub force_push_armed: bool,
+    /// Short id HEAD points at when detached, listed first as its own entry.
+    pub detached: Option<String>,
+}
+
+/// A destructive branch operation waiting for confirmation.
+pub enum BranchAction {
+    Delete(String),
+    DeleteRemote {
+        remote: String,
+        branch: String,
+    },
+    /// Stash local changes, then switch to the branch.
+    StashAndSwitch(String),
+    /// Switch away from a detached HEAD whose commits are on no branch.
+    LeaveDetached(String),
+    /// Push a branch whose outgoing commits were previewed, and track it.
+    Push {
+        remote: String,
+        branch: String,
+    },
 }
 
 #[derive(PartialEq)]
@@ -25,6 +82,9 @@ pub enum InputMode {
     Normal,
     CreatingBranch,
     DeletingBranch,
+    PushingBranch,
+    /// Typing the `remote/branch` the selected branch should track.
+    SettingUpstream(String),
 }
 
 impl BranchView {
@@ -34,25 +94,128 @@ impl BranchView {
             input_mode: InputMode::Normal,
             input: String::new(),
             selected: 0,
+            marked: None,
+            comparison_scroll: 0,
+            diff_requested: None,
+            comparison: None,
+            upstream: None,
+            upstreams: HashMap::new(),
+            ahead_behind: HashMap::new(),
+            confirm: None,
+            offset: 0,
+            list_area: Rect::default(),
+            task: None,
+            show_remotes: false,
+            remote_start: 0,
+            force_push_armed: false,
+            detached: None,
+        }
+    }
+
+    /// Returns the name of the highlighted branch without the HEAD marker;
+    /// `None` on the detached HEAD entry.
+    fn selected_branch(&self) -> Option<String> {
+        if self.detached.is_some() && self.selected == 0 {
+            return None;
+        }
+        self.items
+            .get(self.selected)
+            .map(|item| item.trim_start_matches("* ").trim().to_string())
+    }
+
+    /// The checked-out local branch, if HEAD is on one.
+    fn current_branch(&self) -> Option<String> {
+        if self.detached.is_some() {
+            return None;
+        }
+        self.items
+            .get(..self.remote_start)
+            .and_then(|items| items.iter().find(|item| item.starts_with("* ")))
+            .map(|item| item.trim_start_matches("* ").trim().to_string())
+    }
+
+    /// Returns the highlighted remote-tracking branch, such as `origin/main`.
+    fn selected_remote_branch(&self) -> Option<String> {
+        if self.selected < self.remote_start {
+            return None;
+        }
+        self.selected_branch()
+    }
+
+    fn render_comparison<B: Backend>(
+        &self,
+        f: &mut Frame<B>,
+        area: Rect,
+        cmp: &BranchComparison,
+        theme: &Theme,
+        keys: &KeyMap,
+    ) {
+        let lines = comparison_lines(cmp, keys);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme.border_style())
+            .title("Branch Comparison")
+            .style(Style::default().fg(theme.marked));
+        let paragraph = Paragraph::new(lines.join("\n"))
+            .block(block)
+            .style(Style::default().fg(theme.text))
+            .alignment(tui::layout::Alignment::Left)
+            .scroll((self.comparison_scroll, 0));
+        f.render_widget(paragraph, area);
+    }
+
+    /// Whether keys, `q` included, belong to a prompt or dialog rather than
+    /// the global bindings.
+    pub fn captures_input(&self) -> bool {
+        self.input_mode != InputMode::Normal || self.confirm.is_some()
+    }
+
+    pub fn render<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        area: Rect,
+        theme: &Theme,
+        keys: &KeyMap,
+    ) {
+        self.render_view(f, area, theme, keys);
+        if let Some(dialog) = &self.confirm {
+            dialog.render(f, area, theme);
         }
     }
 
-    pub fn render<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+    fn render_view<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        area: Rect,
+        theme: &Theme,
+        keys: &KeyMap,
+    ) {
+        self.list_area = Rect::default();
         // If in input mode, render the input prompt
         if self.input_mode != InputMode::Normal {
             let block = Block::default()
                 .borders(Borders::ALL)
-                .title(match self.input_mode {
-                    InputMode::CreatingBranch => "Create New Branch",
-                    InputMode::DeletingBranch => "Delete Branch",
-                    _ => "",
+                .border_style(theme.border_style())
+                .title(match &self.input_mode {
+                    InputMode::CreatingBranch => {
+                        "Create New Branch: name [start point]".to_string()
+                    }
+                    InputMode::DeletingBranch => "Delete Branch".to_string(),
+                    InputMode::PushingBranch => "Push and Track: Remote Name".to_string(),
+                    InputMode::SettingUpstream(branch) => {
+                        format!("Upstream of '{}': remote/branch (empty to clear)", branch)
+                    }
+                    _ => String::new(),
                 });
             let paragraph =
                 Paragraph::new(&self.input[..])
                     .block(block)
                     .style(match self.input_mode {
-                        InputMode::CreatingBranch => Style::default().fg(Color::Green),
-                        InputMode::DeletingBranch => Style::default().fg(Color::Red),
+                        InputMode::CreatingBranch => Style::default().fg(theme.added),
+                        InputMode::DeletingBranch => Style::default().fg(theme.removed),
+                        InputMode::PushingBranch | InputMode::SettingUpstream(_) => {
+                            Style::default().fg(theme.added)
+                        }
                         _ => Style::default(),
                     });
             f.render_widget(Clear, area); // Clear the area before rendering the input
@@ -60,44 +223,174 @@ impl BranchView {
             return;
         }
 
+        if let Some(cmp) = &self.comparison {
+            self.render_comparison(f, area, cmp, theme, keys);
+            return;
+        }
+
         // Render the list of branches with the selected item highlighted
+        self.offset = scroll_offset(self.offset, self.selected, area);
         let items: Vec<ListItem> = self
             .items
             .iter()
             .enumerate()
+            .skip(self.offset)
             .map(|(i, item)| {
-                let content = item.clone();
+                let name = item.trim_start_matches("* ").trim();
+                let is_marked = self.marked.as_deref() == Some(name);
+                let mut content = item.clone();
+                if let Some(upstream) = self.upstreams.get(name).filter(|_| i < self.remote_start) {
+                    match upstream {
+                        Some(upstream) => content.push_str(&format!(" → {}", upstream)),
+                        None => content.push_str(" (no upstream)"),
+                    }
+                }
+                if let Some((ahead, behind)) = self.ahead_behind.get(name) {
+                    content.push_str(&format!(" ↑{} ↓{}", ahead, behind));
+                }
+                if is_marked {
+                    content.push_str(" [marked]");
+                }
                 let mut list_item = ListItem::new(content);
+                if i >= self.remote_start {
+                    list_item = list_item.style(Style::default().fg(theme.removed));
+                } else if self.detached.is_some() && i == 0 {
+                    list_item = list_item.style(Style::default().fg(theme.warning));
+                }
+                if is_marked {
+                    list_item = list_item.style(Style::default().fg(theme.marked));
+                }
                 if i == self.selected {
-                    list_item = list_item.style(
-                        Style::default()
-                            .fg(Color::Yellow)
-                            .add_modifier(Modifier::BOLD),
-                    );
+                    list_item = list_item.style(theme.highlight_style());
                 }
                 list_item
             })
             .collect();
+        let mut title = match &self.upstream {
+            Some(upstream) => format!("Branches (tracking {})", upstream),
+            None => "Branches".to_string(),
+        };
+        if self.show_remotes {
+            title.push_str(" + remotes");
+        }
         let list = List::new(items)
-            .block(Block::default().borders(Borders::ALL).title("Branches"))
-            .highlight_style(
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(theme.border_style())
+                    .title(title),
             )
+            .highlight_style(theme.highlight_style())
             .highlight_symbol(">> ");
         f.render_widget(list, area);
+        self.list_area = area;
     }
 
-    pub fn handle_input(&mut self, key: KeyEvent, messages: &mut Vec<String>) -> Result<()> {
+    /// Selects the clicked row, or moves the selection with the scroll wheel.
+    pub fn handle_mouse(&mut self, event: MouseEvent) {
+        if self.confirm.is_some() {
+            return;
+        }
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                self.selected = clicked_row(self.list_area, &event)
+                    .map(|row| row + self.offset)
+                    .filter(|&row| row < self.items.len())
+                    .unwrap_or(self.selected);
+            }
+            MouseEventKind::ScrollDown if self.selected + 1 < self.items.len() => {
+                self.selected += 1;
+            }
+            MouseEventKind::ScrollUp if self.selected > 0 => self.selected -= 1,
+            _ => {}
+        }
+    }
+
+    pub fn handle_input(
+        &mut self,
+        key: KeyEvent,
+        messages: &mut Vec<String>,
+        keys: &KeyMap,
+        repo: &mut GitRepo,
+    ) -> Result<()> {
+        // Any other key in between cancels a force push.
+        let force_push_armed = std::mem::take(&mut self.force_push_armed);
+        if let Some(dialog) = self.confirm.take() {
+            match dialog.handle_input(key) {
+                DialogResult::Open(dialog) => self.confirm = Some(dialog),
+                DialogResult::Confirmed(action) => self.run_action(action, messages, repo),
+                DialogResult::Cancelled => messages.push("Cancelled.".to_string()),
+            }
+            return Ok(());
+        }
+
+        if let Some(cmp) = &self.comparison {
+            match keys.action("branch.compare", &key) {
+                Some("merge") => {
+                    match merge_branch(repo, &cmp.head) {
+                        Ok(_) => messages.push(format!("Merged '{}'.", cmp.head)),
+                        Err(e) => messages.push(format!("Failed to merge: {}", e)),
+                    }
+                    self.comparison = None;
+                    self.update(repo);
+                }
+                Some("rebase") => {
+                    match rebase_onto(repo, &cmp.base) {
+                        Ok(_) => messages.push(format!("Rebased onto '{}'.", cmp.base)),
+                        Err(e) => messages.push(format!("Failed to rebase: {}", e)),
+                    }
+                    self.comparison = None;
+                    self.update(repo);
+                }
+                Some("diff") => {
+                    // What the head branch would bring in, as a pull request shows it.
+                    self.diff_requested = Some(DiffRequest {
+                        old: cmp.merge_base.clone().unwrap_or_else(|| cmp.base.clone()),
+                        new: cmp.head.clone(),
+                        label: format!("{}...{}", cmp.base, cmp.head),
+                    });
+                }
+                _ => match key.code {
+                    KeyCode::Down => {
+                        let max_scroll = comparison_lines(cmp, keys).len().saturating_sub(1) as u16;
+                        self.comparison_scroll = (self.comparison_scroll + 1).min(max_scroll);
+                    }
+                    KeyCode::Up => {
+                        self.comparison_scroll = self.comparison_scroll.saturating_sub(1)
+                    }
+                    KeyCode::Esc => {
+                        self.comparison = None;
+                    }
+                    _ => {}
+                },
+            }
+            return Ok(());
+        }
+
+        if self.input_mode == InputMode::Normal {
+            if let Some(motion) = Motion::from_key(&key, keys) {
+                self.selected = motion.apply(self.selected, self.items.len(), self.list_area);
+                return Ok(());
+            }
+        }
+
         match self.input_mode {
-            InputMode::Normal => match key.code {
-                KeyCode::Char('c') => {
+            InputMode::Normal => match (keys.action("branch", &key), key.code) {
+                (Some("force_push"), _) => self.force_push(force_push_armed, messages, keys, repo),
+                (Some("copy"), _) => {
+                    if let Some(branch) = self.selected_branch() {
+                        copy_reporting(&branch, &format!("branch name '{}'", branch), messages);
+                    }
+                }
+                (Some("create"), _) => {
                     self.input_mode = InputMode::CreatingBranch;
                     self.input.clear();
-                    messages.push("Enter new branch name:".to_string());
+                    messages.push(
+                        "Enter new branch name, optionally followed by a commit, branch or tag to start from:"
+                            .to_string(),
+                    );
                 }
-                KeyCode::Char('d') => {
+                (Some("delete"), _) => {
                     if !self.items.is_empty() {
                         self.input_mode = InputMode::DeletingBranch;
                         self.input.clear();
@@ -106,41 +399,135 @@ impl BranchView {
                         messages.push("No branches available to delete.".to_string());
                     }
                 }
-                KeyCode::Down => {
-                    if self.selected < self.items.len().saturating_sub(1) {
-                        self.selected += 1;
-                    }
+                (Some("remotes"), _) => {
+                    self.show_remotes = !self.show_remotes;
+                    self.update(repo);
                 }
-                KeyCode::Up => {
-                    if self.selected > 0 {
-                        self.selected -= 1;
+                (Some("delete_remote"), _) => match self.selected_remote_branch() {
+                    Some(name) => match split_remote_branch(repo, &name) {
+                        Ok((remote, branch)) => {
+                            self.confirm = Some(ConfirmDialog::new(
+                                format!(
+                                    "Delete '{}' on remote '{}'? This removes it for everyone using the remote.",
+                                    branch, remote
+                                ),
+                                BranchAction::DeleteRemote { remote, branch },
+                            ));
+                        }
+                        Err(e) => messages.push(format!("Failed to delete '{}': {}", name, e)),
+                    },
+                    None => messages.push(format!(
+                        "Select a remote branch to delete it upstream ('{}' lists them).",
+                        keys.key_for("branch.remotes")
+                    )),
+                },
+                (Some("rebase"), _) => {
+                    if let Some(branch) = self.selected_branch() {
+                        match rebase_onto(repo, &branch) {
+                            Ok(_) => messages.push(format!("Rebased onto '{}'.", branch)),
+                            Err(e) => messages.push(format!("Failed to rebase: {}", e)),
+                        }
+                        self.update(repo);
                     }
                 }
-                KeyCode::Enter => {
-                    if !self.items.is_empty() {
-                        let branch_name = self.items[self.selected].trim_start_matches("* ").trim();
-                        match switch_branch(".", branch_name) {
-                            Ok(_) => {
-                                messages.push(format!("Switched to branch '{}'.", branch_name))
+                (Some("push"), _) => {
+                    self.input_mode = InputMode::PushingBranch;
+                    self.input = "origin".to_string();
+                    messages.push("Push the current branch and track it on remote:".to_string());
+                }
+                (Some("upstream"), _) => match self.selected_branch() {
+                    Some(branch) if self.selected < self.remote_start => {
+                        self.input = match self.upstreams.get(&branch) {
+                            Some(Some(upstream)) => upstream.clone(),
+                            _ => format!("origin/{}", branch),
+                        };
+                        self.input_mode = InputMode::SettingUpstream(branch);
+                    }
+                    _ => messages.push("Select a local branch to set its upstream.".to_string()),
+                },
+                (Some("pull"), _) => self.task = Some(Task::Pull { rebase: false }),
+                (Some("pull_rebase"), _) => self.task = Some(Task::Pull { rebase: true }),
+                (Some("abort_rebase"), _) => match rebase_abort(repo) {
+                    Ok(_) => messages.push("Rebase aborted.".to_string()),
+                    Err(e) => messages.push(format!("Failed to abort rebase: {}", e)),
+                },
+                (Some("mark"), _) => {
+                    if let Some(branch) = self.selected_branch() {
+                        match self.marked.take() {
+                            Some(base) if base == branch => {
+                                messages.push(format!("Unmarked branch '{}'.", branch));
+                            }
+                            Some(base) => match compare_branches(repo, &base, &branch) {
+                                Ok(cmp) => {
+                                    self.comparison = Some(cmp);
+                                    self.comparison_scroll = 0;
+                                }
+                                Err(e) => {
+                                    messages.push(format!("Failed to compare branches: {}", e))
+                                }
+                            },
+                            None => {
+                                messages.push(format!(
+                                    "Marked branch '{}'. Select another branch and press '{}' to compare.",
+                                    branch,
+                                    keys.key_for("branch.mark")
+                                ));
+                                self.marked = Some(branch);
                             }
-                            Err(e) => messages.push(format!("Failed to switch branch: {}", e)),
                         }
-                        self.update(); // Refresh the branch list
                     }
                 }
+                (Some("switch"), _) => match self.selected_branch() {
+                    Some(branch_name) => match unreferenced_head_commits(repo) {
+                        Ok(0) => self.switch_to(branch_name, messages, repo),
+                        Ok(count) => {
+                            self.confirm = Some(ConfirmDialog::new(
+                                format!(
+                                    "HEAD is detached with {} commit(s) on no branch. Switching to '{}' leaves them only in the reflog. Switch anyway?",
+                                    count, branch_name
+                                ),
+                                BranchAction::LeaveDetached(branch_name),
+                            ));
+                        }
+                        Err(e) => messages.push(format!("Failed to switch branch: {}", e)),
+                    },
+                    None if self.detached.is_some() && self.selected == 0 => {
+                        messages.push(format!(
+                            "HEAD is already detached here; press '{}' to create a branch at it.",
+                            keys.key_for("branch.create")
+                        ))
+                    }
+                    None => {}
+                },
                 _ => {}
             },
             InputMode::CreatingBranch => match key.code {
                 KeyCode::Enter => {
-                    let branch_name = self.input.trim();
+                    // Names can't contain spaces, so anything after one is
+                    // the start point.
+                    let mut words = self.input.split_whitespace();
+                    let branch_name = words.next().unwrap_or("");
+                    let start_point = words.next();
+                    if words.next().is_some() {
+                        // Left open to fix, rather than dropping the rest.
+                        messages.push(
+                            "Enter a branch name and at most one start point; names can't contain spaces."
+                                .to_string(),
+                        );
+                        return Ok(());
+                    }
                     if branch_name.is_empty() {
                         messages.push("Branch name cannot be empty.".to_string());
                     } else {
-                        match create_branch(".", branch_name) {
-                            Ok(_) => messages.push(format!("Branch '{}' created.", branch_name)),
+                        match create_branch(repo, branch_name, start_point) {
+                            Ok(_) => messages.push(format!(
+                                "Branch '{}' created at {}.",
+                                branch_name,
+                                start_point.unwrap_or("HEAD")
+                            )),
                             Err(e) => messages.push(format!("Failed to create branch: {}", e)),
                         }
-                        self.update(); // Refresh the branch list
+                        self.update(repo); // Refresh the branch list
                     }
                     self.input_mode = InputMode::Normal;
                     self.input.clear();
@@ -164,11 +551,10 @@ impl BranchView {
                     if branch_name.is_empty() {
                         messages.push("Branch name cannot be empty.".to_string());
                     } else {
-                        match delete_branch(".", branch_name) {
-                            Ok(_) => messages.push(format!("Branch '{}' deleted.", branch_name)),
-                            Err(e) => messages.push(format!("Failed to delete branch: {}", e)),
-                        }
-                        self.update(); // Refresh the branch list
+                        self.confirm = Some(ConfirmDialog::new(
+                            format!("Delete branch '{}'? This cannot be undone.", branch_name),
+                            BranchAction::Delete(branch_name.to_string()),
+                        ));
                     }
                     self.input_mode = InputMode::Normal;
                     self.input.clear();
@@ -186,41 +572,305 @@ impl BranchView {
                 }
                 _ => {}
             },
+            InputMode::PushingBranch => match key.code {
+                KeyCode::Enter => {
+                    let remote = self.input.trim().to_string();
+                    match self.current_branch() {
+                        Some(branch) => {
+                            // Shows what would be sent before pushing it.
+                            self.task = Some(Task::PushPreview { remote, branch })
+                        }
+                        None => messages.push("No branch is checked out.".to_string()),
+                    }
+                    self.input_mode = InputMode::Normal;
+                    self.input.clear();
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.input.clear();
+                    messages.push("Push cancelled.".to_string());
+                }
+                KeyCode::Char(c) => {
+                    self.input.push(c);
+                }
+                KeyCode::Backspace => {
+                    self.input.pop();
+                }
+                _ => {}
+            },
+            InputMode::SettingUpstream(ref branch) => match key.code {
+                KeyCode::Enter => {
+                    let branch = branch.clone();
+                    let upstream = self.input.trim();
+                    let upstream = (!upstream.is_empty()).then_some(upstream);
+                    match set_upstream(repo, &branch, upstream) {
+                        Ok(_) => messages.push(match upstream {
+                            Some(upstream) => {
+                                format!("'{}' now tracks '{}'.", branch, upstream)
+                            }
+                            None => format!("'{}' no longer tracks an upstream.", branch),
+                        }),
+                        Err(e) => messages.push(format!("Failed to set upstream: {}", e)),
+                    }
+                    self.input_mode = InputMode::Normal;
+                    self.input.clear();
+                    self.update(repo);
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.input.clear();
+                }
+                KeyCode::Char(c) => {
+                    self.input.push(c);
+                }
+                KeyCode::Backspace => {
+                    self.input.pop();
+                }
+                _ => {}
+            },
         }
         Ok(())
     }
 
-    pub fn update(&mut self) {
+    /// Arms the force push on the first press and starts it on the second,
+    /// since it replaces whatever the remote branch has.
+    fn force_push(
+        &mut self,
+        armed: bool,
+        messages: &mut Vec<String>,
+        keys: &KeyMap,
+        repo: &GitRepo,
+    ) {
+        let Some(branch) = self.current_branch() else {
+            messages.push("No branch is checked out.".to_string());
+            return;
+        };
+        // Push where the branch is tracked, or to origin.
+        let remote = self
+            .upstream
+            .as_deref()
+            .and_then(|upstream| split_remote_branch(repo, upstream).ok())
+            .map_or_else(|| "origin".to_string(), |(remote, _)| remote);
+        if armed {
+            self.task = Some(Task::Push {
+                remote,
+                branch,
+                set_upstream: false,
+                force: true,
+            });
+        } else {
+            self.force_push_armed = true;
+            messages.push(format!(
+                "Press '{}' again to FORCE-push '{}' to {}. This overwrites the remote branch's history.",
+                keys.key_for("branch.force_push"),
+                branch,
+                remote
+            ));
+        }
+    }
+
+    /// Asks to confirm a push once its preview lists the outgoing commits.
+    pub fn confirm_push(
+        &mut self,
+        remote: String,
+        branch: String,
+        commits: Vec<String>,
+        messages: &mut Vec<String>,
+    ) {
+        if commits.is_empty() {
+            messages.push(format!(
+                "Everything up to date: {} already has every commit on '{}'.",
+                remote, branch
+            ));
+            return;
+        }
+        self.confirm = Some(
+            ConfirmDialog::new(
+                format!(
+                    "Push {} commit(s) from '{}' to {}?",
+                    commits.len(),
+                    branch,
+                    remote
+                ),
+                BranchAction::Push { remote, branch },
+            )
+            .with_details(commits),
+        );
+    }
+
+    /// Switches to `branch_name`, first offering to stash local changes when
+    /// it is a local branch.
+    fn switch_to(&mut self, branch_name: String, messages: &mut Vec<String>, repo: &GitRepo) {
+        if self.selected < self.remote_start && has_uncommitted_changes(repo).unwrap_or(false) {
+            self.confirm = Some(ConfirmDialog::new(
+                format!(
+                    "You have uncommitted changes. Stash them and switch to '{}'?",
+                    branch_name
+                ),
+                BranchAction::StashAndSwitch(branch_name),
+            ));
+            return;
+        }
+        match switch_branch(repo, &branch_name, false) {
+            Ok(_) => messages.push(format!("Switched to branch '{}'.", branch_name)),
+            Err(e) => messages.push(format!("Failed to switch branch: {}", e)),
+        }
+        self.update(repo); // Refresh the branch list
+    }
+
+    fn run_action(&mut self, action: BranchAction, messages: &mut Vec<String>, repo: &mut GitRepo) {
+        match action {
+            BranchAction::Delete(branch_name) => {
+                match delete_branch(repo, &branch_name) {
+                    Ok(_) => messages.push(format!("Branch '{}' deleted.", branch_name)),
+                    Err(e) => messages.push(format!("Failed to delete branch: {}", e)),
+                }
+                self.update(repo); // Refresh the branch list
+            }
+            BranchAction::DeleteRemote { remote, branch } => {
+                self.task = Some(Task::DeleteRemoteBranch { remote, branch });
+            }
+            BranchAction::Push { remote, branch } => {
+                self.task = Some(Task::Push {
+                    remote,
+                    branch,
+                    set_upstream: true,
+                    force: false,
+                });
+            }
+            BranchAction::LeaveDetached(branch_name) => self.switch_to(branch_name, messages, repo),
+            BranchAction::StashAndSwitch(branch_name) => {
+                let message = format!("Before switching to '{}'", branch_name);
+                if let Err(e) = stash_save(repo, Some(&message)) {
+                    messages.push(format!("Failed to stash changes: {}", e));
+                    return;
+                }
+                // A new stash is always the newest entry.
+                match switch_branch(repo, &branch_name, false) {
+                    Ok(_) => messages.push(format!(
+                        "Stashed your changes as stash@{{0}} and switched to '{}'. Pop it from the Stash view to get them back.",
+                        branch_name
+                    )),
+                    Err(e) => messages.push(format!(
+                        "Stashed your changes as stash@{{0}}, but failed to switch branch: {}",
+                        e
+                    )),
+                }
+                self.update(repo);
+            }
+        }
+    }
+
+    pub fn update(&mut self, repo: &GitRepo) {
         self.items.clear();
-        match GitRepo::open(".") {
-            Ok(repo) => match repo.branches(Some(BranchType::Local)) {
-                Ok(branches) => {
-                    for branch in branches {
-                        match branch {
-                            Ok((b, _)) => {
-                                let name = match b.name() {
-                                    Ok(Some(n)) => n.to_string(),
-                                    _ => "Unnamed".to_string(),
-                                };
-                                if b.is_head() {
-                                    self.items.push(format!("* {}", name));
-                                } else {
-                                    self.items.push(format!("  {}", name));
-                                }
+        self.upstreams.clear();
+        self.ahead_behind.clear();
+        self.upstream = None;
+        self.detached = repo
+            .head_detached()
+            .unwrap_or(false)
+            .then(|| repo.head().ok()?.target())
+            .flatten()
+            .map(|oid| oid.to_string()[..7].to_string());
+        if let Some(short) = &self.detached {
+            self.items.push(format!("* (detached @ {})", short));
+        }
+        match repo.branches(Some(BranchType::Local)) {
+            Ok(branches) => {
+                for branch in branches {
+                    match branch {
+                        Ok((b, _)) => {
+                            let name = match b.name() {
+                                Ok(Some(n)) => n.to_string(),
+                                _ => "Unnamed".to_string(),
+                            };
+                            if let Ok(Some(counts)) = branch_ahead_behind(repo, &name) {
+                                self.ahead_behind.insert(name.clone(), counts);
                             }
-                            Err(e) => {
-                                self.items.push(format!("Error iterating branches: {}", e));
+                            let upstream = branch_upstream(repo, &name).ok().flatten();
+                            if b.is_head() {
+                                self.upstream = upstream.clone();
+                                self.items.push(format!("* {}", name));
+                            } else {
+                                self.items.push(format!("  {}", name));
                             }
+                            self.upstreams.insert(name, upstream);
+                        }
+                        Err(e) => {
+                            self.items.push(format!("Error iterating branches: {}", e));
                         }
                     }
                 }
-                Err(e) => {
-                    self.items.push(format!("Error retrieving branches: {}", e));
-                }
-            },
+            }
             Err(e) => {
-                self.items.push(format!("Error opening repository: {}", e));
+                self.items.push(format!("Error retrieving branches: {}", e));
             }
         }
+        self.remote_start = self.items.len();
+        if self.show_remotes {
+            self.push_remote_branches(repo);
+        }
+        if self.selected >= self.items.len() {
+            self.selected = self.items.len().saturating_sub(1);
+        }
     }
+
+    fn push_remote_branches(&mut self, repo: &GitRepo) {
+        let branches = match repo.branches(Some(BranchType::Remote)) {
+            Ok(branches) => branches,
+            Err(e) => {
+                self.items
+                    .push(format!("Error retrieving remote branches: {}", e));
+                return;
+            }
+        };
+        for (branch, _) in branches.flatten() {
+            // `origin/HEAD` only points at another remote branch.
+            match branch.name() {
+                Ok(Some(name)) if !name.ends_with("/HEAD") => {
+                    self.items.push(format!("  {}", name));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// The text of a branch comparison, one entry per line.
+fn comparison_lines(cmp: &BranchComparison, keys: &KeyMap) -> Vec<String> {
+    let mut lines = vec![
+        format!("Base: {}    Compare: {}", cmp.base, cmp.head),
+        format!(
+            "Merge base: {}",
+            cmp.merge_base
+                .as_deref()
+                .unwrap_or("none (unrelated histories)")
+        ),
+        format!(
+            "'{}' is {} ahead, {} behind '{}'",
+            cmp.head,
+            cmp.ahead.len(),
+            cmp.behind.len(),
+            cmp.base
+        ),
+        "".to_string(),
+        format!("Commits only in '{}':", cmp.head),
+    ];
+    lines.extend(cmp.ahead.iter().map(|c| format!("  {}", c)));
+    lines.push("".to_string());
+    lines.push(format!("Commits only in '{}':", cmp.base));
+    lines.extend(cmp.behind.iter().map(|c| format!("  {}", c)));
+    lines.push("".to_string());
+    lines.push(format!("Files changed ({}):", cmp.files.len()));
+    lines.extend(cmp.files.iter().map(|file| format!("  {}", file)));
+    lines.push("".to_string());
+    lines.push(format!(
+        "Press '{}' to see the combined diff, '{}' to merge '{}' into the current branch, '{}' to rebase the current branch onto '{}', 'Esc' to close.",
+        keys.key_for("branch.compare.diff"),
+        keys.key_for("branch.compare.merge"),
+        cmp.head,
+        keys.key_for("branch.compare.rebase"),
+        cmp.base
+    ));
+    lines
 }
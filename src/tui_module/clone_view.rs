@@ -0,0 +1,201 @@
+use crate::git_utils::{clone_repo, default_callbacks, Transfer};
+use crate::theme::Theme;
+use crate::tui_module::progress::render_transfer;
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use std::{
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::Instant,
+};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::Style,
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+/// Progress reported by the background clone thread.
+enum CloneEvent {
+    Progress(Transfer),
+    Done(Result<()>),
+}
+
+pub struct CloneView {
+    pub repo_path: String,
+    pub input_mode: InputMode,
+    pub input: String,
+    pub url: String,
+    pub destination: String,
+    pub status: String,
+    /// Destination of a clone that finished successfully, for the app to open.
+    pub finished: Option<String>,
+    receiver: Option<Receiver<CloneEvent>>,
+    /// Latest progress of the running clone, drawn as a gauge.
+    transfer: Option<Transfer>,
+    started: Instant,
+}
+
+#[derive(PartialEq)]
+pub enum InputMode {
+    EnteringUrl,
+    EnteringDestination,
+    Cloning,
+}
+
+impl CloneView {
+    pub fn new(repo_path: String) -> CloneView {
+        CloneView {
+            repo_path,
+            input_mode: InputMode::EnteringUrl,
+            input: String::new(),
+            url: String::new(),
+            destination: String::new(),
+            status: String::new(),
+            finished: None,
+            receiver: None,
+            transfer: None,
+            started: Instant::now(),
+        }
+    }
+
+    fn start_clone(&mut self, dest: String) {
+        let (tx, rx) = mpsc::channel();
+        let url = self.url.clone();
+        self.destination = dest.clone();
+        thread::spawn(move || {
+            let progress_tx = tx.clone();
+            let mut callbacks = default_callbacks();
+            callbacks.transfer_progress(move |progress| {
+                let _ = progress_tx.send(CloneEvent::Progress(Transfer::from(progress)));
+                true
+            });
+            let result = clone_repo(&url, &dest, callbacks);
+            let _ = tx.send(CloneEvent::Done(result));
+        });
+
+        self.receiver = Some(rx);
+        self.transfer = None;
+        self.started = Instant::now();
+        self.input_mode = InputMode::Cloning;
+        self.status = format!("Cloning '{}'...", self.url);
+    }
+
+    /// Whether keys, `q` included, belong to a prompt or dialog rather than
+    /// the global bindings.
+    pub fn captures_input(&self) -> bool {
+        self.input_mode != InputMode::Cloning
+    }
+
+    pub fn render<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect, theme: &Theme) {
+        let (title, body) = match self.input_mode {
+            InputMode::EnteringUrl => ("Clone: Repository URL", self.input.clone()),
+            InputMode::EnteringDestination => ("Clone: Destination Directory", self.input.clone()),
+            InputMode::Cloning => ("Cloning", String::new()),
+        };
+
+        let mut lines = vec![
+            format!("No git repository found at '{}'.", self.repo_path),
+            "Enter a URL to clone, or press Esc to go back.".to_string(),
+            "".to_string(),
+        ];
+        if self.input_mode != InputMode::Cloning {
+            lines.push(format!("> {}", body));
+        }
+        if !self.status.is_empty() {
+            lines.push("".to_string());
+            lines.push(self.status.clone());
+        }
+
+        let paragraph = Paragraph::new(lines.join("\n"))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(theme.border_style())
+                    .title(title),
+            )
+            .style(Style::default().fg(theme.added))
+            .wrap(Wrap { trim: false });
+        f.render_widget(paragraph, area);
+
+        if let Some(transfer) = &self.transfer {
+            render_transfer(
+                f,
+                area,
+                "Receiving objects",
+                transfer,
+                self.started.elapsed(),
+                theme,
+            );
+        }
+    }
+
+    pub fn handle_input(&mut self, key: KeyEvent, messages: &mut Vec<String>) -> Result<()> {
+        match self.input_mode {
+            InputMode::Cloning => {}
+            InputMode::EnteringUrl | InputMode::EnteringDestination => match key.code {
+                KeyCode::Enter => {
+                    let value = self.input.trim().to_string();
+                    if value.is_empty() {
+                        messages.push("Input cannot be empty.".to_string());
+                    } else if self.input_mode == InputMode::EnteringUrl {
+                        self.url = value;
+                        self.input = self.repo_path.clone();
+                        self.input_mode = InputMode::EnteringDestination;
+                        messages.push("Enter the destination directory:".to_string());
+                    } else {
+                        self.input.clear();
+                        self.start_clone(value);
+                    }
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::EnteringUrl;
+                    self.input.clear();
+                    self.status.clear();
+                }
+                KeyCode::Char(c) => {
+                    self.input.push(c);
+                }
+                KeyCode::Backspace => {
+                    self.input.pop();
+                }
+                _ => {}
+            },
+        }
+        Ok(())
+    }
+
+    /// Drains progress from the clone thread.
+    pub fn update(&mut self) {
+        let Some(receiver) = &self.receiver else {
+            return;
+        };
+        loop {
+            match receiver.try_recv() {
+                Ok(CloneEvent::Progress(transfer)) => self.transfer = Some(transfer),
+                Ok(CloneEvent::Done(result)) => {
+                    self.receiver = None;
+                    self.transfer = None;
+                    self.input_mode = InputMode::EnteringUrl;
+                    match result {
+                        Ok(_) => {
+                            self.status = "Clone complete.".to_string();
+                            self.finished = Some(self.destination.clone());
+                        }
+                        Err(e) => self.status = format!("Clone failed: {:#}", e),
+                    }
+                    return;
+                }
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    self.receiver = None;
+                    self.transfer = None;
+                    self.input_mode = InputMode::EnteringUrl;
+                    self.status = "Clone failed: worker thread exited.".to_string();
+                    return;
+                }
+            }
+        }
+    }
+}
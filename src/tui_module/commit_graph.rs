@@ -0,0 +1,119 @@
+use git2::Oid;
+
+/// Assigns commits to lanes as a topologically sorted walk proceeds and
+/// draws the `* | |` gutter for each one.
+#[derive(Default)]
+pub struct CommitGraph {
+    /// The commit each lane is waiting for; `None` for a free lane.
+    lanes: Vec<Option<Oid>>,
+}
+
+impl CommitGraph {
+    /// Places `id` and returns its row of the gutter, then makes room for
+    /// its `parents` in the lanes below.
+    pub fn row(&mut self, id: Oid, parents: &[Oid]) -> String {
+        let column = match self.lanes.iter().position(|lane| *lane == Some(id)) {
+            Some(column) => column,
+            // A branch tip nothing has pointed to yet.
+            None => self.free_lane(),
+        };
+
+        let mut cells: Vec<char> = self
+            .lanes
+            .iter()
+            .enumerate()
+            .map(|(i, lane)| match lane {
+                _ if i == column => '*',
+                // Other lanes waiting for this commit join it here.
+                Some(lane) if *lane == id && i < column => '\\',
+                Some(lane) if *lane == id => '/',
+                Some(_) => '|',
+                None => ' ',
+            })
+            .collect();
+        for lane in self.lanes.iter_mut() {
+            if *lane == Some(id) {
+                *lane = None;
+            }
+        }
+
+        self.lanes[column] = parents.first().copied();
+        for parent in parents.iter().skip(1) {
+            if self.lanes.contains(&Some(*parent)) {
+                continue;
+            }
+            let lane = self.free_lane();
+            self.lanes[lane] = Some(*parent);
+            // The merged branch forks off to the right of the commit.
+            if lane >= cells.len() {
+                cells.resize(lane + 1, ' ');
+            }
+            if cells[lane] == ' ' {
+                cells[lane] = if lane > column { '\\' } else { '/' };
+            }
+        }
+        while self.lanes.last() == Some(&None) {
+            self.lanes.pop();
+        }
+
+        let row: Vec<String> = cells.iter().map(char::to_string).collect();
+        row.join(" ").trim_end().to_string()
+    }
+
+    /// Index of the first free lane, adding one if all are taken.
+    fn free_lane(&mut self) -> usize {
+        match self.lanes.iter().position(Option::is_none) {
+            Some(lane) => lane,
+            None => {
+                self.lanes.push(None);
+                self.lanes.len() - 1
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(n: u8) -> Oid {
+        Oid::from_bytes(&[n; 20]).unwrap()
+    }
+
+    #[test]
+    fn a_linear_history_stays_in_one_lane() {
+        let mut graph = CommitGraph::default();
+        assert_eq!(graph.row(id(3), &[id(2)]), "*");
+        assert_eq!(graph.row(id(2), &[id(1)]), "*");
+        assert_eq!(graph.row(id(1), &[]), "*");
+        assert!(graph.lanes.is_empty());
+    }
+
+    #[test]
+    fn a_merge_forks_a_lane_that_joins_at_the_common_parent() {
+        let mut graph = CommitGraph::default();
+        assert_eq!(graph.row(id(4), &[id(2), id(3)]), "* \\");
+        assert_eq!(graph.row(id(2), &[id(1)]), "* |");
+        assert_eq!(graph.row(id(3), &[id(1)]), "| *");
+        assert_eq!(graph.row(id(1), &[]), "* /");
+        assert!(graph.lanes.is_empty());
+    }
+
+    #[test]
+    fn a_second_branch_tip_opens_a_new_lane() {
+        let mut graph = CommitGraph::default();
+        assert_eq!(graph.row(id(3), &[id(1)]), "*");
+        assert_eq!(graph.row(id(2), &[id(1)]), "| *");
+        assert_eq!(graph.row(id(1), &[]), "* /");
+    }
+
+    #[test]
+    fn a_freed_lane_is_reused() {
+        let mut graph = CommitGraph::default();
+        graph.row(id(5), &[id(3), id(4)]);
+        // The merged branch ends, freeing its lane for the next tip.
+        assert_eq!(graph.row(id(4), &[]), "| *");
+        assert_eq!(graph.row(id(6), &[id(3)]), "| *");
+        assert_eq!(graph.row(id(3), &[]), "* /");
+    }
+}
@@ -1,101 +1,466 @@
-
-
-use crate::git_utils::commit_changes;
+use crate::git_utils::{
+    amend_commit, commit_all, commit_changes, commit_changes_with_signoff, commit_signed,
+    has_uncommitted_changes, head_commit_message, reword_head, sign_off, staged_summary,
+    SigningError, StagedSummary,
+};
+use crate::keymap::KeyMap;
+use crate::theme::Theme;
 use crate::utils::{print_error, print_info};
 use anyhow::Result;
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use git2::Repository as GitRepo;
 use tui::{
     backend::Backend,
-    layout::Rect,
-    style::{Color, Modifier, Style},
-    widgets::{Block, Borders, Clear, Paragraph},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
     Frame,
 };
 #[derive(Debug)]
 pub struct CommitView {
     pub input_mode: InputMode,
     pub commit_message: String,
+    /// Byte index of the cursor within `commit_message`.
+    pub cursor: usize,
+    /// Append a `Signed-off-by` trailer when committing.
+    pub signoff: bool,
+    /// GPG-sign new commits; defaults to the `commit.gpgsign` config.
+    pub gpg_sign: bool,
+    /// Stage changes to tracked files when committing, like `git commit -a`.
+    pub all: bool,
+    /// The staged changes, refreshed by `update`.
+    pub staged: Result<StagedSummary>,
+    /// Set when a reword started from the log ends, for the app to go back.
+    pub reword_finished: bool,
 }
 
 #[derive(PartialEq, Debug)]
 pub enum InputMode {
     Normal,
     WritingCommit,
+    Amending,
+    /// Editing HEAD's message only, leaving staged changes out.
+    Rewording,
 }
 
 impl CommitView {
-    pub fn new() -> CommitView {
+    /// `gpg_sign` sets the initial signing toggle, normally from
+    /// `gpg_sign_enabled`.
+    pub fn new(gpg_sign: bool) -> CommitView {
         CommitView {
+            gpg_sign,
             input_mode: InputMode::Normal,
             commit_message: String::new(),
+            cursor: 0,
+            signoff: false,
+            all: false,
+            staged: Ok(StagedSummary {
+                files: vec![],
+                insertions: 0,
+                deletions: 0,
+            }),
+            reword_finished: false,
+        }
+    }
+
+    /// Opens the editor on HEAD's message, to save it with Ctrl+S. Returns
+    /// whether there was a commit to reword.
+    pub fn start_reword(&mut self, messages: &mut Vec<String>, repo: &GitRepo) -> bool {
+        match head_commit_message(repo) {
+            Ok(message) => {
+                self.input_mode = InputMode::Rewording;
+                self.commit_message = message.trim_end().to_string();
+                self.cursor = self.commit_message.len();
+                messages.push("Edit the message and press Ctrl+S to reword.".to_string());
+                true
+            }
+            Err(e) => {
+                messages.push(format!("Cannot reword: {}", e));
+                false
+            }
         }
     }
 
-    pub fn render<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+    pub fn update(&mut self, repo: &GitRepo) {
+        self.staged = staged_summary(repo);
+    }
+
+    /// Whether keys, `q` included, belong to a prompt or dialog rather than
+    /// the global bindings.
+    pub fn captures_input(&self) -> bool {
+        self.input_mode != InputMode::Normal
+    }
+
+    pub fn render<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        area: Rect,
+        theme: &Theme,
+        keys: &KeyMap,
+    ) {
         match self.input_mode {
             InputMode::Normal => {
-                let block = Block::default().borders(Borders::ALL).title("Commit");
-                let paragraph = Paragraph::new("Press 'c' to write a commit message.")
-                    .block(block)
-                    .style(Style::default().fg(Color::Yellow))
-                    .alignment(tui::layout::Alignment::Left);
-                f.render_widget(paragraph, area);
+                let block = Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(theme.border_style())
+                    .title("Commit");
+                let paragraph = Paragraph::new(format!(
+                    "Press '{}' to write a commit message, '{}' to commit all tracked changes\n\
+                     or '{}' to amend the last commit.\n\
+                     Sign-off: {} (press '{}' to toggle)\n\
+                     GPG signing: {} (press '{}' to toggle)",
+                    keys.key_for("commit.write"),
+                    keys.key_for("commit.all"),
+                    keys.key_for("commit.amend"),
+                    if self.signoff { "on" } else { "off" },
+                    keys.key_for("commit.signoff"),
+                    if self.gpg_sign { "on" } else { "off" },
+                    keys.key_for("commit.gpg_sign")
+                ))
+                .block(block)
+                .style(Style::default().fg(theme.title))
+                .alignment(tui::layout::Alignment::Left);
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(6), Constraint::Min(0)].as_ref())
+                    .split(area);
+                f.render_widget(paragraph, chunks[0]);
+                self.render_staged(f, chunks[1], theme);
             }
-            InputMode::WritingCommit => {
+            InputMode::WritingCommit | InputMode::Amending | InputMode::Rewording => {
                 let block = Block::default()
                     .borders(Borders::ALL)
-                    .title("Enter Commit Message")
-                    .style(Style::default().fg(Color::Green));
+                    .border_style(theme.border_style())
+                    .title(format!(
+                        "{}{}{}{}",
+                        match self.input_mode {
+                            InputMode::Amending =>
+                                "Amend Commit Message (Ctrl+S to amend, Esc to cancel)",
+                            InputMode::Rewording =>
+                                "Reword HEAD Commit Message (Ctrl+S to save, Esc to cancel)",
+                            _ => "Enter Commit Message (Ctrl+S to commit, Esc to cancel)",
+                        },
+                        if self.all { " [all tracked]" } else { "" },
+                        if self.signoff && self.input_mode != InputMode::Rewording {
+                            " [signed-off]"
+                        } else {
+                            ""
+                        },
+                        if self.gpg_sign && self.input_mode == InputMode::WritingCommit {
+                            " [gpg]"
+                        } else {
+                            ""
+                        }
+                    ))
+                    .style(Style::default().fg(theme.added));
                 let paragraph = Paragraph::new(&self.commit_message[..])
                     .block(block)
-                    .style(Style::default().fg(Color::White))
-                    .alignment(tui::layout::Alignment::Left);
+                    .style(Style::default().fg(theme.text))
+                    .alignment(tui::layout::Alignment::Left)
+                    .wrap(Wrap { trim: false });
                 f.render_widget(Clear, area); // Clear the area before rendering the input
                 f.render_widget(paragraph, area);
+
+                let (row, col) = self.cursor_position(area.width.saturating_sub(2));
+                f.set_cursor(area.x + 1 + col, area.y + 1 + row);
+            }
+        }
+    }
+
+    /// Lists the staged files with an insertions/deletions summary.
+    fn render_staged<B: Backend>(&self, f: &mut Frame<B>, area: Rect, theme: &Theme) {
+        let (title, items) = match &self.staged {
+            Ok(staged) if staged.files.is_empty() => (
+                "Nothing staged".to_string(),
+                vec![ListItem::new(
+                    "Stage files in the Status view before committing.",
+                )],
+            ),
+            Ok(staged) => (
+                format!(
+                    "Staged: {} file(s), +{} -{}",
+                    staged.files.len(),
+                    staged.insertions,
+                    staged.deletions
+                ),
+                staged
+                    .files
+                    .iter()
+                    .map(|file| ListItem::new(file.as_str()))
+                    .collect(),
+            ),
+            Err(e) => (
+                "Staged".to_string(),
+                vec![ListItem::new(format!("Error reading the index: {}", e))
+                    .style(Style::default().fg(theme.removed))],
+            ),
+        };
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border_style())
+                .title(title),
+        );
+        f.render_widget(list, area);
+    }
+
+    /// Applies an editing key to the message at the cursor.
+    fn edit_message(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char(c) => {
+                self.commit_message.insert(self.cursor, c);
+                self.cursor += c.len_utf8();
+            }
+            KeyCode::Enter => {
+                self.commit_message.insert(self.cursor, '\n');
+                self.cursor += 1;
+            }
+            KeyCode::Backspace => {
+                if let Some(c) = self.commit_message[..self.cursor].chars().next_back() {
+                    self.cursor -= c.len_utf8();
+                    self.commit_message.remove(self.cursor);
+                }
+            }
+            KeyCode::Delete if self.cursor < self.commit_message.len() => {
+                self.commit_message.remove(self.cursor);
+            }
+            KeyCode::Left => {
+                if let Some(c) = self.commit_message[..self.cursor].chars().next_back() {
+                    self.cursor -= c.len_utf8();
+                }
+            }
+            KeyCode::Right => {
+                if let Some(c) = self.commit_message[self.cursor..].chars().next() {
+                    self.cursor += c.len_utf8();
+                }
+            }
+            KeyCode::Home => {
+                self.cursor = self.commit_message[..self.cursor]
+                    .rfind('\n')
+                    .map_or(0, |i| i + 1);
+            }
+            KeyCode::End => {
+                self.cursor += self.commit_message[self.cursor..]
+                    .find('\n')
+                    .unwrap_or(self.commit_message.len() - self.cursor);
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the cursor's (row, column) inside a text area `width` cells
+    /// wide, counting long lines as wrapping at the edge.
+    fn cursor_position(&self, width: u16) -> (u16, u16) {
+        let width = width.max(1) as usize;
+        let before = &self.commit_message[..self.cursor];
+        let mut row = 0;
+        let mut lines = before.split('\n').peekable();
+        while let Some(line) = lines.next() {
+            let len = line.chars().count();
+            if lines.peek().is_some() {
+                row += len / width + 1;
+            } else {
+                return ((row + len / width) as u16, (len % width) as u16);
             }
         }
+        (row as u16, 0)
     }
 
-    pub fn handle_input(&mut self, key: KeyEvent, messages: &mut Vec<String>) -> Result<()> {
+    pub fn handle_input(
+        &mut self,
+        key: KeyEvent,
+        messages: &mut Vec<String>,
+        keys: &KeyMap,
+        repo: &GitRepo,
+    ) -> Result<()> {
         match self.input_mode {
-            InputMode::Normal => match key.code {
-                KeyCode::Char('c') => {
+            InputMode::Normal => match (keys.action("commit", &key), key.code) {
+                (Some("write"), _) => {
+                    self.update(repo);
+                    if matches!(&self.staged, Ok(staged) if staged.files.is_empty()) {
+                        messages.push("Nothing staged.".to_string());
+                        return Ok(());
+                    }
+                    self.input_mode = InputMode::WritingCommit;
+                    self.all = false;
+                    self.commit_message.clear();
+                    self.cursor = 0;
+                    messages.push(
+                        "Enter your commit message below. Enter adds a line, Ctrl+S commits."
+                            .to_string(),
+                    );
+                }
+                (Some("all"), _) => {
+                    if !has_uncommitted_changes(repo).unwrap_or(true) {
+                        messages.push("No changes to tracked files.".to_string());
+                        return Ok(());
+                    }
                     self.input_mode = InputMode::WritingCommit;
+                    self.all = true;
                     self.commit_message.clear();
-                    messages.push("Enter your commit message below.".to_string());
+                    self.cursor = 0;
+                    messages.push(
+                        "Enter your commit message below. Ctrl+S stages all tracked changes and commits."
+                            .to_string(),
+                    );
+                }
+                (Some("amend"), _) => match head_commit_message(repo) {
+                    Ok(message) => {
+                        self.input_mode = InputMode::Amending;
+                        self.commit_message = message.trim_end().to_string();
+                        self.cursor = self.commit_message.len();
+                        messages.push("Edit the message and press Ctrl+S to amend.".to_string());
+                    }
+                    Err(e) => messages.push(format!("Cannot amend: {}", e)),
+                },
+                (Some("gpg_sign"), _) => {
+                    self.gpg_sign = !self.gpg_sign;
+                    messages.push(format!(
+                        "GPG signing {}.",
+                        if self.gpg_sign { "enabled" } else { "disabled" }
+                    ));
+                }
+                (Some("signoff"), _) => {
+                    self.signoff = !self.signoff;
+                    messages.push(format!(
+                        "Sign-off {}.",
+                        if self.signoff { "enabled" } else { "disabled" }
+                    ));
                 }
                 _ => {}
             },
             InputMode::WritingCommit => match key.code {
-                KeyCode::Enter => {
+                KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    let message = self.commit_message.trim();
+                    if message.is_empty() {
+                        messages.push("Commit message cannot be empty.".to_string());
+                    } else {
+                        // Commits what is staged, signed and signed off as set.
+                        let commit = |repo: &GitRepo| match (self.gpg_sign, self.signoff) {
+                            (true, true) => sign_off(repo, message)
+                                .and_then(|message| commit_signed(repo, &message, None)),
+                            (true, false) => commit_signed(repo, message, None),
+                            (false, true) => commit_changes_with_signoff(repo, message),
+                            (false, false) => commit_changes(repo, message),
+                        };
+                        // Either returns how many files were staged for the commit.
+                        let result = if self.all {
+                            commit_all(repo, commit)
+                        } else {
+                            commit(repo).map(|_| 0)
+                        };
+                        let committed = result.is_ok();
+                        match result {
+                            Ok(staged) if self.all => messages.push(format!(
+                                "Staged {} file(s) and committed with message: '{}'",
+                                staged,
+                                message.lines().next().unwrap_or("")
+                            )),
+                            Ok(_) => messages.push(format!(
+                                "Committed with message: '{}'",
+                                message.lines().next().unwrap_or("")
+                            )),
+                            Err(e) if e.is::<SigningError>() => {
+                                messages.push(format!("Failed to sign commit: {}", e))
+                            }
+                            Err(e) => {
+                                messages.push(format!("Failed to commit: {}", e));
+                                identity_hint(messages, repo);
+                            }
+                        }
+                        // A failed commit keeps the editor open so the message can be retried.
+                        if committed {
+                            self.input_mode = InputMode::Normal;
+                            self.commit_message.clear();
+                            self.cursor = 0;
+                        }
+                    }
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.commit_message.clear();
+                    self.cursor = 0;
+                    messages.push("Commit cancelled.".to_string());
+                }
+                _ => self.edit_message(key),
+            },
+            InputMode::Amending => match key.code {
+                KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                     let message = self.commit_message.trim();
                     if message.is_empty() {
                         messages.push("Commit message cannot be empty.".to_string());
                     } else {
-                        match commit_changes(".", message) {
-                            Ok(_) => {
-                                messages.push(format!("Committed with message: '{}'", message))
+                        let result = if self.signoff {
+                            sign_off(repo, message)
+                                .and_then(|message| amend_commit(repo, Some(&message)))
+                        } else {
+                            amend_commit(repo, Some(message))
+                        };
+                        match result {
+                            Ok(_) => messages.push(format!(
+                                "Amended commit: '{}'",
+                                message.lines().next().unwrap_or("")
+                            )),
+                            Err(e) => {
+                                messages.push(format!("Failed to amend: {}", e));
+                                identity_hint(messages, repo);
                             }
-                            Err(e) => messages.push(format!("Failed to commit: {}", e)),
                         }
                         self.input_mode = InputMode::Normal;
                         self.commit_message.clear();
+                        self.cursor = 0;
                     }
                 }
                 KeyCode::Esc => {
                     self.input_mode = InputMode::Normal;
                     self.commit_message.clear();
-                    messages.push("Commit cancelled.".to_string());
+                    self.cursor = 0;
+                    messages.push("Amend cancelled.".to_string());
                 }
-                KeyCode::Char(c) => {
-                    self.commit_message.push(c);
+                _ => self.edit_message(key),
+            },
+            InputMode::Rewording => match key.code {
+                KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    let message = self.commit_message.trim();
+                    if message.is_empty() {
+                        messages.push("Commit message cannot be empty.".to_string());
+                    } else {
+                        match reword_head(repo, message) {
+                            Ok(_) => messages.push(format!(
+                                "Reworded commit: '{}'",
+                                message.lines().next().unwrap_or("")
+                            )),
+                            Err(e) => {
+                                messages.push(format!("Failed to reword: {}", e));
+                                identity_hint(messages, repo);
+                            }
+                        }
+                        self.input_mode = InputMode::Normal;
+                        self.commit_message.clear();
+                        self.cursor = 0;
+                        self.reword_finished = true;
+                    }
                 }
-                KeyCode::Backspace => {
-                    self.commit_message.pop();
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.commit_message.clear();
+                    self.cursor = 0;
+                    self.reword_finished = true;
+                    messages.push("Reword cancelled.".to_string());
                 }
-                _ => {}
+                _ => self.edit_message(key),
             },
         }
         Ok(())
     }
 }
+
+/// Points to the config view when a commit failed for want of an identity.
+fn identity_hint(messages: &mut Vec<String>, repo: &GitRepo) {
+    if repo.signature().is_err() {
+        messages.push(
+            "No identity is configured: set user.name and user.email in the Config view (Tab)."
+                .to_string(),
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use git2::{Oid, Repository, Sort};
+use std::collections::{BinaryHeap, HashSet};
+
+/// Where the log's walk through history stopped, so each page carries on
+/// from there instead of walking again from HEAD.
+pub enum CommitWalk {
+    /// Newest commit first, reading each commit only once it is reached.
+    ByTime {
+        /// Commits waiting to be listed, by commit time.
+        queue: BinaryHeap<(i64, Oid)>,
+        /// Commits queued so far, so shared ancestors are listed once.
+        seen: HashSet<Oid>,
+        first_parent: bool,
+    },
+    /// Children before their parents, as the graph needs. Ordering that
+    /// takes the whole history, so it is walked once, for the first page.
+    Topological(std::vec::IntoIter<Oid>),
+}
+
+impl CommitWalk {
+    /// Starts at HEAD. With `first_parent`, merged-in parents are skipped
+    /// like `git log --first-parent` does.
+    pub fn new(
+        repo: &Repository,
+        topological: bool,
+        first_parent: bool,
+    ) -> Result<CommitWalk, git2::Error> {
+        let head = repo.head()?.peel_to_commit()?;
+        if topological {
+            let mut revwalk = repo.revwalk()?;
+            revwalk.push(head.id())?;
+            if first_parent {
+                revwalk.simplify_first_parent()?;
+            }
+            revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+            let order = revwalk.collect::<Result<Vec<_>, _>>()?;
+            return Ok(CommitWalk::Topological(order.into_iter()));
+        }
+        Ok(CommitWalk::ByTime {
+            queue: BinaryHeap::from([(head.time().seconds(), head.id())]),
+            seen: HashSet::from([head.id()]),
+            first_parent,
+        })
+    }
+
+    /// The next commit of the walk, or `None` past the root commits.
+    pub fn next_commit(&mut self, repo: &Repository) -> Option<Result<Oid, git2::Error>> {
+        match self {
+            CommitWalk::Topological(order) => order.next().map(Ok),
+            CommitWalk::ByTime {
+                queue,
+                seen,
+                first_parent,
+            } => {
+                let (_, id) = queue.pop()?;
+                let commit = match repo.find_commit(id) {
+                    Ok(commit) => commit,
+                    Err(e) => return Some(Err(e)),
+                };
+                let followed = if *first_parent { 1 } else { usize::MAX };
+                for parent in commit.parents().take(followed) {
+                    if seen.insert(parent.id()) {
+                        queue.push((parent.time().seconds(), parent.id()));
+                    }
+                }
+                Some(Ok(id))
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use crate::git_utils::{read_identity, set_identity, ConfigScope};
+use crate::keymap::KeyMap;
+use crate::theme::Theme;
+use crate::tui_module::navigation::Motion;
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use git2::Repository as GitRepo;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::Style,
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
+    Frame,
+};
+
+/// The config keys shown, in order.
+const KEYS: [&str; 2] = ["user.name", "user.email"];
+
+/// Shows and edits the identity commits are made with, in the repository's
+/// config or the global one.
+pub struct ConfigView {
+    pub scope: ConfigScope,
+    /// Values of `KEYS` in `scope`.
+    pub values: [Option<String>; 2],
+    /// The identity commits actually use, after every scope is merged.
+    pub effective: String,
+    pub input_mode: InputMode,
+    pub input: String,
+    pub selected: usize,
+}
+
+#[derive(PartialEq)]
+pub enum InputMode {
+    Normal,
+    Editing,
+}
+
+impl ConfigView {
+    pub fn new() -> ConfigView {
+        ConfigView {
+            scope: ConfigScope::Local,
+            values: [None, None],
+            effective: String::new(),
+            input_mode: InputMode::Normal,
+            input: String::new(),
+            selected: 0,
+        }
+    }
+
+    /// Whether keys, `q` included, belong to a prompt or dialog rather than
+    /// the global bindings.
+    pub fn captures_input(&self) -> bool {
+        self.input_mode != InputMode::Normal
+    }
+
+    fn scope_name(&self) -> &'static str {
+        match self.scope {
+            ConfigScope::Local => "local",
+            ConfigScope::Global => "global",
+        }
+    }
+
+    pub fn render<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        area: Rect,
+        theme: &Theme,
+        keys: &KeyMap,
+    ) {
+        if self.input_mode == InputMode::Editing {
+            let paragraph = Paragraph::new(&self.input[..])
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(theme.border_style())
+                        .title(format!(
+                            "{} ({}) - Enter to save, Esc to cancel",
+                            KEYS[self.selected],
+                            self.scope_name()
+                        )),
+                )
+                .style(Style::default().fg(theme.added));
+            f.render_widget(Clear, area);
+            f.render_widget(paragraph, area);
+            return;
+        }
+
+        let mut items: Vec<ListItem> = KEYS
+            .iter()
+            .zip(&self.values)
+            .enumerate()
+            .map(|(i, (key, value))| {
+                let value = value.as_deref().unwrap_or("(unset)");
+                let mut list_item = ListItem::new(format!("{:<12} {}", key, value));
+                if i == self.selected {
+                    list_item = list_item.style(theme.highlight_style());
+                }
+                list_item
+            })
+            .collect();
+        items.push(ListItem::new(""));
+        items.push(ListItem::new(format!(
+            "Commits are made as: {}",
+            self.effective
+        )));
+
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border_style())
+                .title(format!(
+                    "Config ({}) - {} to edit, '{}' for {}",
+                    self.scope_name(),
+                    keys.key_for("config.edit"),
+                    keys.key_for("config.scope"),
+                    match self.scope {
+                        ConfigScope::Local => "global",
+                        ConfigScope::Global => "local",
+                    }
+                )),
+        );
+        f.render_widget(list, area);
+    }
+
+    pub fn handle_input(
+        &mut self,
+        key: KeyEvent,
+        messages: &mut Vec<String>,
+        keys: &KeyMap,
+        repo: &GitRepo,
+    ) -> Result<()> {
+        match self.input_mode {
+            InputMode::Normal => {
+                if let Some(motion) = Motion::from_key(&key, keys) {
+                    self.selected = motion.apply(self.selected, KEYS.len(), Rect::default());
+                    return Ok(());
+                }
+                match keys.action("config", &key) {
+                    Some("edit") => {
+                        self.input_mode = InputMode::Editing;
+                        self.input = self.values[self.selected].clone().unwrap_or_default();
+                    }
+                    Some("scope") => {
+                        self.scope = match self.scope {
+                            ConfigScope::Local => ConfigScope::Global,
+                            ConfigScope::Global => ConfigScope::Local,
+                        };
+                        self.update(repo);
+                    }
+                    _ => {}
+                }
+            }
+            InputMode::Editing => match key.code {
+                KeyCode::Enter => {
+                    let value = self.input.trim();
+                    let key = KEYS[self.selected];
+                    if value.is_empty() {
+                        messages.push(format!("{} cannot be empty.", key));
+                        return Ok(());
+                    }
+                    match set_identity(repo, self.scope, key, value) {
+                        Ok(_) => messages.push(format!(
+                            "Set {} to '{}' in the {} config.",
+                            key,
+                            value,
+                            self.scope_name()
+                        )),
+                        Err(e) => messages.push(format!("Failed to set {}: {:#}", key, e)),
+                    }
+                    self.input_mode = InputMode::Normal;
+                    self.input.clear();
+                    self.update(repo);
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.input.clear();
+                }
+                KeyCode::Char(c) => self.input.push(c),
+                KeyCode::Backspace => {
+                    self.input.pop();
+                }
+                _ => {}
+            },
+        }
+        Ok(())
+    }
+
+    pub fn update(&mut self, repo: &GitRepo) {
+        match read_identity(repo, self.scope) {
+            Ok(identity) => self.values = [identity.name, identity.email],
+            Err(e) => {
+                log::error!("{:?}", e);
+                self.values = [None, None];
+            }
+        }
+        self.effective = match repo.signature() {
+            Ok(signature) => format!(
+                "{} <{}>",
+                signature.name().unwrap_or(""),
+                signature.email().unwrap_or("")
+            ),
+            Err(_) => "nobody yet; set both values in either scope".to_string(),
+        };
+    }
+}
@@ -0,0 +1,122 @@
+use crate::theme::Theme;
+use crossterm::event::{KeyCode, KeyEvent};
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+/// Most detail lines a dialog shows before summarizing the rest.
+const MAX_DETAILS: usize = 12;
+
+/// A yes/no popup guarding a destructive `action` until the user confirms it.
+pub struct ConfirmDialog<A> {
+    pub message: String,
+    /// Lines listed under the message, such as the commits a push sends.
+    pub details: Vec<String>,
+    pub action: A,
+}
+
+/// What happened to a dialog after a key press.
+pub enum DialogResult<A> {
+    /// No decision yet; the dialog stays open.
+    Open(ConfirmDialog<A>),
+    Confirmed(A),
+    Cancelled,
+}
+
+impl<A> ConfirmDialog<A> {
+    pub fn new(message: String, action: A) -> ConfirmDialog<A> {
+        ConfirmDialog {
+            message,
+            details: vec![],
+            action,
+        }
+    }
+
+    pub fn with_details(mut self, details: Vec<String>) -> ConfirmDialog<A> {
+        self.details = details;
+        self
+    }
+
+    /// Draws the dialog centered over `area`, on top of whatever is there.
+    pub fn render<B: Backend>(&self, f: &mut Frame<B>, area: Rect, theme: &Theme) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Confirm")
+            .style(
+                Style::default()
+                    .fg(theme.removed)
+                    .add_modifier(Modifier::BOLD),
+            );
+        if self.details.is_empty() {
+            let popup = centered_rect(60, 7, area);
+            let text = format!("{}\n\n[y] Yes    [n] No", self.message);
+            let paragraph = Paragraph::new(text)
+                .block(block)
+                .style(Style::default().fg(theme.text))
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true });
+            f.render_widget(Clear, popup);
+            f.render_widget(paragraph, popup);
+            return;
+        }
+
+        let mut details: Vec<String> = self.details.iter().take(MAX_DETAILS).cloned().collect();
+        if self.details.len() > MAX_DETAILS {
+            details.push(format!("... and {} more", self.details.len() - MAX_DETAILS));
+        }
+        let width = centered_rect(60, 0, area).width.saturating_sub(2).max(1);
+        let message_height = self.message.len() as u16 / width + 1;
+        // Borders, the message, a blank line, the details and the buttons.
+        let popup = centered_rect(60, details.len() as u16 + message_height + 5, area);
+        let inner = block.inner(popup);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                [
+                    Constraint::Length(message_height + 1),
+                    Constraint::Min(0),
+                    Constraint::Length(2),
+                ]
+                .as_ref(),
+            )
+            .split(inner);
+        f.render_widget(Clear, popup);
+        f.render_widget(block, popup);
+        let text = Style::default().fg(theme.text);
+        let message = Paragraph::new(self.message.clone())
+            .style(text)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        f.render_widget(message, chunks[0]);
+        f.render_widget(Paragraph::new(details.join("\n")).style(text), chunks[1]);
+        let buttons = Paragraph::new("\n[y] Yes    [n] No")
+            .style(text)
+            .alignment(Alignment::Center);
+        f.render_widget(buttons, chunks[2]);
+    }
+
+    /// `y` confirms, `n` or Esc cancels; any other key leaves the dialog open.
+    pub fn handle_input(self, key: KeyEvent) -> DialogResult<A> {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => DialogResult::Confirmed(self.action),
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => DialogResult::Cancelled,
+            _ => DialogResult::Open(self),
+        }
+    }
+}
+
+/// A rectangle `percent_x` wide and `height` rows tall, centered in `area`.
+pub fn centered_rect(percent_x: u16, height: u16, area: Rect) -> Rect {
+    let width = (area.width * percent_x / 100).max(20).min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
@@ -0,0 +1,221 @@
+use crate::git_utils::{
+    continue_operation, list_conflicts, resolve_conflict, Conflict, ConflictSide,
+};
+use crate::keymap::KeyMap;
+use crate::theme::Theme;
+use crate::tui_module::confirm_dialog::{ConfirmDialog, DialogResult};
+use crate::tui_module::navigation::{scroll_offset, Motion};
+use anyhow::Result;
+use crossterm::event::KeyEvent;
+use git2::{Repository as GitRepo, RepositoryState};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Style,
+    widgets::{Block, Borders, List, ListItem, Paragraph},
+    Frame,
+};
+
+/// Lists the files left conflicted by a merge, pull, revert or rebase and
+/// resolves each by taking one side.
+pub struct ConflictView {
+    pub items: Vec<Conflict>,
+    pub selected: usize,
+    /// Index of the first row drawn, so the selection stays in view.
+    pub offset: usize,
+    /// Where the file list was last drawn, for paging.
+    pub list_area: Rect,
+    /// Scroll shared by the base, ours and theirs panes.
+    pub scroll: u16,
+    /// Offers to continue the operation once the last conflict is resolved.
+    pub confirm: Option<ConfirmDialog<()>>,
+}
+
+impl ConflictView {
+    pub fn new() -> ConflictView {
+        ConflictView {
+            items: vec![],
+            selected: 0,
+            offset: 0,
+            list_area: Rect::default(),
+            scroll: 0,
+            confirm: None,
+        }
+    }
+
+    /// Whether keys, `q` included, belong to a prompt or dialog rather than
+    /// the global bindings.
+    pub fn captures_input(&self) -> bool {
+        self.confirm.is_some()
+    }
+
+    pub fn render<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        area: Rect,
+        theme: &Theme,
+        keys: &KeyMap,
+    ) {
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(25), Constraint::Percentage(75)].as_ref())
+            .split(area);
+        self.list_area = chunks[0];
+        self.offset = scroll_offset(self.offset, self.selected, chunks[0]);
+
+        let items: Vec<ListItem> = if self.items.is_empty() {
+            vec![ListItem::new("No conflicts")]
+        } else {
+            self.items
+                .iter()
+                .enumerate()
+                .skip(self.offset)
+                .map(|(i, conflict)| {
+                    let mut list_item = ListItem::new(conflict.path.clone());
+                    if i == self.selected {
+                        list_item = list_item.style(theme.highlight_style());
+                    }
+                    list_item
+                })
+                .collect()
+        };
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border_style())
+                .title(format!(
+                    "Conflicts ({} ours, {} theirs)",
+                    keys.key_for("conflict.ours"),
+                    keys.key_for("conflict.theirs")
+                )),
+        );
+        f.render_widget(list, chunks[0]);
+
+        let sides = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(
+                [
+                    Constraint::Ratio(1, 3),
+                    Constraint::Ratio(1, 3),
+                    Constraint::Ratio(1, 3),
+                ]
+                .as_ref(),
+            )
+            .split(chunks[1]);
+        let conflict = self.items.get(self.selected);
+        let panes = [
+            ("Base", conflict.map(|c| &c.base), Style::default()),
+            (
+                "Ours",
+                conflict.map(|c| &c.ours),
+                Style::default().fg(theme.added),
+            ),
+            (
+                "Theirs",
+                conflict.map(|c| &c.theirs),
+                Style::default().fg(theme.removed),
+            ),
+        ];
+        for ((title, content, style), pane) in panes.into_iter().zip(sides) {
+            let text = match content {
+                Some(Some(content)) => content.clone(),
+                Some(None) => "(deleted)".to_string(),
+                None => String::new(),
+            };
+            let paragraph = Paragraph::new(text)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(theme.border_style())
+                        .title(title),
+                )
+                .style(style)
+                .scroll((self.scroll, 0));
+            f.render_widget(paragraph, pane);
+        }
+
+        if let Some(dialog) = &self.confirm {
+            dialog.render(f, area, theme);
+        }
+    }
+
+    pub fn handle_input(
+        &mut self,
+        key: KeyEvent,
+        messages: &mut Vec<String>,
+        keys: &KeyMap,
+        repo: &mut GitRepo,
+    ) -> Result<()> {
+        if let Some(dialog) = self.confirm.take() {
+            match dialog.handle_input(key) {
+                DialogResult::Open(dialog) => self.confirm = Some(dialog),
+                DialogResult::Confirmed(()) => self.continue_operation(messages, repo),
+                DialogResult::Cancelled => messages.push(format!(
+                    "Not continued yet; press '{}' when ready.",
+                    keys.key_for("global.continue")
+                )),
+            }
+            return Ok(());
+        }
+
+        if let Some(motion) = Motion::from_key(&key, keys) {
+            let selected = motion.apply(self.selected, self.items.len(), self.list_area);
+            if selected != self.selected {
+                self.selected = selected;
+                self.scroll = 0;
+            }
+            return Ok(());
+        }
+
+        match keys.action("conflict", &key) {
+            Some(action @ ("ours" | "theirs")) => {
+                let Some(path) = self.items.get(self.selected).map(|c| c.path.clone()) else {
+                    messages.push("No conflicts to resolve.".to_string());
+                    return Ok(());
+                };
+                let side = if action == "ours" {
+                    ConflictSide::Ours
+                } else {
+                    ConflictSide::Theirs
+                };
+                match resolve_conflict(repo, &path, side) {
+                    Ok(_) => messages.push(format!("Resolved '{}' using {}.", path, action)),
+                    Err(e) => messages.push(format!("Failed to resolve '{}': {}", path, e)),
+                }
+                self.update(repo);
+                if self.items.is_empty() && repo.state() != RepositoryState::Clean {
+                    self.confirm = Some(ConfirmDialog::new(
+                        "All conflicts resolved. Continue?".to_string(),
+                        (),
+                    ));
+                }
+            }
+            Some("scroll_down") => self.scroll = self.scroll.saturating_add(1),
+            Some("scroll_up") => self.scroll = self.scroll.saturating_sub(1),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub fn update(&mut self, repo: &GitRepo) {
+        match list_conflicts(repo) {
+            Ok(conflicts) => self.items = conflicts,
+            Err(e) => {
+                log::error!("{:?}", e);
+                self.items.clear();
+            }
+        }
+        if self.selected >= self.items.len() {
+            self.selected = self.items.len().saturating_sub(1);
+            self.scroll = 0;
+        }
+    }
+
+    fn continue_operation(&mut self, messages: &mut Vec<String>, repo: &mut GitRepo) {
+        match continue_operation(repo) {
+            Ok(operation) => messages.push(format!("Finished the {}.", operation)),
+            Err(e) => messages.push(format!("Failed to continue: {}", e)),
+        }
+        self.update(repo);
+    }
+}
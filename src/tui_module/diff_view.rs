@@ -0,0 +1,313 @@
+use crate::git_utils::{
+    diff_commits, file_diff, highlight_words, stage_hunk, unstage_hunk, DiffLine, DiffLineKind,
+};
+use crate::keymap::KeyMap;
+use crate::theme::Theme;
+use crate::tui_module::navigation::horizontal_scroll;
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use git2::Repository as GitRepo;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+/// Two commits a view wants the app to open a diff between.
+pub struct DiffRequest {
+    /// Revision on the old side of the diff.
+    pub old: String,
+    pub new: String,
+    /// How the diff view titles the comparison.
+    pub label: String,
+}
+
+pub struct DiffView {
+    pub path: Option<String>,
+    pub lines: Vec<DiffLine>,
+    pub scroll: u16,
+    /// Index of the selected hunk among all hunks shown.
+    pub hunk: usize,
+    /// What is being compared, when not showing a file.
+    pub range: Option<String>,
+    /// Whether changed words within modified lines are highlighted.
+    pub word_diff: bool,
+    /// Whether long lines wrap; kept across diffs for the session.
+    pub wrap: bool,
+    /// Columns scrolled past on the left while lines don't wrap.
+    pub hscroll: u16,
+}
+
+impl DiffView {
+    pub fn new() -> DiffView {
+        DiffView {
+            path: None,
+            lines: vec![],
+            scroll: 0,
+            hunk: 0,
+            range: None,
+            word_diff: false,
+            wrap: false,
+            hscroll: 0,
+        }
+    }
+
+    /// Loads the diff for `path` and resets the scroll position.
+    pub fn open(&mut self, path: &str, repo: &GitRepo) -> Result<()> {
+        self.lines = file_diff(repo, path)?;
+        self.refresh_highlights();
+        self.path = Some(path.to_string());
+        self.range = None;
+        self.scroll = 0;
+        self.hscroll = 0;
+        self.hunk = 0;
+        Ok(())
+    }
+
+    /// Loads the diff between the trees of two commits.
+    pub fn open_commits(&mut self, request: DiffRequest, repo: &GitRepo) -> Result<()> {
+        self.lines = diff_commits(repo, &request.old, &request.new)?;
+        self.refresh_highlights();
+        self.path = None;
+        self.range = Some(request.label);
+        self.scroll = 0;
+        self.hscroll = 0;
+        self.hunk = 0;
+        Ok(())
+    }
+
+    /// Line indexes of the hunk headers, staged hunks first.
+    fn hunk_starts(&self) -> Vec<usize> {
+        self.lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| line.kind == DiffLineKind::HunkHeader)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Selects hunk `hunk` (clamped) and scrolls its header to the top.
+    fn select_hunk(&mut self, hunk: usize) {
+        let starts = self.hunk_starts();
+        if starts.is_empty() {
+            return;
+        }
+        self.hunk = hunk.min(starts.len() - 1);
+        self.scroll = starts[self.hunk] as u16;
+    }
+
+    /// Stages (or unstages) the selected hunk, then reloads the diff.
+    fn apply_selected_hunk(
+        &mut self,
+        stage: bool,
+        messages: &mut Vec<String>,
+        repo: &GitRepo,
+    ) -> Result<()> {
+        let Some(path) = self.path.clone() else {
+            messages.push("Only hunks of a file diff can be staged.".to_string());
+            return Ok(());
+        };
+        let starts = self.hunk_starts();
+        let Some(&start) = starts.get(self.hunk) else {
+            messages.push("No hunk selected.".to_string());
+            return Ok(());
+        };
+
+        let staged = self.lines[start].staged;
+        if staged == stage {
+            messages.push(format!(
+                "Hunk is already {}.",
+                if staged { "staged" } else { "unstaged" }
+            ));
+            return Ok(());
+        }
+        // Staged and unstaged hunks come from separate diffs, each counted from zero.
+        let index = starts[..self.hunk]
+            .iter()
+            .filter(|&&i| self.lines[i].staged == staged)
+            .count();
+        if stage {
+            stage_hunk(repo, &path, index)?;
+            messages.push(format!("Staged hunk {} of '{}'.", index + 1, path));
+        } else {
+            unstage_hunk(repo, &path, index)?;
+            messages.push(format!("Unstaged hunk {} of '{}'.", index + 1, path));
+        }
+
+        self.lines = file_diff(repo, &path)?;
+        self.refresh_highlights();
+        self.select_hunk(self.hunk);
+        Ok(())
+    }
+
+    /// Computes word highlights for freshly loaded lines when word diff is on.
+    fn refresh_highlights(&mut self) {
+        if self.word_diff {
+            highlight_words(&mut self.lines);
+        }
+    }
+
+    pub fn render<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        area: Rect,
+        theme: &Theme,
+        keys: &KeyMap,
+    ) {
+        let starts = self.hunk_starts();
+        let selected_start = starts.get(self.hunk).copied();
+        let move_keys = format!(
+            "{}/{}",
+            keys.key_for("diff.prev_hunk"),
+            keys.key_for("diff.next_hunk")
+        );
+        let mut title = match (&self.path, selected_start) {
+            (Some(path), Some(start)) => format!(
+                "Diff: {} - hunk {}/{} ({}) {} to move, {}/{} to stage/unstage",
+                path,
+                self.hunk + 1,
+                starts.len(),
+                if self.lines[start].staged {
+                    "staged"
+                } else {
+                    "unstaged"
+                },
+                move_keys,
+                keys.key_for("diff.stage_hunk"),
+                keys.key_for("diff.unstage_hunk")
+            ),
+            (Some(path), None) => format!("Diff: {}", path),
+            (None, _) => match &self.range {
+                Some(range) => match selected_start {
+                    Some(_) => format!(
+                        "Diff: {} - hunk {}/{} {} to move",
+                        range,
+                        self.hunk + 1,
+                        starts.len(),
+                        move_keys
+                    ),
+                    None => format!("Diff: {}", range),
+                },
+                None => "Diff".to_string(),
+            },
+        };
+        if self.word_diff {
+            title.push_str(" (word diff)");
+        }
+        if self.wrap {
+            title.push_str(" (wrapped)");
+        }
+
+        let text: Vec<Spans> = if self.lines.is_empty() {
+            vec![Spans::from("No changes.")]
+        } else {
+            self.lines
+                .iter()
+                .enumerate()
+                .map(|(i, line)| {
+                    let style = match line.kind {
+                        DiffLineKind::HunkHeader if Some(i) == selected_start => {
+                            theme.highlight_style()
+                        }
+                        DiffLineKind::Added => Style::default().fg(theme.added),
+                        DiffLineKind::Removed => Style::default().fg(theme.removed),
+                        DiffLineKind::HunkHeader => Style::default().fg(theme.accent),
+                        DiffLineKind::FileHeader => Style::default().add_modifier(Modifier::BOLD),
+                        DiffLineKind::Context => Style::default(),
+                    };
+                    if self.word_diff && !line.highlights.is_empty() {
+                        word_spans(line, style)
+                    } else {
+                        Spans::from(Span::styled(line.content.clone(), style))
+                    }
+                })
+                .collect()
+        };
+
+        let mut paragraph = Paragraph::new(text).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border_style())
+                .title(title),
+        );
+        paragraph = if self.wrap {
+            paragraph
+                .wrap(Wrap { trim: false })
+                .scroll((self.scroll, 0))
+        } else {
+            paragraph.scroll((self.scroll, self.hscroll))
+        };
+        f.render_widget(paragraph, area);
+    }
+
+    pub fn handle_input(
+        &mut self,
+        key: KeyEvent,
+        messages: &mut Vec<String>,
+        keys: &KeyMap,
+        repo: &GitRepo,
+    ) -> Result<()> {
+        let max_scroll = self.lines.len().saturating_sub(1) as u16;
+        if !self.wrap {
+            if let Some(column) = horizontal_scroll(self.hscroll, &key) {
+                self.hscroll = column;
+                return Ok(());
+            }
+        }
+        match (keys.action("diff", &key), key.code) {
+            (Some("next_hunk"), _) => self.select_hunk(self.hunk + 1),
+            (Some("prev_hunk"), _) => self.select_hunk(self.hunk.saturating_sub(1)),
+            (Some("stage_hunk"), _) => self.apply_selected_hunk(true, messages, repo)?,
+            (Some("unstage_hunk"), _) => self.apply_selected_hunk(false, messages, repo)?,
+            (Some("word_diff"), _) => {
+                self.word_diff = !self.word_diff;
+                if self.word_diff {
+                    highlight_words(&mut self.lines);
+                }
+            }
+            (Some("wrap"), _) => {
+                self.wrap = !self.wrap;
+                self.hscroll = 0;
+            }
+            (_, KeyCode::Down) => {
+                self.scroll = self.scroll.saturating_add(1).min(max_scroll);
+            }
+            (_, KeyCode::Up) => {
+                self.scroll = self.scroll.saturating_sub(1);
+            }
+            (_, KeyCode::PageDown) => {
+                self.scroll = self.scroll.saturating_add(20).min(max_scroll);
+            }
+            (_, KeyCode::PageUp) => {
+                self.scroll = self.scroll.saturating_sub(20);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Renders a modified line dimmed, with the words that changed in bold.
+fn word_spans(line: &DiffLine, style: Style) -> Spans<'static> {
+    let mut spans = Vec::new();
+    let mut last = 0;
+    for &(start, end) in &line.highlights {
+        spans.push(Span::styled(
+            line.content[last..start].to_string(),
+            style.add_modifier(Modifier::DIM),
+        ));
+        spans.push(Span::styled(
+            line.content[start..end].to_string(),
+            style.add_modifier(Modifier::BOLD | Modifier::REVERSED),
+        ));
+        last = end;
+    }
+    spans.push(Span::styled(
+        line.content[last..].to_string(),
+        style.add_modifier(Modifier::DIM),
+    ));
+    Spans::from(spans)
+}
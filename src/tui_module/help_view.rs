@@ -1,72 +1,135 @@
 // src/tui/help_view.rs
 
-use crate::utils::print_info;
+use crate::keymap::KeyMap;
+use crate::theme::Theme;
+use crate::tui_module::confirm_dialog::centered_rect;
 use crossterm::event::{KeyCode, KeyEvent};
 use tui::{
     backend::Backend,
     layout::Rect,
-    style::{Color, Style},
-    widgets::{Block, Borders, Paragraph},
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, Paragraph},
     Frame,
 };
 
+/// List movement keys, which are fixed rather than part of the key map.
+const NAVIGATION: &[(&str, &str)] = &[
+    ("Up/Down", "Move the selection"),
+    ("Home/End", "Jump to the top/bottom"),
+    ("PgUp/PgDn", "Move a page up/down"),
+    (
+        "Mouse",
+        "Click a row to select it, scroll to move the selection",
+    ),
+    ("Esc", "Cancel the current prompt or go back"),
+];
+
+/// A popup over the active view listing the key bindings in effect.
 pub struct HelpView {
     pub visible: bool,
+    /// Lines scrolled past at the top.
+    pub scroll: u16,
 }
 
 impl HelpView {
     pub fn new() -> HelpView {
-        HelpView { visible: false }
+        HelpView {
+            visible: false,
+            scroll: 0,
+        }
     }
 
-    pub fn render<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
-        if self.visible {
-            let help_text = vec![
-                "Help - Available Commands",
-                "",
-                "Navigation:",
-                "  - Tab        : Switch between views",
-                "  - q          : Exit application",
-                "",
-                "Status View:",
-                "  - a          : Stage a file",
-                "",
-                "Log View:",
-                "  - r          : Refresh commit logs",
-                "",
-                "Branch View:",
-                "  - Up/Down    : Navigate branches",
-                "  - c          : Create a new branch",
-                "  - d          : Delete the selected branch",
-                "",
-                "Commit View:",
-                "  - c          : Write a commit message",
-                "",
-                "General:",
-                "  - Esc        : Cancel current operation",
-                "",
-                "Press 'h' again to hide this help.",
-            ];
-            let paragraph = Paragraph::new(help_text.join("\n"))
-                .block(Block::default().borders(Borders::ALL).title("Help"))
-                .style(Style::default().fg(Color::White).bg(Color::Black))
-                .alignment(tui::layout::Alignment::Left);
-            f.render_widget(paragraph, area);
-        }
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        self.scroll = 0;
     }
 
-    pub fn handle_input(&mut self, key: KeyEvent) {
-        if key.code == KeyCode::Char('h') {
-            self.visible = !self.visible;
-            if self.visible {
-                print_info("Help view opened.");
-            } else {
-                print_info("Help view closed.");
+    pub fn render<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        area: Rect,
+        theme: &Theme,
+        keys: &KeyMap,
+    ) {
+        if !self.visible {
+            return;
+        }
+        let popup = centered_rect(80, area.height, area);
+        let heading = Style::default()
+            .fg(theme.title)
+            .add_modifier(Modifier::BOLD);
+
+        let mut lines = vec![Spans::from(Span::styled("Navigation", heading))];
+        lines.extend(
+            NAVIGATION
+                .iter()
+                .map(|(key, description)| Spans::from(format!("  {:<12} {}", key, description))),
+        );
+        let mut group = "";
+        for binding in &keys.bindings {
+            let (view, _) = binding.action.split_once('.').unwrap_or_default();
+            if view != group {
+                group = view;
+                lines.push(Spans::from(""));
+                lines.push(Spans::from(Span::styled(group_title(view), heading)));
             }
+            lines.push(Spans::from(format!(
+                "  {:<12} {}",
+                binding.key_name(),
+                binding.description
+            )));
         }
+
+        let max_scroll = lines.len().saturating_sub(1) as u16;
+        self.scroll = self.scroll.min(max_scroll);
+        let paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(theme.border_style())
+                    .title(format!(
+                        "Help - Up/Down/PgUp/PgDn scroll, {} or Esc to close",
+                        keys.key_for("global.help")
+                    )),
+            )
+            .style(Style::default().fg(theme.text))
+            .scroll((self.scroll, 0));
+        f.render_widget(Clear, popup);
+        f.render_widget(paragraph, popup);
     }
 
-    pub fn update(&mut self) {
-        // todo
+    /// Scrolls the help; `?` (or whatever `global.help` is bound to) and Esc close it.
+    pub fn handle_input(&mut self, key: KeyEvent, keys: &KeyMap) {
+        if key.code == KeyCode::Esc || keys.is("global.help", &key) {
+            self.toggle();
+            return;
+        }
+        match key.code {
+            KeyCode::Down => self.scroll = self.scroll.saturating_add(1),
+            KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_add(20),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(20),
+            _ if keys.is("nav.down", &key) => self.scroll = self.scroll.saturating_add(1),
+            _ if keys.is("nav.up", &key) => self.scroll = self.scroll.saturating_sub(1),
+            _ => {}
+        }
+    }
+}
+
+/// The heading for the bindings of one key map group.
+fn group_title(view: &str) -> String {
+    match view {
+        "global" => "Everywhere".to_string(),
+        "nav" => "Moving in lists".to_string(),
+        "messages" => "Messages pane (once focused)".to_string(),
+        "welcome" => "Without a repository".to_string(),
+        view => {
+            let mut chars = view.chars();
+            match chars.next() {
+                Some(first) => format!("{}{} View", first.to_ascii_uppercase(), chars.as_str()),
+                None => String::new(),
+            }
+        }
     }
 }
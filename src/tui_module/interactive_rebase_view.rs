@@ -0,0 +1,276 @@
+use crate::git_utils::{
+    check_rebase_todo, interactive_rebase, rebase_todo, RebaseAction, RebaseStep,
+};
+use crate::keymap::KeyMap;
+use crate::theme::Theme;
+use crate::tui_module::confirm_dialog::{ConfirmDialog, DialogResult};
+use crate::tui_module::navigation::{scroll_offset, Motion};
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use git2::{Oid, Repository as GitRepo};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Modifier, Style},
+    widgets::{Block, Borders, List, ListItem},
+    Frame,
+};
+
+/// The todo list of an interactive rebase: the commits after a base, oldest
+/// first, each with an action, reordered before the rebase is started.
+pub struct InteractiveRebaseView {
+    /// Full id of the commit the steps are replayed onto.
+    pub base: String,
+    pub steps: Vec<RebaseStep>,
+    /// The commits in their original order, to tell whether anything changed.
+    original: Vec<Oid>,
+    pub selected: usize,
+    /// Index of the first row drawn, so the selection stays in view.
+    pub offset: usize,
+    /// Where the list was last drawn, for paging.
+    pub list_area: Rect,
+    pub input_mode: InputMode,
+    pub input: String,
+    pub confirm: Option<ConfirmDialog<()>>,
+    /// Set once the rebase is done, for the app to go back to the log.
+    pub finished: bool,
+}
+
+#[derive(PartialEq)]
+pub enum InputMode {
+    Normal,
+    /// Typing the new subject of the selected commit.
+    Rewording,
+}
+
+impl InteractiveRebaseView {
+    pub fn new() -> InteractiveRebaseView {
+        InteractiveRebaseView {
+            base: String::new(),
+            steps: vec![],
+            original: vec![],
+            selected: 0,
+            offset: 0,
+            list_area: Rect::default(),
+            input_mode: InputMode::Normal,
+            input: String::new(),
+            confirm: None,
+            finished: false,
+        }
+    }
+
+    /// Lists the commits after `base` up to HEAD, all picked.
+    pub fn open(&mut self, base: &str, repo: &GitRepo) -> Result<()> {
+        self.steps = rebase_todo(repo, base)?;
+        self.original = self.steps.iter().map(|step| step.id).collect();
+        self.base = base.to_string();
+        self.selected = 0;
+        self.offset = 0;
+        self.input_mode = InputMode::Normal;
+        self.input.clear();
+        self.confirm = None;
+        Ok(())
+    }
+
+    /// Whether keys, `q` included, belong to a prompt or dialog rather than
+    /// the global bindings.
+    pub fn captures_input(&self) -> bool {
+        self.input_mode != InputMode::Normal || self.confirm.is_some()
+    }
+
+    /// The todo line for `step`, showing the new subject of a reword.
+    fn todo_line(step: &RebaseStep) -> String {
+        let summary = match step.action {
+            RebaseAction::Reword => step.message.lines().next().unwrap_or(""),
+            _ => &step.summary,
+        };
+        format!("{:<6} {:.7} {}", step.action.name(), step.id, summary)
+    }
+
+    pub fn render<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        area: Rect,
+        theme: &Theme,
+        keys: &KeyMap,
+    ) {
+        self.list_area = area;
+        self.offset = scroll_offset(self.offset, self.selected, area);
+        let items: Vec<ListItem> = self
+            .steps
+            .iter()
+            .enumerate()
+            .skip(self.offset)
+            .map(|(i, step)| {
+                let style = match step.action {
+                    RebaseAction::Pick => Style::default(),
+                    RebaseAction::Reword => Style::default().fg(theme.accent),
+                    RebaseAction::Squash | RebaseAction::Fixup => {
+                        Style::default().fg(theme.warning)
+                    }
+                    RebaseAction::Drop => Style::default()
+                        .fg(theme.removed)
+                        .add_modifier(Modifier::CROSSED_OUT),
+                };
+                let mut list_item = ListItem::new(Self::todo_line(step)).style(style);
+                if i == self.selected {
+                    list_item = list_item.style(theme.highlight_style());
+                }
+                list_item
+            })
+            .collect();
+
+        let title = match self.input_mode {
+            InputMode::Rewording => format!(
+                "New subject (Enter to save, Esc to cancel): {}_",
+                self.input
+            ),
+            InputMode::Normal => format!(
+                "Rebase onto {:.7}, oldest first - {}/{}/{}/{}/{} action, {}/{} move, {} to start, Esc to cancel",
+                self.base,
+                keys.key_for("rebase.pick"),
+                keys.key_for("rebase.reword"),
+                keys.key_for("rebase.squash"),
+                keys.key_for("rebase.fixup"),
+                keys.key_for("rebase.drop"),
+                keys.key_for("rebase.move_down"),
+                keys.key_for("rebase.move_up"),
+                keys.key_for("rebase.start")
+            ),
+        };
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border_style())
+                .title(title),
+        );
+        f.render_widget(list, area);
+
+        if let Some(dialog) = &self.confirm {
+            dialog.render(f, area, theme);
+        }
+    }
+
+    pub fn handle_input(
+        &mut self,
+        key: KeyEvent,
+        messages: &mut Vec<String>,
+        keys: &KeyMap,
+        repo: &GitRepo,
+    ) {
+        if let Some(dialog) = self.confirm.take() {
+            match dialog.handle_input(key) {
+                DialogResult::Open(dialog) => self.confirm = Some(dialog),
+                DialogResult::Confirmed(()) => self.start(messages, repo),
+                DialogResult::Cancelled => messages.push("Cancelled.".to_string()),
+            }
+            return;
+        }
+
+        if self.input_mode == InputMode::Rewording {
+            match key.code {
+                KeyCode::Enter => {
+                    let subject = self.input.trim().to_string();
+                    if subject.is_empty() {
+                        messages.push("The subject cannot be empty.".to_string());
+                        return;
+                    }
+                    if let Some(step) = self.steps.get_mut(self.selected) {
+                        // Keep the body, replacing only the first line.
+                        step.message = match step.message.split_once('\n') {
+                            Some((_, body)) => format!("{}\n{}", subject, body),
+                            None => subject,
+                        };
+                        step.action = RebaseAction::Reword;
+                    }
+                    self.input_mode = InputMode::Normal;
+                    self.input.clear();
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.input.clear();
+                }
+                KeyCode::Char(c) => self.input.push(c),
+                KeyCode::Backspace => {
+                    self.input.pop();
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        if let Some(motion) = Motion::from_key(&key, keys) {
+            self.selected = motion.apply(self.selected, self.steps.len(), self.list_area);
+            return;
+        }
+
+        if self.steps.is_empty() {
+            return;
+        }
+        let step = &mut self.steps[self.selected];
+        match keys.action("rebase", &key) {
+            Some("pick") => step.action = RebaseAction::Pick,
+            Some("squash") => step.action = RebaseAction::Squash,
+            Some("fixup") => step.action = RebaseAction::Fixup,
+            Some("drop") => step.action = RebaseAction::Drop,
+            Some("reword") => {
+                self.input = step.message.lines().next().unwrap_or("").to_string();
+                self.input_mode = InputMode::Rewording;
+            }
+            Some("move_down") if self.selected + 1 < self.steps.len() => {
+                self.steps.swap(self.selected, self.selected + 1);
+                self.selected += 1;
+            }
+            Some("move_up") if self.selected > 0 => {
+                self.steps.swap(self.selected, self.selected - 1);
+                self.selected -= 1;
+            }
+            Some("start") => {
+                let unchanged = self
+                    .steps
+                    .iter()
+                    .map(|step| step.id)
+                    .eq(self.original.iter().copied())
+                    && self
+                        .steps
+                        .iter()
+                        .all(|step| step.action == RebaseAction::Pick);
+                if unchanged {
+                    messages.push(
+                        "Nothing to do: every commit is picked in its original order.".to_string(),
+                    );
+                    return;
+                }
+                if let Err(e) = check_rebase_todo(&self.steps) {
+                    messages.push(e.to_string());
+                    return;
+                }
+                self.confirm = Some(
+                    ConfirmDialog::new(
+                        format!(
+                            "Rewrite these {} commits onto {:.7}? Pushed commits will need a force-push.",
+                            self.steps.len(),
+                            self.base
+                        ),
+                        (),
+                    )
+                    .with_details(self.steps.iter().map(Self::todo_line).collect()),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, messages: &mut Vec<String>, repo: &GitRepo) {
+        match interactive_rebase(repo, &self.base, &self.steps) {
+            Ok(count) => {
+                messages.push(format!(
+                    "Rebased onto {:.7}; the branch now has {} commit(s) there.",
+                    self.base, count
+                ));
+                self.finished = true;
+            }
+            Err(e) => messages.push(format!("Failed to rebase: {:#}", e)),
+        }
+    }
+}
@@ -1,37 +1,219 @@
+use crate::clipboard::copy_reporting;
+use crate::git_utils::{
+    apply_patch, checkout_commit, commit_diffstat, commit_stats, commit_touches_path,
+    commits_since, create_branch, format_patch, has_uncommitted_changes, reset, resolve_commit,
+    revert_commit, squash_last, undo_last_commit, CommitStats, ResetMode,
+};
+use crate::keymap::KeyMap;
+use crate::theme::Theme;
+use crate::tui_module::commit_graph::CommitGraph;
+use crate::tui_module::commit_walk::CommitWalk;
+use crate::tui_module::confirm_dialog::{ConfirmDialog, DialogResult};
+use crate::tui_module::diff_view::DiffRequest;
+use crate::tui_module::mouse::clicked_row;
+use crate::tui_module::navigation::{horizontal_scroll, scroll_offset, Motion};
 use crate::utils::{print_error, print_info};
 use anyhow::{Context, Result};
-use chrono::{NaiveDateTime, Utc};
-use crossterm::event::{KeyCode, KeyEvent};
+use chrono::{DateTime, FixedOffset, Utc};
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
 use git2::Repository as GitRepo;
+use std::collections::HashMap;
 use tui::{
     backend::Backend,
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
+    text::{Span, Spans},
     widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
     Frame,
 };
 
+/// Number of commits walked per page of the log.
+const PAGE_SIZE: usize = 200;
+
+/// Load the next page once the selection is this close to the end.
+const LOAD_MORE_MARGIN: usize = 20;
+
 pub struct LogView {
     pub items: Vec<CommitItem>,
     pub selected: usize,
+    pub input_mode: InputMode,
+    /// Active search query; `items` holds only the commits matching it.
+    pub search: String,
+    /// Every loaded commit, so clearing the search restores the full list.
+    pub all_items: Vec<CommitItem>,
+    /// Only commits whose author name or email contains this are loaded.
+    pub author_filter: String,
+    /// Only commits that changed this file are loaded, like `git log -- <path>`.
+    pub path_filter: Option<String>,
+    pub input: String,
+
+    /// Commits walked so far, including ones hidden by the author filter.
+    walked: usize,
+    /// The walk the next page continues, started by the first page.
+    walk: Option<CommitWalk>,
+    /// Whether the walk has reached the root commit.
+    exhausted: bool,
+    /// Lanes of the walk so far, continued by each page.
+    graph: CommitGraph,
 
     pub detailed_commit: Option<CommitDetail>,
+    /// Lines scrolled past at the top of the commit detail.
+    pub detail_scroll: u16,
+    /// Whether long detail lines wrap; kept across commits for the session.
+    pub detail_wrap: bool,
+    /// Columns scrolled past on the left while detail lines don't wrap.
+    pub detail_hscroll: u16,
+    /// Where the commit detail was last drawn, for paging through it.
+    pub detail_area: Rect,
+    pub date_format: DateFormat,
+    /// Whether the graph gutter is drawn left of the commits.
+    pub show_graph: bool,
+    /// Whether only first parents are followed, like `git log --first-parent`.
+    pub first_parent: bool,
+    /// Whether each commit's author email is shown after the name.
+    pub show_email: bool,
+    /// Whether each commit ends with the lines it added and removed.
+    pub show_diffstat: bool,
+    /// Lines added and removed by each commit shown so far, by full id.
+    diffstats: HashMap<String, (usize, usize)>,
+    /// Index of the first row drawn, so the selection stays in view.
+    pub offset: usize,
+    /// Where the commit list was last drawn, for mouse clicks.
+    pub list_area: Rect,
+    /// Full id of the commit marked as the old side of a diff.
+    pub marked: Option<String>,
+    /// Diff between the marked and selected commits for the app to open.
+    pub diff_requested: Option<DiffRequest>,
+    /// Set when the user asked to reword HEAD, for the app to open the editor.
+    pub reword_requested: bool,
+    /// Base of the interactive rebase the app should open, a full commit id.
+    pub rebase_requested: Option<String>,
+    pub confirm: Option<ConfirmDialog<LogAction>>,
+}
+
+/// A log operation waiting for confirmation.
+pub enum LogAction {
+    /// Check out the commit with this id despite local changes.
+    Checkout(String),
+    UndoLastCommit,
+}
+
+#[derive(PartialEq)]
+pub enum InputMode {
+    Normal,
+    ChoosingReset,
+    ConfirmingHardReset,
+    Searching,
+    FilteringAuthor,
+    /// Typing the name of a branch to create at the selected commit.
+    CreatingBranch,
+    /// Typing a hash to jump to.
+    GoingTo,
+    /// Typing the path of a patch file to apply.
+    ApplyingPatch,
+    /// Choosing whether the patch in `input` is committed.
+    ChoosingPatchMode,
+    /// Typing the message for this many commits squashed into one.
+    Squashing(usize),
+}
+
+/// How commit dates are shown in the list.
+#[derive(Clone, Copy, PartialEq)]
+pub enum DateFormat {
+    /// `2024-01-02 15:04:05 +0200`
+    Absolute,
+    /// `3 hours ago`, falling back to absolute for dates over a year old.
+    Relative,
 }
 
 #[derive(Clone)]
 pub struct CommitItem {
     pub id: String,
     pub author: String,
-    pub date: String, // New field for commit date
+    /// The author's email, if the commit records one.
+    pub email: Option<String>,
+    /// Commit time; `None` for placeholder entries.
+    pub date: Option<git2::Time>,
     pub message: String,
+    /// This commit's row of the graph gutter.
+    pub graph: String,
+    /// Whether the commit has more than one parent.
+    pub merge: bool,
+}
+
+impl CommitItem {
+    /// A placeholder entry that reports a failure in the list.
+    fn error(message: String) -> CommitItem {
+        CommitItem {
+            id: "Error".to_string(),
+            author: "Error".to_string(),
+            email: None,
+            date: None,
+            message,
+            graph: String::new(),
+            merge: false,
+        }
+    }
+
+    /// Whether the id, author or message contains `query`, ignoring ASCII case.
+    fn matches(&self, query: &str) -> bool {
+        [&self.id, &self.author, &self.message]
+            .iter()
+            .any(|field| find_ignore_case(field, query).is_some())
+    }
 }
 
 pub struct CommitDetail {
     pub id: String,
     pub author: String,
+    pub email: Option<String>,
     pub date: String,
     pub message: String,
+    /// Each parent's id and summary, first parent first.
     pub parents: Vec<String>,
+    pub stats: CommitStats,
+}
+
+impl CommitDetail {
+    /// The text shown in the detail pane, one entry per line.
+    fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("Commit ID: {}", self.id),
+            format!(
+                "Author: {}",
+                format_author(&self.author, self.email.as_deref())
+            ),
+            format!("Date: {}", self.date),
+            "".to_string(),
+            "Message:".to_string(),
+        ];
+        lines.extend(self.message.lines().map(str::to_string));
+        lines.push("".to_string());
+        if self.parents.len() > 1 {
+            lines.push(format!("Merge of {} parents:", self.parents.len()));
+            for (i, parent) in self.parents.iter().enumerate() {
+                let role = if i == 0 { "merged into" } else { "merged in" };
+                lines.push(format!("Parent {} ({}): {}", i + 1, role, parent));
+            }
+        } else {
+            lines.push("Parents:".to_string());
+            lines.extend(self.parents.iter().cloned());
+        }
+        lines.push("".to_string());
+        lines.push(format!(
+            "{} file(s) changed, +{} -{}{}:",
+            self.stats.files.len(),
+            self.stats.insertions,
+            self.stats.deletions,
+            if self.parents.len() > 1 {
+                " against parent 1"
+            } else {
+                ""
+            }
+        ));
+        lines.extend(self.stats.files.iter().cloned());
+        lines
+    }
 }
 
 impl LogView {
@@ -39,197 +221,1018 @@ impl LogView {
         LogView {
             items: vec![],
             selected: 0,
+            input_mode: InputMode::Normal,
+            search: String::new(),
+            all_items: vec![],
+            author_filter: String::new(),
+            path_filter: None,
+            input: String::new(),
+            walked: 0,
+            walk: None,
+            exhausted: false,
+            graph: CommitGraph::default(),
 
             detailed_commit: None,
+            detail_scroll: 0,
+            detail_wrap: true,
+            detail_area: Rect::default(),
+            detail_hscroll: 0,
+            date_format: DateFormat::Absolute,
+            show_graph: false,
+            first_parent: false,
+            show_email: false,
+            show_diffstat: false,
+            diffstats: HashMap::new(),
+            offset: 0,
+            list_area: Rect::default(),
+            marked: None,
+            diff_requested: None,
+            reword_requested: false,
+            rebase_requested: None,
+            confirm: None,
         }
     }
 
-    pub fn render<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+    /// Whether keys, `q` included, belong to a prompt or dialog rather than
+    /// the global bindings.
+    pub fn captures_input(&self) -> bool {
+        self.input_mode != InputMode::Normal || self.confirm.is_some()
+    }
+
+    pub fn render<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        area: Rect,
+        theme: &Theme,
+        keys: &KeyMap,
+    ) {
+        self.list_area = Rect::default();
         if let Some(detail) = &self.detailed_commit {
             let block = Block::default()
                 .borders(Borders::ALL)
-                .title("Commit Details")
-                .style(Style::default().fg(Color::Green));
-            let content = vec![
-                format!("Commit ID: {}", detail.id),
-                format!("Author: {}", detail.author),
-                format!("Date: {}", detail.date),
-                "".to_string(),
-                "Message:".to_string(),
-                detail.message.clone(),
-                "".to_string(),
-                "Parents:".to_string(),
-                detail.parents.join(", "),
-            ];
-            let paragraph = Paragraph::new(content.join("\n"))
+                .border_style(theme.border_style())
+                .title(if self.detail_wrap {
+                    "Commit Details (Up/Down/PgUp/PgDn to scroll, Esc to close)"
+                } else {
+                    "Commit Details (Up/Down/PgUp/PgDn/Left/Right to scroll, Esc to close)"
+                })
+                .style(Style::default().fg(theme.added));
+            let mut paragraph = Paragraph::new(detail.lines().join("\n"))
                 .block(block)
-                .style(Style::default().fg(Color::White))
-                .alignment(tui::layout::Alignment::Left)
-                .wrap(tui::widgets::Wrap { trim: true });
+                .style(Style::default().fg(theme.text))
+                .alignment(tui::layout::Alignment::Left);
+            paragraph = if self.detail_wrap {
+                paragraph
+                    .wrap(tui::widgets::Wrap { trim: true })
+                    .scroll((self.detail_scroll, 0))
+            } else {
+                paragraph.scroll((self.detail_scroll, self.detail_hscroll))
+            };
             f.render_widget(paragraph, area);
+            self.detail_area = area;
             return;
         }
 
+        let now = Utc::now();
+        self.offset = scroll_offset(self.offset, self.selected, area);
         let items: Vec<ListItem> = self
             .items
             .iter()
             .enumerate()
+            .skip(self.offset)
             .map(|(i, commit)| {
-                let content = format!(
-                    "{} {} [{}] - {}",
-                    commit.id, commit.author, commit.date, commit.message
-                );
-                let mut list_item = ListItem::new(content);
-                if i == self.selected {
-                    list_item = list_item.style(
-                        Style::default()
-                            .fg(Color::Yellow)
-                            .add_modifier(Modifier::BOLD),
+                let date = match (commit.date, self.date_format) {
+                    (None, _) => String::new(),
+                    (Some(time), DateFormat::Absolute) => format_commit_time(time),
+                    (Some(time), DateFormat::Relative) => format_relative_time(time, now),
+                };
+                let author = if self.show_email {
+                    format_author(&commit.author, commit.email.as_deref())
+                } else {
+                    commit.author.clone()
+                };
+                let content = format!("{} {} [{}] - {}", commit.id, author, date, commit.message);
+                let is_marked = self.marked.as_deref() == Some(commit.id.as_str());
+                let mut spans = highlight_matches(&content, &self.search, theme);
+                if self.show_graph {
+                    spans.0.insert(
+                        0,
+                        Span::styled(
+                            format!("{} ", commit.graph),
+                            Style::default().fg(theme.graph),
+                        ),
                     );
                 }
+                if commit.merge {
+                    spans.0.push(Span::raw(" (merge)"));
+                }
+                if let Some((insertions, deletions)) = self
+                    .diffstats
+                    .get(&commit.id)
+                    .filter(|_| self.show_diffstat)
+                {
+                    spans.0.push(Span::styled(
+                        format!(" +{}", insertions),
+                        Style::default().fg(theme.added),
+                    ));
+                    spans.0.push(Span::styled(
+                        format!(" -{}", deletions),
+                        Style::default().fg(theme.removed),
+                    ));
+                }
+                if is_marked {
+                    spans.0.push(Span::raw(" [marked]"));
+                }
+                let mut list_item = ListItem::new(spans);
+                if is_marked {
+                    list_item = list_item.style(Style::default().fg(theme.marked));
+                } else if commit.merge {
+                    list_item = list_item.style(Style::default().fg(theme.merge));
+                }
+                if i == self.selected {
+                    list_item = list_item.style(theme.highlight_style());
+                }
                 list_item
             })
             .collect();
+        let title = match self.input_mode {
+            InputMode::Normal => {
+                let mut title = "Commit Log".to_string();
+                if self.first_parent {
+                    title.push_str(" - first parent only");
+                }
+                if let Some(path) = &self.path_filter {
+                    title.push_str(&format!(" - file: '{}' (Esc to clear)", path));
+                }
+                if !self.author_filter.is_empty() {
+                    title.push_str(&format!(" - author: '{}'", self.author_filter));
+                }
+                if !self.search.is_empty() {
+                    title.push_str(&format!(
+                        " - search: '{}' ({} matches, {}/{} to jump, Esc to clear)",
+                        self.search,
+                        self.items.len(),
+                        keys.key_for("log.next_match"),
+                        keys.key_for("log.prev_match")
+                    ));
+                }
+                title
+            }
+            InputMode::Searching => format!("Search: {}_", self.search),
+            InputMode::FilteringAuthor => {
+                format!("Filter by author (empty to clear): {}_", self.input)
+            }
+            InputMode::ApplyingPatch => format!(
+                "Apply patch file (relative to the work tree): {}_",
+                self.input
+            ),
+            InputMode::ChoosingPatchMode => format!(
+                "Apply '{}': [c]ommit it, [w]ork tree only, Esc to cancel",
+                self.input
+            ),
+            InputMode::GoingTo => format!("Go to commit (hash or revision): {}_", self.input),
+            InputMode::CreatingBranch => format!(
+                "New branch at {} (Enter to create, Esc to cancel): {}_",
+                self.selected_short_id(),
+                self.input
+            ),
+            InputMode::Squashing(count) => format!(
+                "Squash {} commits into one, message (Enter to squash, Esc to cancel): {}_",
+                count, self.input
+            ),
+            InputMode::ChoosingReset => format!(
+                "Reset to {}: [s]oft [m]ixed [h]ard, Esc to cancel",
+                self.selected_short_id()
+            ),
+            InputMode::ConfirmingHardReset => format!(
+                "Hard reset to {} discards uncommitted changes! [y] to confirm, any other key to cancel",
+                self.selected_short_id()
+            ),
+        };
         let list = List::new(items)
-            .block(Block::default().borders(Borders::ALL).title("Commit Log"))
-            .highlight_style(
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(theme.border_style())
+                    .title(title),
             )
+            .highlight_style(theme.highlight_style())
             .highlight_symbol(">> ");
         f.render_widget(list, area);
+        self.list_area = area;
+
+        if let Some(dialog) = &self.confirm {
+            dialog.render(f, area, theme);
+        }
     }
 
-    pub fn handle_input(&mut self, key: KeyEvent, messages: &mut Vec<String>) -> Result<()> {
-        match key.code {
-            KeyCode::Down => {
+    /// Selects the clicked commit, or moves the selection with the scroll
+    /// wheel, loading more commits near the end like the arrow keys do.
+    pub fn handle_mouse(&mut self, event: MouseEvent, repo: &GitRepo) {
+        if self.input_mode != InputMode::Normal {
+            return;
+        }
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                self.selected = clicked_row(self.list_area, &event)
+                    .map(|row| row + self.offset)
+                    .filter(|&row| row < self.items.len())
+                    .unwrap_or(self.selected);
+            }
+            MouseEventKind::ScrollDown => {
                 if self.selected < self.items.len().saturating_sub(1) {
                     self.selected += 1;
                 }
+                if self.selected + LOAD_MORE_MARGIN >= self.items.len() {
+                    self.load_more(repo);
+                }
             }
-            KeyCode::Up => {
-                if self.selected > 0 {
-                    self.selected -= 1;
+            MouseEventKind::ScrollUp if self.selected > 0 => self.selected -= 1,
+            _ => {}
+        }
+    }
+
+    fn checkout(&mut self, id: &str, messages: &mut Vec<String>, repo: &GitRepo) {
+        match checkout_commit(repo, id) {
+            Ok(_) => messages.push(format!(
+                "Checked out {:.7}; HEAD is detached. Create a branch to keep new commits.",
+                id
+            )),
+            Err(e) => messages.push(format!("Failed to check out {:.7}: {:#}", id, e)),
+        }
+        self.update(repo);
+    }
+
+    fn selected_short_id(&self) -> String {
+        self.items
+            .get(self.selected)
+            .map(|commit| commit.id.chars().take(7).collect())
+            .unwrap_or_default()
+    }
+
+    fn reset_selected(&mut self, mode: ResetMode, messages: &mut Vec<String>, repo: &GitRepo) {
+        self.input_mode = InputMode::Normal;
+        let Some(commit) = self.items.get(self.selected) else {
+            return;
+        };
+        match reset(repo, &commit.id, mode) {
+            Ok(_) => messages.push(format!(
+                "Reset ({:?}) to {}.",
+                mode,
+                self.selected_short_id()
+            )),
+            Err(e) => messages.push(format!("Failed to reset: {}", e)),
+        }
+        self.update(repo);
+    }
+
+    pub fn handle_input(
+        &mut self,
+        key: KeyEvent,
+        messages: &mut Vec<String>,
+        keys: &KeyMap,
+        repo: &GitRepo,
+    ) -> Result<()> {
+        if let Some(dialog) = self.confirm.take() {
+            match dialog.handle_input(key) {
+                DialogResult::Open(dialog) => self.confirm = Some(dialog),
+                DialogResult::Confirmed(LogAction::Checkout(id)) => {
+                    self.checkout(&id, messages, repo)
+                }
+                DialogResult::Confirmed(LogAction::UndoLastCommit) => {
+                    match undo_last_commit(repo) {
+                        Ok(summary) => messages.push(format!(
+                            "Undid commit '{}'; its changes are staged.",
+                            summary
+                        )),
+                        Err(e) => messages.push(format!("Failed to undo commit: {:#}", e)),
+                    }
+                    self.update(repo);
                 }
+                DialogResult::Cancelled => messages.push("Cancelled.".to_string()),
             }
-            KeyCode::Enter => {
-                if !self.items.is_empty() {
-                    let commit = &self.items[self.selected];
-                    self.detailed_commit = Some(self.get_commit_detail(&commit.id)?);
+            return Ok(());
+        }
+
+        match self.input_mode {
+            InputMode::ChoosingReset => {
+                match key.code {
+                    KeyCode::Char('s') => self.reset_selected(ResetMode::Soft, messages, repo),
+                    KeyCode::Char('m') => self.reset_selected(ResetMode::Mixed, messages, repo),
+                    KeyCode::Char('h') => {
+                        self.input_mode = InputMode::ConfirmingHardReset;
+                        messages.push(
+                            "Warning: a hard reset permanently discards uncommitted changes. Press 'y' to confirm."
+                                .to_string(),
+                        );
+                    }
+                    KeyCode::Esc => {
+                        self.input_mode = InputMode::Normal;
+                        messages.push("Reset cancelled.".to_string());
+                    }
+                    _ => {}
                 }
+                return Ok(());
             }
-            KeyCode::Char('r') => {
-                self.update();
-                messages.push("Commit logs refreshed.".to_string());
+            InputMode::ConfirmingHardReset => {
+                if key.code == KeyCode::Char('y') {
+                    self.reset_selected(ResetMode::Hard, messages, repo);
+                } else {
+                    self.input_mode = InputMode::Normal;
+                    messages.push("Hard reset cancelled.".to_string());
+                }
+                return Ok(());
+            }
+            InputMode::Searching => {
+                match key.code {
+                    KeyCode::Enter => {
+                        self.input_mode = InputMode::Normal;
+                        if !self.search.is_empty() {
+                            messages.push(format!(
+                                "{} commits match '{}'.",
+                                self.items.len(),
+                                self.search
+                            ));
+                        }
+                    }
+                    KeyCode::Esc => {
+                        self.input_mode = InputMode::Normal;
+                        self.clear_search();
+                    }
+                    KeyCode::Char(c) => {
+                        self.search.push(c);
+                        self.apply_search();
+                    }
+                    KeyCode::Backspace => {
+                        self.search.pop();
+                        self.apply_search();
+                    }
+                    _ => {}
+                }
+                return Ok(());
+            }
+            InputMode::FilteringAuthor => {
+                match key.code {
+                    KeyCode::Enter => {
+                        self.input_mode = InputMode::Normal;
+                        self.author_filter = self.input.trim().to_string();
+                        self.input.clear();
+                        if self.author_filter.is_empty() {
+                            messages.push("Author filter cleared.".to_string());
+                        } else {
+                            messages.push(format!("Showing commits by '{}'.", self.author_filter));
+                        }
+                        self.selected = 0;
+                        self.update(repo);
+                    }
+                    KeyCode::Esc => {
+                        self.input_mode = InputMode::Normal;
+                        self.input.clear();
+                    }
+                    KeyCode::Char(c) => {
+                        self.input.push(c);
+                    }
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    _ => {}
+                }
+                return Ok(());
+            }
+            InputMode::Squashing(count) => {
+                match key.code {
+                    KeyCode::Enter => {
+                        let message = self.input.trim().to_string();
+                        if message.is_empty() {
+                            messages.push("The commit message cannot be empty.".to_string());
+                            return Ok(());
+                        }
+                        match squash_last(repo, count, &message) {
+                            Ok(id) => {
+                                messages.push(format!("Squashed {} commits into {:.7}.", count, id))
+                            }
+                            Err(e) => messages.push(format!("Failed to squash: {:#}", e)),
+                        }
+                        self.input_mode = InputMode::Normal;
+                        self.input.clear();
+                        self.update(repo);
+                    }
+                    KeyCode::Esc => {
+                        self.input_mode = InputMode::Normal;
+                        self.input.clear();
+                    }
+                    KeyCode::Char(c) => self.input.push(c),
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    _ => {}
+                }
+                return Ok(());
+            }
+            InputMode::CreatingBranch => {
+                match key.code {
+                    KeyCode::Enter => {
+                        let name = self.input.trim().to_string();
+                        let Some(commit) = self.items.get(self.selected) else {
+                            return Ok(());
+                        };
+                        match create_branch(repo, &name, Some(&commit.id)) {
+                            Ok(_) => messages.push(format!(
+                                "Created branch '{}' at {}.",
+                                name,
+                                self.selected_short_id()
+                            )),
+                            Err(e) => messages.push(format!("Failed to create branch: {}", e)),
+                        }
+                        self.input_mode = InputMode::Normal;
+                        self.input.clear();
+                    }
+                    KeyCode::Esc => {
+                        self.input_mode = InputMode::Normal;
+                        self.input.clear();
+                    }
+                    KeyCode::Char(c) => self.input.push(c),
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    _ => {}
+                }
+                return Ok(());
+            }
+            InputMode::ApplyingPatch => {
+                match key.code {
+                    KeyCode::Enter if !self.input.trim().is_empty() => {
+                        self.input = self.input.trim().to_string();
+                        self.input_mode = InputMode::ChoosingPatchMode;
+                    }
+                    KeyCode::Esc => {
+                        self.input_mode = InputMode::Normal;
+                        self.input.clear();
+                    }
+                    KeyCode::Char(c) => self.input.push(c),
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    _ => {}
+                }
+                return Ok(());
+            }
+            InputMode::ChoosingPatchMode => {
+                match key.code {
+                    KeyCode::Char(c @ ('c' | 'w')) => {
+                        self.apply_patch(c == 'c', messages, repo);
+                        self.input_mode = InputMode::Normal;
+                        self.input.clear();
+                    }
+                    KeyCode::Esc => {
+                        self.input_mode = InputMode::Normal;
+                        self.input.clear();
+                        messages.push("Patch not applied.".to_string());
+                    }
+                    _ => {}
+                }
+                return Ok(());
+            }
+            InputMode::GoingTo => {
+                match key.code {
+                    KeyCode::Enter => {
+                        let spec = self.input.trim().to_string();
+                        self.input_mode = InputMode::Normal;
+                        self.input.clear();
+                        if !spec.is_empty() {
+                            self.go_to(&spec, messages, repo)?;
+                        }
+                    }
+                    KeyCode::Esc => {
+                        self.input_mode = InputMode::Normal;
+                        self.input.clear();
+                    }
+                    KeyCode::Char(c) => self.input.push(c),
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    _ => {}
+                }
+                return Ok(());
+            }
+            InputMode::Normal => {}
+        }
+
+        if let Some(detail) = &self.detailed_commit {
+            let lines = detail.lines();
+            // Rows on screen: wrapped lines take several.
+            let rows = if self.detail_wrap {
+                wrapped_height(&lines, self.detail_area.width.saturating_sub(2))
+            } else {
+                lines.len()
+            };
+            let max_scroll = rows.saturating_sub(1).min(u16::MAX as usize) as u16;
+            // The rows inside the borders, as `Motion::apply` pages lists.
+            let page = self.detail_area.height.saturating_sub(2).max(1);
+            if keys.is("log.copy", &key) {
+                copy_reporting(&detail.id, &detail.id[..7], messages);
+                return Ok(());
+            }
+            if keys.is("log.wrap", &key) {
+                self.detail_wrap = !self.detail_wrap;
+                self.detail_hscroll = 0;
+                return Ok(());
             }
-            KeyCode::Esc => {
-                if self.detailed_commit.is_some() {
+            if !self.detail_wrap {
+                if let Some(column) = horizontal_scroll(self.detail_hscroll, &key) {
+                    self.detail_hscroll = column;
+                    return Ok(());
+                }
+            }
+            match key.code {
+                KeyCode::Down => {
+                    self.detail_scroll = self.detail_scroll.saturating_add(1).min(max_scroll);
+                }
+                KeyCode::Up => {
+                    self.detail_scroll = self.detail_scroll.saturating_sub(1);
+                }
+                KeyCode::PageDown => {
+                    self.detail_scroll = self.detail_scroll.saturating_add(page).min(max_scroll);
+                }
+                KeyCode::PageUp => {
+                    self.detail_scroll = self.detail_scroll.saturating_sub(page);
+                }
+                KeyCode::Esc => {
                     self.detailed_commit = None;
                 }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        if let Some(motion) = Motion::from_key(&key, keys) {
+            self.selected = motion.apply(self.selected, self.items.len(), self.list_area);
+            if self.selected + LOAD_MORE_MARGIN >= self.items.len() {
+                self.load_more(repo);
+            }
+            return Ok(());
+        }
+
+        match (keys.action("log", &key), key.code) {
+            (Some("search"), _) => {
+                self.input_mode = InputMode::Searching;
+            }
+            (Some("filter_author"), _) => {
+                self.input_mode = InputMode::FilteringAuthor;
+                self.input = self.author_filter.clone();
+            }
+            (Some("next_match"), _) if !self.search.is_empty() && !self.items.is_empty() => {
+                self.selected = (self.selected + 1) % self.items.len();
+            }
+            (Some("prev_match"), _) if !self.search.is_empty() && !self.items.is_empty() => {
+                self.selected = (self.selected + self.items.len() - 1) % self.items.len();
+            }
+            (Some("reset"), _) if !self.items.is_empty() => {
+                self.input_mode = InputMode::ChoosingReset;
+                messages.push(format!(
+                    "Reset to {}? Choose [s]oft, [m]ixed or [h]ard.",
+                    self.selected_short_id()
+                ));
+            }
+            (Some("mark"), _) => {
+                if let Some(commit) = self.items.get(self.selected).filter(|c| c.date.is_some()) {
+                    let short_id = self.selected_short_id();
+                    match self.marked.take() {
+                        Some(marked) if marked == commit.id => {
+                            messages.push(format!("Unmarked {}.", short_id));
+                        }
+                        Some(marked) => {
+                            self.diff_requested = Some(DiffRequest {
+                                label: format!("{:.7}..{:.7}", marked, commit.id),
+                                old: marked,
+                                new: commit.id.clone(),
+                            })
+                        }
+                        None => {
+                            messages.push(format!(
+                                "Marked {}. Select another commit and press Space to diff them.",
+                                short_id
+                            ));
+                            self.marked = Some(commit.id.clone());
+                        }
+                    }
+                }
+            }
+            (Some("copy"), _) => {
+                if let Some(commit) = self.items.get(self.selected).filter(|c| c.date.is_some()) {
+                    copy_reporting(&commit.id, &self.selected_short_id(), messages);
+                }
+            }
+            (Some("revert"), _) => {
+                if let Some(commit) = self.items.get(self.selected) {
+                    let short_id = self.selected_short_id();
+                    match revert_commit(repo, &commit.id) {
+                        Ok(_) => messages.push(format!("Reverted {}", short_id)),
+                        Err(e) => messages.push(format!("Failed to revert {}: {}", short_id, e)),
+                    }
+                    self.update(repo);
+                }
+            }
+            (Some("undo"), _) => {
+                let summary = repo
+                    .head()
+                    .and_then(|head| head.peel_to_commit())
+                    .map(|commit| commit.summary().unwrap_or("").to_string());
+                match summary {
+                    Ok(summary) => {
+                        self.confirm = Some(ConfirmDialog::new(
+                            format!(
+                                "Undo the last commit '{}'? Its changes stay staged.",
+                                summary
+                            ),
+                            LogAction::UndoLastCommit,
+                        ))
+                    }
+                    Err(_) => messages.push("There is no commit to undo.".to_string()),
+                }
+            }
+            (Some("export_patch"), _) => {
+                if let Some(commit) = self.items.get(self.selected).filter(|c| c.date.is_some()) {
+                    // Like `git format-patch`, next to the work tree.
+                    let out_dir = repo.workdir().unwrap_or_else(|| repo.path());
+                    match format_patch(repo, &commit.id, out_dir) {
+                        Ok(path) => messages.push(format!(
+                            "Saved {} as '{}'.",
+                            self.selected_short_id(),
+                            path.display()
+                        )),
+                        Err(e) => messages.push(format!("Failed to export patch: {:#}", e)),
+                    }
+                }
+            }
+            (Some("reword"), _) => {
+                if let Some(commit) = self.items.get(self.selected).filter(|c| c.date.is_some()) {
+                    let head = repo.head().ok().and_then(|head| head.target());
+                    if head.is_some_and(|head| head.to_string() == commit.id) {
+                        self.reword_requested = true;
+                    } else {
+                        messages.push(
+                            "Only the HEAD commit can be reworded; older commits need an interactive rebase."
+                                .to_string(),
+                        );
+                    }
+                }
+            }
+            (Some("rebase"), _) => {
+                if let Some(commit) = self.items.get(self.selected).filter(|c| c.date.is_some()) {
+                    self.rebase_requested = Some(commit.id.clone());
+                }
+            }
+            (Some("squash"), _) => {
+                if let Some(commit) = self.items.get(self.selected).filter(|c| c.date.is_some()) {
+                    match commits_since(repo, &commit.id) {
+                        Ok(1) => messages.push(
+                            "Select an older commit; everything from it up to HEAD is squashed."
+                                .to_string(),
+                        ),
+                        Ok(count) => {
+                            self.input_mode = InputMode::Squashing(count);
+                            self.input = commit.message.clone();
+                        }
+                        Err(e) => messages.push(format!("Cannot squash: {:#}", e)),
+                    }
+                }
+            }
+            (Some("apply_patch"), _) => {
+                self.input_mode = InputMode::ApplyingPatch;
+                self.input.clear();
+            }
+            (Some("goto"), _) => {
+                self.input_mode = InputMode::GoingTo;
+                self.input.clear();
+            }
+            (Some("branch"), _)
+                if self
+                    .items
+                    .get(self.selected)
+                    .is_some_and(|c| c.date.is_some()) =>
+            {
+                self.input_mode = InputMode::CreatingBranch;
+                self.input.clear();
+            }
+            (Some("checkout"), _) => {
+                if let Some(commit) = self.items.get(self.selected) {
+                    let id = commit.id.clone();
+                    if has_uncommitted_changes(repo).unwrap_or(false) {
+                        self.confirm = Some(ConfirmDialog::new(
+                            format!("You have uncommitted changes. Check out {:.7} anyway?", id),
+                            LogAction::Checkout(id),
+                        ));
+                    } else {
+                        self.checkout(&id, messages, repo);
+                    }
+                }
+            }
+            (Some("details"), _) if !self.items.is_empty() => {
+                let commit = &self.items[self.selected];
+                self.detailed_commit = Some(self.get_commit_detail(&commit.id, repo)?);
+                self.detail_scroll = 0;
+                self.detail_hscroll = 0;
+            }
+            (Some("toggle_date"), _) => {
+                self.date_format = match self.date_format {
+                    DateFormat::Absolute => DateFormat::Relative,
+                    DateFormat::Relative => DateFormat::Absolute,
+                };
+            }
+            (Some("graph"), _) => {
+                self.show_graph = !self.show_graph;
+                // The graph needs the topological order, which the walk
+                // only uses while it is shown.
+                self.update(repo);
+            }
+            (Some("first_parent"), _) => {
+                self.first_parent = !self.first_parent;
+                self.update(repo);
+                messages.push(if self.first_parent {
+                    "Showing only the mainline: merged branches are hidden.".to_string()
+                } else {
+                    "Showing every commit.".to_string()
+                });
+            }
+            (Some("email"), _) => self.show_email = !self.show_email,
+            (Some("diffstat"), _) => {
+                self.show_diffstat = !self.show_diffstat;
+                self.load_diffstats(repo);
+            }
+            (Some("refresh"), _) => {
+                self.update(repo);
+                messages.push("Commit logs refreshed.".to_string());
+            }
+            (_, KeyCode::Esc) => {
+                if !self.search.is_empty() {
+                    self.clear_search();
+                } else if !self.author_filter.is_empty() {
+                    self.author_filter.clear();
+                    messages.push("Author filter cleared.".to_string());
+                    self.update(repo);
+                } else if let Some(path) = self.path_filter.take() {
+                    messages.push(format!("Showing full history instead of '{}'.", path));
+                    self.update(repo);
+                }
             }
             _ => {}
         }
         Ok(())
     }
 
-    pub fn update(&mut self) {
-        self.items.clear();
-        self.detailed_commit = None;
-        match GitRepo::open(".") {
-            Ok(repo) => {
-                let mut revwalk = match repo.revwalk() {
-                    Ok(rw) => rw,
-                    Err(e) => {
-                        self.items.push(CommitItem {
-                            id: "Error".to_string(),
-                            author: "Error".to_string(),
-                            date: "".to_string(),
-                            message: format!("Error creating revwalk: {}", e),
-                        });
-                        return;
-                    }
-                };
+    /// Reloads the log from HEAD as far as it was loaded before, keeping the
+    /// selected commit and any open detail.
+    pub fn update(&mut self, repo: &GitRepo) {
+        let selected_id = self.items.get(self.selected).map(|c| c.id.clone());
+        let walked = self.walked;
+        self.all_items.clear();
+        self.walked = 0;
+        self.walk = None;
+        self.exhausted = false;
+        self.graph = CommitGraph::default();
+        self.load_more(repo);
+        // Filters can leave a page empty, and with nothing to scroll the next
+        // page would never load.
+        while (self.all_items.len() < LOAD_MORE_MARGIN || self.walked < walked) && !self.exhausted {
+            self.load_more(repo);
+        }
 
-                if let Err(e) = revwalk.push_head() {
-                    self.items.push(CommitItem {
-                        id: "Error".to_string(),
-                        author: "Error".to_string(),
-                        date: "".to_string(),
-                        message: format!("Error pushing HEAD: {}", e),
-                    });
-                    return;
-                }
-
-                revwalk
-                    .set_sorting(git2::Sort::TIME | git2::Sort::REVERSE)
-                    .unwrap();
-                use anyhow::Context;
-                for oid_result in revwalk {
-                    match oid_result {
-                        Ok(oid) => match repo.find_commit(oid) {
-                            Ok(commit) => {
-                                let author =
-                                    commit.author().name().unwrap_or("Unknown").to_string();
-                                let message = commit
-                                    .message()
-                                    .unwrap_or("")
-                                    .split('\n')
-                                    .next()
-                                    .unwrap_or("");
-
-                                // Extract and format the commit date
-                                let time = commit.time();
-                                let timestamp = time.seconds();
-                                let naive = NaiveDateTime::from_timestamp_opt(timestamp, 0)
-                                    .unwrap_or_else(|| NaiveDateTime::from_timestamp(0, 0));
-                                let datetime = naive.format("%Y-%m-%d %H:%M:%S").to_string();
-
-                                self.items.push(CommitItem {
-                                    id: commit.id().to_string(),
-                                    author: author.to_string(),
-                                    date: datetime, // Assign formatted date
-                                    message: message.to_string(),
-                                });
-                            }
-                            Err(e) => {
-                                self.items.push(CommitItem {
-                                    id: "Error".to_string(),
-                                    author: "Error".to_string(),
-                                    date: "".to_string(),
-                                    message: format!("Error finding commit {}: {}", oid, e),
-                                });
-                            }
-                        },
-                        Err(e) => {
-                            self.items.push(CommitItem {
-                                id: "Error".to_string(),
-                                author: "Error".to_string(),
-                                date: "".to_string(),
-                                message: format!("Error iterating oid: {}", e),
-                            });
+        if let Some(index) = selected_id.and_then(|id| self.items.iter().position(|c| c.id == id)) {
+            self.selected = index;
+        } else if self.selected >= self.items.len() && self.selected > 0 {
+            self.selected = self.items.len() - 1;
+        }
+    }
+
+    /// Walks the next `PAGE_SIZE` commits after the ones already loaded.
+    pub fn load_more(&mut self, repo: &GitRepo) {
+        if self.exhausted {
+            return;
+        }
+
+        // Topological order keeps each branch's commits below their children,
+        // which the graph relies on, but costs a walk of the whole history.
+        let walk = match self.walk.take() {
+            Some(walk) => Ok(walk),
+            None => CommitWalk::new(repo, self.show_graph, self.first_parent),
+        };
+        let mut walk = match walk {
+            Ok(walk) => walk,
+            Err(e) => {
+                self.push_error(format!("Error walking the history from HEAD: {}", e));
+                return;
+            }
+        };
+
+        let mut walked = 0;
+        while walked < PAGE_SIZE {
+            let Some(oid_result) = walk.next_commit(repo) else {
+                self.exhausted = true;
+                break;
+            };
+            walked += 1;
+            match oid_result {
+                Ok(oid) => match repo.find_commit(oid) {
+                    Ok(commit) => {
+                        // Hidden commits still occupy their lanes.
+                        let parents: Vec<git2::Oid> = commit.parent_ids().collect();
+                        // Merged-in parents are never walked, so leave them
+                        // out of the graph.
+                        let lanes = if self.first_parent {
+                            &parents[..parents.len().min(1)]
+                        } else {
+                            &parents[..]
+                        };
+                        let graph = self.graph.row(oid, lanes);
+                        if !self.author_matches(&commit) || !self.path_matches(repo, &commit) {
+                            continue;
                         }
+                        let signature = commit.author();
+                        let author = signature.name().unwrap_or("Unknown").to_string();
+                        let message = commit
+                            .message()
+                            .unwrap_or("")
+                            .split('\n')
+                            .next()
+                            .unwrap_or("");
+
+                        self.all_items.push(CommitItem {
+                            id: commit.id().to_string(),
+                            author: author.to_string(),
+                            email: author_email(&signature),
+                            date: Some(commit.time()),
+                            message: message.to_string(),
+                            graph,
+                            merge: parents.len() > 1,
+                        });
+                    }
+                    Err(e) => {
+                        self.all_items.push(CommitItem::error(format!(
+                            "Error finding commit {}: {}",
+                            oid, e
+                        )));
                     }
+                },
+                Err(e) => {
+                    self.all_items
+                        .push(CommitItem::error(format!("Error iterating oid: {}", e)));
                 }
             }
-            Err(e) => {
-                self.items.push(CommitItem {
-                    id: "Error".to_string(),
-                    author: "Error".to_string(),
-                    date: "".to_string(),
-                    message: format!("Error opening repository: {}", e),
-                });
+        }
+        self.walk = Some(walk);
+        self.walked += walked;
+
+        self.filter_items();
+    }
+
+    /// Computes the diffstat of the commits in view that don't have one yet;
+    /// the rest of the history is left until it is scrolled to.
+    pub fn load_diffstats(&mut self, repo: &GitRepo) {
+        if !self.show_diffstat || self.detailed_commit.is_some() {
+            return;
+        }
+        let offset = scroll_offset(self.offset, self.selected, self.list_area);
+        let page = self.list_area.height.saturating_sub(2) as usize;
+        for commit in self.items.iter().skip(offset).take(page) {
+            if self.diffstats.contains_key(&commit.id) {
+                continue;
+            }
+            let Ok(oid) = commit.id.parse() else {
+                continue;
+            };
+            match repo
+                .find_commit(oid)
+                .map_err(anyhow::Error::from)
+                .and_then(|c| commit_diffstat(repo, &c))
+            {
+                Ok(counts) => {
+                    self.diffstats.insert(commit.id.clone(), counts);
+                }
+                Err(e) => log::error!("{:?}", e),
             }
         }
+    }
 
-        // Reset selection if necessary
-        if self.selected >= self.items.len() && self.selected > 0 {
-            self.selected = self.items.len() - 1;
+    /// Shows `message` as the last log entry and stops loading further pages.
+    fn push_error(&mut self, message: String) {
+        self.all_items.push(CommitItem::error(message));
+        self.exhausted = true;
+        self.filter_items();
+    }
+
+    /// Whether `commit` passes the author filter (by name or email).
+    fn author_matches(&self, commit: &git2::Commit) -> bool {
+        if self.author_filter.is_empty() {
+            return true;
         }
+        let author = commit.author();
+        [author.name(), author.email()]
+            .iter()
+            .flatten()
+            .any(|field| find_ignore_case(field, &self.author_filter).is_some())
+    }
+
+    /// Applies the patch file named in `input`, committing it if asked.
+    fn apply_patch(&mut self, commit: bool, messages: &mut Vec<String>, repo: &GitRepo) {
+        let path = repo
+            .workdir()
+            .unwrap_or_else(|| repo.path())
+            .join(&self.input);
+        match apply_patch(repo, &path, commit) {
+            Ok(Some(message)) => messages.push(format!(
+                "Applied and committed '{}'.",
+                message.lines().next().unwrap_or("")
+            )),
+            Ok(None) => messages.push(format!(
+                "Applied '{}' to the work tree; review and commit it from Status.",
+                self.input
+            )),
+            Err(e) => messages.push(format!("Failed to apply '{}': {:#}", self.input, e)),
+        }
+        self.update(repo);
+    }
+
+    /// Selects the commit `spec` names, loading pages until it shows up, or
+    /// opens its details when the list doesn't include it.
+    fn go_to(&mut self, spec: &str, messages: &mut Vec<String>, repo: &GitRepo) -> Result<()> {
+        let id = match resolve_commit(repo, spec) {
+            Ok(id) => id.to_string(),
+            Err(e) => {
+                messages.push(format!("{:#}", e));
+                return Ok(());
+            }
+        };
+        loop {
+            if let Some(index) = self.items.iter().position(|commit| commit.id == id) {
+                self.selected = index;
+                return Ok(());
+            }
+            if self.exhausted {
+                break;
+            }
+            self.load_more(repo);
+        }
+        messages.push(format!(
+            "{:.7} isn't in the list (not on this branch, or filtered out); showing its details.",
+            id
+        ));
+        self.detailed_commit = Some(self.get_commit_detail(&id, repo)?);
+        self.detail_scroll = 0;
+        self.detail_hscroll = 0;
+        Ok(())
+    }
+
+    /// Whether `commit` passes the path filter.
+    fn path_matches(&self, repo: &GitRepo, commit: &git2::Commit) -> bool {
+        let Some(path) = &self.path_filter else {
+            return true;
+        };
+        commit_touches_path(repo, commit, path).unwrap_or_else(|e| {
+            log::error!("{:?}", e);
+            false
+        })
+    }
+
+    /// Shows only the history of `path`, from the newest commit.
+    pub fn follow_path(&mut self, path: &str, repo: &GitRepo) {
+        self.path_filter = Some(path.to_string());
+        self.search.clear();
+        self.detailed_commit = None;
+        self.update(repo);
+        self.selected = 0;
+    }
+
+    /// Rebuilds `items` from `all_items` using the current search query.
+    fn filter_items(&mut self) {
+        self.items = self
+            .all_items
+            .iter()
+            .filter(|commit| commit.matches(&self.search))
+            .cloned()
+            .collect();
     }
 
-    fn get_commit_detail(&self, commit_id: &str) -> Result<CommitDetail> {
-        let repo = GitRepo::open(".").context("Failed to open repository")?;
+    /// Re-filters after the query changed and selects the first match.
+    fn apply_search(&mut self) {
+        self.filter_items();
+        self.selected = 0;
+    }
+
+    /// Drops the search query, keeping the selected commit highlighted.
+    fn clear_search(&mut self) {
+        let selected_id = self
+            .items
+            .get(self.selected)
+            .map(|commit| commit.id.clone());
+        self.search.clear();
+        self.filter_items();
+        self.selected = selected_id
+            .and_then(|id| self.items.iter().position(|commit| commit.id == id))
+            .unwrap_or(0);
+    }
+
+    fn get_commit_detail(&self, commit_id: &str, repo: &GitRepo) -> Result<CommitDetail> {
         let oid = commit_id.parse()?;
         let commit = repo
             .find_commit(oid)
@@ -237,24 +1240,137 @@ impl LogView {
 
         let parents = commit
             .parents()
-            .map(|parent| parent.id().to_string())
+            .map(|parent| format!("{} {}", parent.id(), parent.summary().unwrap_or("")))
             .collect();
 
+        let stats = commit_stats(repo, &commit)?;
+
         // Format the commit date
-        let time = commit.time();
-        let timestamp = time.seconds();
-        let naive = NaiveDateTime::from_timestamp_opt(timestamp, 0)
-            .unwrap_or_else(|| NaiveDateTime::from_timestamp(0, 0));
-        let datetime = naive.format("%Y-%m-%d %H:%M:%S").to_string();
+        let datetime = format_commit_time(commit.time());
 
         let detail = CommitDetail {
             id: commit.id().to_string(),
             author: commit.author().name().unwrap_or("Unknown").to_string(),
+            email: author_email(&commit.author()),
             date: datetime, // Assign formatted date
             message: commit.message().unwrap_or("").to_string(),
             parents,
+            stats,
         };
 
         Ok(detail)
     }
 }
+
+/// Rows `lines` take word-wrapped to `width` columns, as the detail pane
+/// draws them.
+fn wrapped_height(lines: &[String], width: u16) -> usize {
+    let width = width.max(1) as usize;
+    lines
+        .iter()
+        .map(|line| {
+            let (mut rows, mut column) = (1, 0);
+            for word in line.split_whitespace() {
+                let word_width = Span::raw(word).width();
+                let needed = if column == 0 {
+                    word_width
+                } else {
+                    column + 1 + word_width
+                };
+                if needed <= width {
+                    column = needed;
+                    continue;
+                }
+                if column > 0 {
+                    rows += 1;
+                }
+                // A word wider than the pane is broken across rows.
+                rows += (word_width - 1) / width;
+                column = (word_width - 1) % width + 1;
+            }
+            rows
+        })
+        .sum()
+}
+
+/// The email in `signature`, unless it is empty or not valid UTF-8.
+fn author_email(signature: &git2::Signature) -> Option<String> {
+    signature
+        .email()
+        .filter(|email| !email.is_empty())
+        .map(str::to_string)
+}
+
+/// `Name <email>`, or just the name when there is no email.
+fn format_author(name: &str, email: Option<&str>) -> String {
+    match email {
+        Some(email) => format!("{} <{}>", name, email),
+        None => name.to_string(),
+    }
+}
+
+/// Formats a commit time in the committer's own timezone, like `git log`
+/// does: `2024-01-02 15:04:05 +0200`.
+fn format_commit_time(time: git2::Time) -> String {
+    let offset = FixedOffset::east_opt(time.offset_minutes() * 60)
+        .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+    DateTime::from_timestamp(time.seconds(), 0)
+        .unwrap_or_default()
+        .with_timezone(&offset)
+        .format("%Y-%m-%d %H:%M:%S %z")
+        .to_string()
+}
+
+/// Formats a commit time relative to `now`, e.g. `3 hours ago`. Dates more
+/// than a year old or in the future are shown in full instead.
+fn format_relative_time(time: git2::Time, now: DateTime<Utc>) -> String {
+    let elapsed = now.timestamp() - time.seconds();
+    let (count, unit) = match elapsed {
+        s if !(0..365 * 86_400).contains(&s) => return format_commit_time(time),
+        s if s < 60 => (s, "second"),
+        s if s < 3_600 => (s / 60, "minute"),
+        s if s < 86_400 => (s / 3_600, "hour"),
+        s if s < 30 * 86_400 => (s / 86_400, "day"),
+        s => (s / (30 * 86_400), "month"),
+    };
+    format!(
+        "{} {}{} ago",
+        count,
+        unit,
+        if count == 1 { "" } else { "s" }
+    )
+}
+
+/// Finds `needle` in `haystack` ignoring ASCII case, returning the byte range.
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<(usize, usize)> {
+    if needle.is_empty() {
+        return Some((0, 0));
+    }
+    haystack
+        .char_indices()
+        .map(|(start, _)| (start, start + needle.len()))
+        .find(|&(start, end)| {
+            end <= haystack.len()
+                && haystack.is_char_boundary(end)
+                && haystack[start..end].eq_ignore_ascii_case(needle)
+        })
+}
+
+/// Splits `content` into spans with every occurrence of `query` highlighted.
+fn highlight_matches(content: &str, query: &str, theme: &Theme) -> Spans<'static> {
+    // Reversed, so the match reads on any background.
+    let highlight = Style::default()
+        .fg(theme.highlight)
+        .add_modifier(Modifier::REVERSED);
+    let mut spans = Vec::new();
+    let mut rest = content;
+    if !query.is_empty() {
+        while let Some((start, end)) = find_ignore_case(rest, query) {
+            spans.push(Span::raw(rest[..start].to_string()));
+            spans.push(Span::styled(rest[start..end].to_string(), highlight));
+            rest = &rest[end..];
+        }
+    }
+    spans.push(Span::raw(rest.to_string()));
+    Spans::from(spans)
+}
@@ -0,0 +1,152 @@
+use crate::keymap::KeyMap;
+use crate::theme::Theme;
+use crate::tui_module::navigation::Motion;
+use chrono::{DateTime, Local};
+use crossterm::event::{KeyCode, KeyEvent};
+use std::collections::VecDeque;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::Style,
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+/// Messages kept when no `--max-messages` is given.
+pub const DEFAULT_LIMIT: usize = 500;
+
+/// Timestamped history behind the messages pane. The newest messages are
+/// shown at the bottom; once focused, the pane scrolls back through older
+/// ones.
+pub struct MessageLog {
+    entries: VecDeque<(DateTime<Local>, String)>,
+    limit: usize,
+    /// Messages scrolled past at the bottom; 0 follows the newest.
+    pub scroll: usize,
+    /// Whether keys go to the pane instead of the active view.
+    pub focused: bool,
+    /// Rows inside the pane when it was last drawn, for paging.
+    page: usize,
+}
+
+impl Default for MessageLog {
+    fn default() -> MessageLog {
+        MessageLog::new(DEFAULT_LIMIT)
+    }
+}
+
+impl MessageLog {
+    /// Keeps at most `limit` messages, dropping the oldest first.
+    pub fn new(limit: usize) -> MessageLog {
+        MessageLog {
+            entries: VecDeque::new(),
+            limit: limit.max(1),
+            scroll: 0,
+            focused: false,
+            page: 1,
+        }
+    }
+
+    pub fn push(&mut self, message: String) {
+        if self.entries.len() == self.limit {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((Local::now(), message));
+        // Stay on the same messages while reading back through the log.
+        if self.scroll > 0 {
+            self.scroll = (self.scroll + 1).min(self.max_scroll());
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.scroll = 0;
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.entries.len().saturating_sub(1)
+    }
+
+    pub fn render<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        area: Rect,
+        theme: &Theme,
+        keys: &KeyMap,
+    ) {
+        let width = area.width.saturating_sub(2).max(1) as usize;
+        let height = area.height.saturating_sub(2) as usize;
+        self.page = height.max(1);
+
+        // Walk back from the newest shown message while the wrapped lines fit.
+        let mut lines = Vec::new();
+        let mut rows = 0;
+        for (time, message) in self.entries.iter().rev().skip(self.scroll) {
+            let line = format!("{} {}", time.format("%H:%M:%S"), message);
+            let line_rows = line
+                .lines()
+                .map(|part| part.chars().count().max(1).div_ceil(width))
+                .sum::<usize>()
+                .max(1);
+            if rows + line_rows > height && !lines.is_empty() {
+                break;
+            }
+            rows += line_rows;
+            lines.push(line);
+        }
+        lines.reverse();
+
+        let title = if self.focused {
+            format!(
+                "Messages ({}/{}) - Up/Down/PgUp/PgDn scroll, {} clear, Esc back",
+                self.entries.len() - self.scroll.min(self.entries.len()),
+                self.entries.len(),
+                keys.key_for("messages.clear")
+            )
+        } else if self.scroll > 0 {
+            format!("Messages ({} newer below)", self.scroll)
+        } else {
+            "Messages".to_string()
+        };
+        let border_style = if self.focused {
+            theme.highlight_style()
+        } else {
+            theme.border_style()
+        };
+        let paragraph = Paragraph::new(lines.join("\n"))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(border_style)
+                    .title(title),
+            )
+            .style(Style::default().fg(theme.messages))
+            .wrap(Wrap { trim: true });
+        f.render_widget(paragraph, area);
+    }
+
+    /// Scrolls or clears the log; Esc hands the keys back to the view.
+    pub fn handle_input(&mut self, key: KeyEvent, keys: &KeyMap) {
+        if keys.is("messages.clear", &key) {
+            self.clear();
+            return;
+        }
+        if key.code == KeyCode::Esc {
+            self.focused = false;
+            return;
+        }
+        // Motions are in terms of older (up) and newer (down) messages.
+        let max = self.max_scroll();
+        self.scroll = match Motion::from_key(&key, keys) {
+            Some(Motion::Up) => (self.scroll + 1).min(max),
+            Some(Motion::Down) => self.scroll.saturating_sub(1),
+            Some(Motion::PageUp) => (self.scroll + self.page).min(max),
+            Some(Motion::PageDown) => self.scroll.saturating_sub(self.page),
+            Some(Motion::HalfPageUp) => (self.scroll + (self.page / 2).max(1)).min(max),
+            Some(Motion::HalfPageDown) => self.scroll.saturating_sub((self.page / 2).max(1)),
+            Some(Motion::Top) => max,
+            Some(Motion::Bottom) => 0,
+            None => self.scroll,
+        };
+    }
+}
@@ -1,6 +1,24 @@
+pub mod blame_view;
 pub mod branch_view;
+pub mod clone_view;
+pub mod commit_graph;
 pub mod commit_view;
+pub mod commit_walk;
+pub mod config_view;
+pub mod confirm_dialog;
+pub mod conflict_view;
+pub mod diff_view;
 pub mod help_view;
+pub mod interactive_rebase_view;
 pub mod log_view;
-pub mod status_view;
+pub mod message_log;
+pub mod mouse;
+pub mod navigation;
+pub mod progress;
+pub mod reflog_view;
+pub mod remote_view;
+pub mod spinner;
+pub mod stash_view;
+pub mod tag_view;
 pub mod views;
+pub mod welcome_view;
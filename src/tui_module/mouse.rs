@@ -0,0 +1,14 @@
+use crossterm::event::MouseEvent;
+use tui::layout::Rect;
+
+/// The index of the list row under the mouse, for a bordered list drawn
+/// from its first item in `area`.
+pub fn clicked_row(area: Rect, event: &MouseEvent) -> Option<usize> {
+    let inside_x = event.column > area.x && event.column + 1 < area.x + area.width;
+    let inside_y = event.row > area.y && event.row + 1 < area.y + area.height;
+    if inside_x && inside_y {
+        Some((event.row - area.y - 1) as usize)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,87 @@
+use crate::keymap::KeyMap;
+use crossterm::event::{KeyCode, KeyEvent};
+use tui::layout::Rect;
+
+/// A movement of the selection in a list view.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Motion {
+    Up,
+    Down,
+    Top,
+    Bottom,
+    HalfPageUp,
+    HalfPageDown,
+    PageUp,
+    PageDown,
+}
+
+impl Motion {
+    /// Recognises the arrow keys, PageUp/PageDown and Home/End, which are
+    /// fixed, and the `nav` bindings of `keys` (vim's `j`/`k`, `g`/`G` and
+    /// `Ctrl+d`/`Ctrl+u` by default). Views only ask while no input mode is
+    /// active.
+    pub fn from_key(key: &KeyEvent, keys: &KeyMap) -> Option<Motion> {
+        match key.code {
+            KeyCode::Up => return Some(Motion::Up),
+            KeyCode::Down => return Some(Motion::Down),
+            KeyCode::PageUp => return Some(Motion::PageUp),
+            KeyCode::PageDown => return Some(Motion::PageDown),
+            KeyCode::Home => return Some(Motion::Top),
+            KeyCode::End => return Some(Motion::Bottom),
+            _ => {}
+        }
+        match keys.action("nav", key)? {
+            "up" => Some(Motion::Up),
+            "down" => Some(Motion::Down),
+            "top" => Some(Motion::Top),
+            "bottom" => Some(Motion::Bottom),
+            "half_page_up" => Some(Motion::HalfPageUp),
+            "half_page_down" => Some(Motion::HalfPageDown),
+            _ => None,
+        }
+    }
+
+    /// The selection after moving from `selected` in a list of `len` items
+    /// drawn in `area`, clamped to the list.
+    pub fn apply(self, selected: usize, len: usize, area: Rect) -> usize {
+        let last = len.saturating_sub(1);
+        // The rows inside the borders, at least one.
+        let page = (area.height.saturating_sub(2) as usize).max(1);
+        let half_page = (page / 2).max(1);
+        match self {
+            Motion::Up => selected.saturating_sub(1),
+            Motion::Down => (selected + 1).min(last),
+            Motion::Top => 0,
+            Motion::Bottom => last,
+            Motion::HalfPageUp => selected.saturating_sub(half_page),
+            Motion::HalfPageDown => (selected + half_page).min(last),
+            Motion::PageUp => selected.saturating_sub(page),
+            Motion::PageDown => (selected + page).min(last),
+        }
+    }
+}
+
+/// The first row to draw so that `selected` stays visible in a bordered list
+/// drawn in `area`, scrolling as little as possible from `offset`.
+pub fn scroll_offset(offset: usize, selected: usize, area: Rect) -> usize {
+    let page = (area.height.saturating_sub(2) as usize).max(1);
+    if selected < offset {
+        selected
+    } else if selected >= offset + page {
+        selected + 1 - page
+    } else {
+        offset
+    }
+}
+
+/// Columns moved per Left/Right press in views that scroll sideways.
+const HORIZONTAL_STEP: u16 = 8;
+
+/// The column to draw from after `key`, when it is Left or Right.
+pub fn horizontal_scroll(column: u16, key: &KeyEvent) -> Option<u16> {
+    match key.code {
+        KeyCode::Left => Some(column.saturating_sub(HORIZONTAL_STEP)),
+        KeyCode::Right => Some(column.saturating_add(HORIZONTAL_STEP)),
+        _ => None,
+    }
+}
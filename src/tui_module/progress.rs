@@ -0,0 +1,53 @@
+use crate::git_utils::Transfer;
+use crate::theme::Theme;
+use crate::tui_module::confirm_dialog::centered_rect;
+use std::time::Duration;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::Style,
+    widgets::{Block, Borders, Clear, Gauge},
+    Frame,
+};
+
+/// Draws a transfer as a gauge popup centered over `area`, labelled with the
+/// object count and the average throughput over `elapsed`.
+pub fn render_transfer<B: Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    title: &str,
+    transfer: &Transfer,
+    elapsed: Duration,
+    theme: &Theme,
+) {
+    let ratio = if transfer.total_objects == 0 {
+        0.0
+    } else {
+        transfer.received_objects as f64 / transfer.total_objects as f64
+    };
+    let mib = transfer.received_bytes as f64 / (1024.0 * 1024.0);
+    let seconds = elapsed.as_secs_f64();
+    let rate = if seconds > 0.0 { mib / seconds } else { 0.0 };
+    let label = format!(
+        "{:.0}% ({}/{} objects), {:.1} MiB at {:.1} MiB/s",
+        ratio * 100.0,
+        transfer.received_objects,
+        transfer.total_objects,
+        mib,
+        rate
+    );
+
+    let gauge = Gauge::default()
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border_style())
+                .title(title),
+        )
+        .gauge_style(Style::default().fg(theme.added))
+        .ratio(ratio.min(1.0))
+        .label(label);
+    let popup = centered_rect(60, 3, area);
+    f.render_widget(Clear, popup);
+    f.render_widget(gauge, popup);
+}
@@ -0,0 +1,148 @@
+use crate::git_utils::{read_reflog, reset, ReflogEntry, ResetMode};
+use crate::keymap::KeyMap;
+use crate::theme::Theme;
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use git2::Repository as GitRepo;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    widgets::{Block, Borders, List, ListItem},
+    Frame,
+};
+
+pub struct ReflogView {
+    pub items: Vec<ReflogEntry>,
+    pub input_mode: InputMode,
+    pub selected: usize,
+}
+
+#[derive(PartialEq)]
+pub enum InputMode {
+    Normal,
+    ConfirmingReset,
+}
+
+impl ReflogView {
+    pub fn new() -> ReflogView {
+        ReflogView {
+            items: vec![],
+            input_mode: InputMode::Normal,
+            selected: 0,
+        }
+    }
+
+    fn selected_short_id(&self) -> String {
+        self.items
+            .get(self.selected)
+            .map(|entry| entry.oid.chars().take(7).collect())
+            .unwrap_or_default()
+    }
+
+    /// Whether keys, `q` included, belong to a prompt or dialog rather than
+    /// the global bindings.
+    pub fn captures_input(&self) -> bool {
+        self.input_mode != InputMode::Normal
+    }
+
+    pub fn render<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect, theme: &Theme) {
+        let items: Vec<ListItem> = if self.items.is_empty() {
+            vec![ListItem::new("Reflog is empty")]
+        } else {
+            self.items
+                .iter()
+                .enumerate()
+                .map(|(i, entry)| {
+                    let content = format!(
+                        "{} HEAD@{{{}}} {} ({})",
+                        &entry.oid[..7.min(entry.oid.len())],
+                        i,
+                        entry.message,
+                        entry.committer
+                    );
+                    let mut list_item = ListItem::new(content);
+                    if i == self.selected {
+                        list_item = list_item.style(theme.highlight_style());
+                    }
+                    list_item
+                })
+                .collect()
+        };
+
+        let title = match self.input_mode {
+            InputMode::Normal => "Reflog (HEAD)".to_string(),
+            InputMode::ConfirmingReset => format!(
+                "Hard reset to {} discards uncommitted changes! [y] to confirm, any other key to cancel",
+                self.selected_short_id()
+            ),
+        };
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(theme.border_style())
+                    .title(title),
+            )
+            .highlight_style(theme.highlight_style())
+            .highlight_symbol(">> ");
+        f.render_widget(list, area);
+    }
+
+    pub fn handle_input(
+        &mut self,
+        key: KeyEvent,
+        messages: &mut Vec<String>,
+        keys: &KeyMap,
+        repo: &GitRepo,
+    ) -> Result<()> {
+        match self.input_mode {
+            InputMode::Normal => match (keys.action("reflog", &key), key.code) {
+                (Some("reset"), _) if !self.items.is_empty() => {
+                    self.input_mode = InputMode::ConfirmingReset;
+                    messages.push(format!(
+                        "Reset HEAD to {}? Uncommitted changes will be lost. Press 'y' to confirm.",
+                        self.selected_short_id()
+                    ));
+                }
+                (_, KeyCode::Down) if self.selected < self.items.len().saturating_sub(1) => {
+                    self.selected += 1;
+                }
+                (_, KeyCode::Up) if self.selected > 0 => self.selected -= 1,
+                _ => {}
+            },
+            InputMode::ConfirmingReset => {
+                if key.code == KeyCode::Char('y') {
+                    if let Some(entry) = self.items.get(self.selected) {
+                        match reset(repo, &entry.oid, ResetMode::Hard) {
+                            Ok(_) => messages
+                                .push(format!("HEAD reset to {}.", self.selected_short_id())),
+                            Err(e) => messages.push(format!("Failed to reset: {}", e)),
+                        }
+                    }
+                    self.update(repo);
+                } else {
+                    messages.push("Reset cancelled.".to_string());
+                }
+                self.input_mode = InputMode::Normal;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn update(&mut self, repo: &GitRepo) {
+        match read_reflog(repo, "HEAD") {
+            Ok(entries) => self.items = entries,
+            Err(e) => {
+                self.items = vec![ReflogEntry {
+                    oid: "Error".to_string(),
+                    committer: "Error".to_string(),
+                    message: format!("Error reading reflog: {}", e),
+                }];
+            }
+        }
+
+        if self.selected >= self.items.len() {
+            self.selected = self.items.len().saturating_sub(1);
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use crate::git_utils::{
+    add_remote, list_remotes, remove_remote, rename_remote, set_remote_url, FetchSummary,
+};
+use crate::keymap::KeyMap;
+use crate::task::Task;
+use crate::theme::Theme;
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use git2::Repository as GitRepo;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::Style,
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
+    Frame,
+};
+
+pub struct RemoteView {
+    pub items: Vec<(String, String)>,
+    pub input_mode: InputMode,
+    pub input: String,
+    pub selected: usize,
+    pending_name: String,
+    /// Fetch for the app to run in the background.
+    pub task: Option<Task>,
+}
+
+#[derive(PartialEq)]
+pub enum InputMode {
+    Normal,
+    AddingName,
+    AddingUrl,
+    Renaming,
+    SettingUrl,
+}
+
+impl RemoteView {
+    pub fn new() -> RemoteView {
+        RemoteView {
+            items: vec![],
+            input_mode: InputMode::Normal,
+            input: String::new(),
+            selected: 0,
+            pending_name: String::new(),
+            task: None,
+        }
+    }
+
+    fn selected_remote(&self) -> Option<String> {
+        self.items.get(self.selected).map(|(name, _)| name.clone())
+    }
+
+    /// Whether keys, `q` included, belong to a prompt or dialog rather than
+    /// the global bindings.
+    pub fn captures_input(&self) -> bool {
+        self.input_mode != InputMode::Normal
+    }
+
+    pub fn render<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect, theme: &Theme) {
+        if self.input_mode != InputMode::Normal {
+            let title = match self.input_mode {
+                InputMode::AddingName => "New Remote Name".to_string(),
+                InputMode::AddingUrl => format!("URL for '{}'", self.pending_name),
+                InputMode::Renaming => format!("Rename '{}' to", self.pending_name),
+                InputMode::SettingUrl => format!("New URL for '{}'", self.pending_name),
+                InputMode::Normal => String::new(),
+            };
+            let paragraph = Paragraph::new(&self.input[..])
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(theme.border_style())
+                        .title(title),
+                )
+                .style(Style::default().fg(theme.added));
+            f.render_widget(Clear, area);
+            f.render_widget(paragraph, area);
+            return;
+        }
+
+        let items: Vec<ListItem> = if self.items.is_empty() {
+            vec![ListItem::new("No remotes")]
+        } else {
+            self.items
+                .iter()
+                .enumerate()
+                .map(|(i, (name, url))| {
+                    let mut list_item = ListItem::new(format!("{:<12} {}", name, url));
+                    if i == self.selected {
+                        list_item = list_item.style(theme.highlight_style());
+                    }
+                    list_item
+                })
+                .collect()
+        };
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(theme.border_style())
+                    .title("Remotes"),
+            )
+            .highlight_style(theme.highlight_style())
+            .highlight_symbol(">> ");
+        f.render_widget(list, area);
+    }
+
+    pub fn handle_input(
+        &mut self,
+        key: KeyEvent,
+        messages: &mut Vec<String>,
+        keys: &KeyMap,
+        repo: &GitRepo,
+    ) -> Result<()> {
+        match self.input_mode {
+            InputMode::Normal => match (keys.action("remote", &key), key.code) {
+                (Some("add"), _) => {
+                    self.input_mode = InputMode::AddingName;
+                    self.input.clear();
+                    messages.push("Enter the new remote's name:".to_string());
+                }
+                (Some("delete"), _) => {
+                    if let Some(name) = self.selected_remote() {
+                        match remove_remote(repo, &name) {
+                            Ok(_) => messages.push(format!("Remote '{}' removed.", name)),
+                            Err(e) => messages.push(format!("Failed to remove remote: {}", e)),
+                        }
+                        self.update(repo);
+                    }
+                }
+                (Some("fetch"), _) => {
+                    if let Some(remote) = self.selected_remote() {
+                        self.task = Some(Task::Fetch { remote });
+                    }
+                }
+                (Some("fetch_all"), _) => self.task = Some(Task::FetchAll { prune: true }),
+                (Some("rename"), _) => {
+                    if let Some(name) = self.selected_remote() {
+                        self.input_mode = InputMode::Renaming;
+                        self.input.clear();
+                        messages.push(format!("Enter a new name for '{}':", name));
+                        self.pending_name = name;
+                    }
+                }
+                (Some("set_url"), _) => {
+                    if let Some(name) = self.selected_remote() {
+                        self.input_mode = InputMode::SettingUrl;
+                        self.input.clear();
+                        messages.push(format!("Enter a new URL for '{}':", name));
+                        self.pending_name = name;
+                    }
+                }
+                (_, KeyCode::Down) if self.selected < self.items.len().saturating_sub(1) => {
+                    self.selected += 1;
+                }
+                (_, KeyCode::Up) if self.selected > 0 => self.selected -= 1,
+                _ => {}
+            },
+            _ => match key.code {
+                KeyCode::Enter => {
+                    let value = self.input.trim().to_string();
+                    if value.is_empty() {
+                        messages.push("Input cannot be empty.".to_string());
+                        return Ok(());
+                    }
+                    self.input.clear();
+                    match self.input_mode {
+                        InputMode::AddingName => {
+                            self.pending_name = value;
+                            self.input_mode = InputMode::AddingUrl;
+                            messages.push(format!("Enter the URL for '{}':", self.pending_name));
+                            return Ok(());
+                        }
+                        InputMode::AddingUrl => {
+                            match add_remote(repo, &self.pending_name, &value) {
+                                Ok(_) => {
+                                    messages.push(format!("Remote '{}' added.", self.pending_name))
+                                }
+                                Err(e) => messages.push(format!("Failed to add remote: {}", e)),
+                            }
+                        }
+                        InputMode::Renaming => {
+                            match rename_remote(repo, &self.pending_name, &value) {
+                                Ok(problems) => {
+                                    messages.push(format!(
+                                        "Remote '{}' renamed to '{}'.",
+                                        self.pending_name, value
+                                    ));
+                                    for refspec in problems {
+                                        messages.push(format!(
+                                            "Could not update refspec '{}'.",
+                                            refspec
+                                        ));
+                                    }
+                                }
+                                Err(e) => messages.push(format!("Failed to rename remote: {}", e)),
+                            }
+                        }
+                        InputMode::SettingUrl => {
+                            match set_remote_url(repo, &self.pending_name, &value) {
+                                Ok(_) => messages.push(format!(
+                                    "Remote '{}' now points to '{}'.",
+                                    self.pending_name, value
+                                )),
+                                Err(e) => messages.push(format!("Failed to set URL: {}", e)),
+                            }
+                        }
+                        InputMode::Normal => {}
+                    }
+                    self.input_mode = InputMode::Normal;
+                    self.update(repo);
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.input.clear();
+                    messages.push("Remote edit cancelled.".to_string());
+                }
+                KeyCode::Char(c) => {
+                    self.input.push(c);
+                }
+                KeyCode::Backspace => {
+                    self.input.pop();
+                }
+                _ => {}
+            },
+        }
+        Ok(())
+    }
+
+    pub fn update(&mut self, repo: &GitRepo) {
+        match list_remotes(repo) {
+            Ok(remotes) => self.items = remotes,
+            Err(e) => {
+                self.items = vec![("Error".to_string(), format!("Error listing remotes: {}", e))]
+            }
+        }
+
+        if self.selected >= self.items.len() {
+            self.selected = self.items.len().saturating_sub(1);
+        }
+    }
+}
+
+/// Formats a fetch result like "Fetched origin: main now at abc1234 (3 new commits)".
+pub fn describe_fetch(summary: &FetchSummary) -> String {
+    let pruned = match summary.pruned_refs {
+        0 => String::new(),
+        1 => ", 1 stale ref pruned".to_string(),
+        n => format!(", {} stale refs pruned", n),
+    };
+    let described = match (&summary.branch, &summary.tip) {
+        (Some(branch), Some(tip)) => format!(
+            "Fetched {}: {} now at {} ({} new commit{}, {} ref{} updated)",
+            summary.remote,
+            branch,
+            &tip[..7],
+            summary.new_commits,
+            if summary.new_commits == 1 { "" } else { "s" },
+            summary.updated_refs,
+            if summary.updated_refs == 1 { "" } else { "s" },
+        ),
+        _ => format!(
+            "Fetched {}: {} ref{} updated",
+            summary.remote,
+            summary.updated_refs,
+            if summary.updated_refs == 1 { "" } else { "s" },
+        ),
+    };
+    described + &pruned
+}
@@ -0,0 +1,42 @@
+use crate::theme::Theme;
+use crate::tui_module::confirm_dialog::centered_rect;
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    style::Style,
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+const FRAMES: [&str; 4] = ["|", "/", "-", "\\"];
+
+/// An activity indicator whose frame advances on every tick.
+#[derive(Default)]
+pub struct Spinner {
+    frame: usize,
+}
+
+impl Spinner {
+    pub fn tick(&mut self) {
+        self.frame = self.frame.wrapping_add(1);
+    }
+
+    pub fn frame(&self) -> &'static str {
+        FRAMES[self.frame % FRAMES.len()]
+    }
+
+    /// Draws the spinner and `label` in a small popup centered over `area`.
+    pub fn render<B: Backend>(&self, f: &mut Frame<B>, area: Rect, label: &str, theme: &Theme) {
+        let paragraph = Paragraph::new(format!("{} {}...", self.frame(), label))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(theme.border_style()),
+            )
+            .style(Style::default().fg(theme.title))
+            .alignment(Alignment::Center);
+        let popup = centered_rect(40, 3, area);
+        f.render_widget(Clear, popup);
+        f.render_widget(paragraph, popup);
+    }
+}
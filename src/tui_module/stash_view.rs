@@ -0,0 +1,295 @@
+use crate::git_utils::{
+    stash_apply, stash_diff, stash_drop, stash_list, stash_pop, stash_save, DiffLine, DiffLineKind,
+    StashEntry,
+};
+use crate::keymap::KeyMap;
+use crate::theme::Theme;
+use crate::tui_module::confirm_dialog::{ConfirmDialog, DialogResult};
+use crate::tui_module::navigation::{scroll_offset, Motion};
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use git2::Repository as GitRepo;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
+    Frame,
+};
+
+pub struct StashView {
+    pub items: Vec<StashEntry>,
+    pub input_mode: InputMode,
+    pub input: String,
+    pub selected: usize,
+    /// Index of the first row drawn, so the selection stays in view.
+    pub offset: usize,
+    /// Where the stash list was last drawn, for paging.
+    pub list_area: Rect,
+    /// Diff of the selected stash against the commit it was saved on.
+    pub preview: Vec<DiffLine>,
+    pub preview_scroll: u16,
+    pub confirm: Option<ConfirmDialog<StashAction>>,
+}
+
+/// A destructive stash operation waiting for confirmation.
+pub enum StashAction {
+    Drop(usize),
+}
+
+#[derive(PartialEq)]
+pub enum InputMode {
+    Normal,
+    SavingStash,
+}
+
+impl StashView {
+    pub fn new() -> StashView {
+        StashView {
+            items: vec![],
+            input_mode: InputMode::Normal,
+            input: String::new(),
+            selected: 0,
+            offset: 0,
+            list_area: Rect::default(),
+            preview: vec![],
+            preview_scroll: 0,
+            confirm: None,
+        }
+    }
+
+    /// Whether keys, `q` included, belong to a prompt or dialog rather than
+    /// the global bindings.
+    pub fn captures_input(&self) -> bool {
+        self.input_mode != InputMode::Normal || self.confirm.is_some()
+    }
+
+    pub fn render<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        area: Rect,
+        theme: &Theme,
+        keys: &KeyMap,
+    ) {
+        if self.input_mode == InputMode::SavingStash {
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border_style())
+                .title("Stash Message (optional)");
+            let paragraph = Paragraph::new(&self.input[..])
+                .block(block)
+                .style(Style::default().fg(theme.added));
+            f.render_widget(Clear, area);
+            f.render_widget(paragraph, area);
+            return;
+        }
+
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+            .split(area);
+        self.list_area = chunks[0];
+        self.offset = scroll_offset(self.offset, self.selected, chunks[0]);
+
+        let items: Vec<ListItem> = if self.items.is_empty() {
+            vec![ListItem::new("No stashes")]
+        } else {
+            self.items
+                .iter()
+                .enumerate()
+                .skip(self.offset)
+                .map(|(i, entry)| {
+                    let text = match &entry.branch {
+                        Some(branch) => {
+                            format!("stash@{{{}}} [{}] {}", entry.index, branch, entry.message)
+                        }
+                        None => format!("stash@{{{}}} {}", entry.index, entry.message),
+                    };
+                    let mut list_item = ListItem::new(text);
+                    if i == self.selected {
+                        list_item = list_item.style(theme.highlight_style());
+                    }
+                    list_item
+                })
+                .collect()
+        };
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(theme.border_style())
+                    .title("Stashes"),
+            )
+            .highlight_style(theme.highlight_style())
+            .highlight_symbol(">> ");
+        f.render_widget(list, chunks[0]);
+
+        let text: Vec<Spans> = self
+            .preview
+            .iter()
+            .map(|line| {
+                let style = match line.kind {
+                    DiffLineKind::Added => Style::default().fg(theme.added),
+                    DiffLineKind::Removed => Style::default().fg(theme.removed),
+                    DiffLineKind::HunkHeader => Style::default().fg(theme.accent),
+                    DiffLineKind::FileHeader => Style::default().add_modifier(Modifier::BOLD),
+                    DiffLineKind::Context => Style::default(),
+                };
+                Spans::from(Span::styled(line.content.clone(), style))
+            })
+            .collect();
+        let preview = Paragraph::new(text)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(theme.border_style())
+                    .title(format!(
+                        "Stashed changes ({}/{} to scroll)",
+                        keys.key_for("stash.scroll_down"),
+                        keys.key_for("stash.scroll_up")
+                    )),
+            )
+            .scroll((self.preview_scroll, 0));
+        f.render_widget(preview, chunks[1]);
+
+        if let Some(dialog) = &self.confirm {
+            dialog.render(f, area, theme);
+        }
+    }
+
+    pub fn handle_input(
+        &mut self,
+        key: KeyEvent,
+        messages: &mut Vec<String>,
+        keys: &KeyMap,
+        repo: &mut GitRepo,
+    ) -> Result<()> {
+        if let Some(dialog) = self.confirm.take() {
+            match dialog.handle_input(key) {
+                DialogResult::Open(dialog) => self.confirm = Some(dialog),
+                DialogResult::Confirmed(StashAction::Drop(index)) => {
+                    match stash_drop(repo, index) {
+                        Ok(_) => messages.push(format!("Dropped stash@{{{}}}.", index)),
+                        Err(e) => messages.push(format!("Stash operation failed: {}", e)),
+                    }
+                    self.update(repo);
+                }
+                DialogResult::Cancelled => messages.push("Cancelled.".to_string()),
+            }
+            return Ok(());
+        }
+
+        if self.input_mode == InputMode::Normal {
+            if let Some(motion) = Motion::from_key(&key, keys) {
+                let selected = motion.apply(self.selected, self.items.len(), self.list_area);
+                if selected != self.selected {
+                    self.selected = selected;
+                    self.load_preview(repo);
+                }
+                return Ok(());
+            }
+        }
+
+        match self.input_mode {
+            InputMode::Normal => match keys.action("stash", &key) {
+                Some("save") => {
+                    self.input_mode = InputMode::SavingStash;
+                    self.input.clear();
+                    messages.push("Enter a stash message (or leave empty):".to_string());
+                }
+                Some(action @ ("pop" | "apply" | "drop")) => {
+                    let Some(index) = self.items.get(self.selected).map(|entry| entry.index) else {
+                        messages.push("No stashes.".to_string());
+                        return Ok(());
+                    };
+                    let (result, action) = match action {
+                        "pop" => (stash_pop(repo, index), "Popped"),
+                        "apply" => (stash_apply(repo, index), "Applied"),
+                        _ => {
+                            self.confirm = Some(ConfirmDialog::new(
+                                format!("Drop stash@{{{}}}? This cannot be undone.", index),
+                                StashAction::Drop(index),
+                            ));
+                            return Ok(());
+                        }
+                    };
+                    match result {
+                        Ok(_) => messages.push(format!("{} stash@{{{}}}.", action, index)),
+                        Err(e) => messages.push(format!("Stash operation failed: {}", e)),
+                    }
+                    self.update(repo);
+                }
+                Some("scroll_down") => {
+                    let max_scroll = self.preview.len().saturating_sub(1) as u16;
+                    self.preview_scroll = (self.preview_scroll + 1).min(max_scroll);
+                }
+                Some("scroll_up") => {
+                    self.preview_scroll = self.preview_scroll.saturating_sub(1);
+                }
+                _ => {}
+            },
+            InputMode::SavingStash => match key.code {
+                KeyCode::Enter => {
+                    let message = self.input.trim();
+                    let message = (!message.is_empty()).then_some(message);
+                    match stash_save(repo, message) {
+                        Ok(_) => messages.push("Saved working changes to stash@{0}.".to_string()),
+                        Err(e) => messages.push(format!("Failed to stash changes: {}", e)),
+                    }
+                    self.input_mode = InputMode::Normal;
+                    self.input.clear();
+                    self.update(repo);
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.input.clear();
+                    messages.push("Stash cancelled.".to_string());
+                }
+                KeyCode::Char(c) => {
+                    self.input.push(c);
+                }
+                KeyCode::Backspace => {
+                    self.input.pop();
+                }
+                _ => {}
+            },
+        }
+        Ok(())
+    }
+
+    pub fn update(&mut self, repo: &mut GitRepo) {
+        match stash_list(repo) {
+            Ok(stashes) => self.items = stashes,
+            Err(e) => {
+                self.items.clear();
+                self.items.push(StashEntry {
+                    index: 0,
+                    branch: None,
+                    message: format!("Error listing stashes: {}", e),
+                });
+            }
+        }
+
+        if self.selected >= self.items.len() {
+            self.selected = self.items.len().saturating_sub(1);
+        }
+        self.load_preview(repo);
+    }
+
+    /// Loads the diff of the selected stash into the preview pane.
+    fn load_preview(&mut self, repo: &mut GitRepo) {
+        self.preview_scroll = 0;
+        self.preview = match self.items.get(self.selected) {
+            Some(entry) => stash_diff(repo, entry.index).unwrap_or_else(|e| {
+                vec![DiffLine {
+                    kind: DiffLineKind::Context,
+                    content: format!("Failed to load stash diff: {}", e),
+                    staged: false,
+                    highlights: Vec::new(),
+                }]
+            }),
+            None => vec![],
+        };
+    }
+}
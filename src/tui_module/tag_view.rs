@@ -0,0 +1,201 @@
+use crate::git_utils::{create_tag, list_tags};
+use crate::keymap::KeyMap;
+use crate::theme::Theme;
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use git2::Repository as GitRepo;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::Style,
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
+    Frame,
+};
+
+pub struct TagView {
+    pub items: Vec<String>,
+    pub input_mode: InputMode,
+    pub input: String,
+    pub selected: usize,
+    /// Commit new tags point at; set by the app from the log selection.
+    pub target: Option<String>,
+    pending_name: String,
+}
+
+#[derive(PartialEq)]
+pub enum InputMode {
+    Normal,
+    EnteringName,
+    EnteringMessage,
+}
+
+impl TagView {
+    pub fn new() -> TagView {
+        TagView {
+            items: vec![],
+            input_mode: InputMode::Normal,
+            input: String::new(),
+            selected: 0,
+            target: None,
+            pending_name: String::new(),
+        }
+    }
+
+    fn target_label(&self) -> String {
+        match &self.target {
+            Some(id) => id.chars().take(7).collect(),
+            None => "HEAD".to_string(),
+        }
+    }
+
+    /// Whether keys, `q` included, belong to a prompt or dialog rather than
+    /// the global bindings.
+    pub fn captures_input(&self) -> bool {
+        self.input_mode != InputMode::Normal
+    }
+
+    pub fn render<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect, theme: &Theme) {
+        if self.input_mode != InputMode::Normal {
+            let title = match self.input_mode {
+                InputMode::EnteringName => format!("New Tag at {}", self.target_label()),
+                _ => format!(
+                    "Message for '{}' (empty for a lightweight tag)",
+                    self.pending_name
+                ),
+            };
+            let paragraph = Paragraph::new(&self.input[..])
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(theme.border_style())
+                        .title(title),
+                )
+                .style(Style::default().fg(theme.added));
+            f.render_widget(Clear, area);
+            f.render_widget(paragraph, area);
+            return;
+        }
+
+        let items: Vec<ListItem> = if self.items.is_empty() {
+            vec![ListItem::new("No tags")]
+        } else {
+            self.items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| {
+                    let mut list_item = ListItem::new(item.clone());
+                    if i == self.selected {
+                        list_item = list_item.style(theme.highlight_style());
+                    }
+                    list_item
+                })
+                .collect()
+        };
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(theme.border_style())
+                    .title("Tags"),
+            )
+            .highlight_style(theme.highlight_style())
+            .highlight_symbol(">> ");
+        f.render_widget(list, area);
+    }
+
+    pub fn handle_input(
+        &mut self,
+        key: KeyEvent,
+        messages: &mut Vec<String>,
+        keys: &KeyMap,
+        repo: &GitRepo,
+    ) -> Result<()> {
+        match self.input_mode {
+            InputMode::Normal => match (keys.action("tag", &key), key.code) {
+                (Some("create"), _) => {
+                    self.input_mode = InputMode::EnteringName;
+                    self.input.clear();
+                    messages.push(format!(
+                        "Enter a tag name for commit {}:",
+                        self.target_label()
+                    ));
+                }
+                (_, KeyCode::Down) if self.selected < self.items.len().saturating_sub(1) => {
+                    self.selected += 1;
+                }
+                (_, KeyCode::Up) if self.selected > 0 => self.selected -= 1,
+                _ => {}
+            },
+            InputMode::EnteringName => match key.code {
+                KeyCode::Enter => {
+                    let name = self.input.trim();
+                    if name.is_empty() {
+                        messages.push("Tag name cannot be empty.".to_string());
+                    } else {
+                        self.pending_name = name.to_string();
+                        self.input_mode = InputMode::EnteringMessage;
+                        self.input.clear();
+                        messages.push(
+                            "Enter a tag message, or leave empty for a lightweight tag:"
+                                .to_string(),
+                        );
+                    }
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.input.clear();
+                    messages.push("Tag creation cancelled.".to_string());
+                }
+                KeyCode::Char(c) => {
+                    self.input.push(c);
+                }
+                KeyCode::Backspace => {
+                    self.input.pop();
+                }
+                _ => {}
+            },
+            InputMode::EnteringMessage => match key.code {
+                KeyCode::Enter => {
+                    let message = self.input.trim();
+                    let message = (!message.is_empty()).then_some(message);
+                    match create_tag(repo, &self.pending_name, message, self.target.as_deref()) {
+                        Ok(_) => messages.push(format!(
+                            "Tag '{}' created at {}.",
+                            self.pending_name,
+                            self.target_label()
+                        )),
+                        Err(e) => messages.push(format!("Failed to create tag: {}", e)),
+                    }
+                    self.input_mode = InputMode::Normal;
+                    self.input.clear();
+                    self.update(repo);
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.input.clear();
+                    messages.push("Tag creation cancelled.".to_string());
+                }
+                KeyCode::Char(c) => {
+                    self.input.push(c);
+                }
+                KeyCode::Backspace => {
+                    self.input.pop();
+                }
+                _ => {}
+            },
+        }
+        Ok(())
+    }
+
+    pub fn update(&mut self, repo: &GitRepo) {
+        self.items.clear();
+        match list_tags(repo) {
+            Ok(tags) => self.items = tags,
+            Err(e) => self.items.push(format!("Error listing tags: {}", e)),
+        }
+
+        if self.selected >= self.items.len() {
+            self.selected = self.items.len().saturating_sub(1);
+        }
+    }
+}
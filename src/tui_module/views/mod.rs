@@ -1,12 +1,25 @@
-
-
 pub mod status_view;
 
+use crate::keymap::KeyMap;
+use crate::theme::Theme;
 use crossterm::event::KeyEvent;
+use git2::Repository;
 
 /// Trait defining the behavior of a view.
 pub trait View {
-    fn render<B: tui::backend::Backend>(&mut self, f: &mut tui::Frame<B>, area: tui::layout::Rect);
-    fn handle_input(&mut self, key: KeyEvent, messages: &mut Vec<String>);
-    fn update(&mut self);
+    fn render<B: tui::backend::Backend>(
+        &mut self,
+        f: &mut tui::Frame<B>,
+        area: tui::layout::Rect,
+        theme: &Theme,
+        keys: &KeyMap,
+    );
+    fn handle_input(
+        &mut self,
+        key: KeyEvent,
+        messages: &mut Vec<String>,
+        keys: &KeyMap,
+        repo: &Repository,
+    );
+    fn update(&mut self, repo: &Repository);
 }
@@ -1,17 +1,25 @@
 // src/tui/views/status_view.rs
 
 use anyhow::Result;
-use crossterm::event::{KeyCode, KeyEvent};
-use git2::{Repository as GitRepo, StatusOptions};
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use git2::{Repository as GitRepo, Status, StatusOptions};
+use std::collections::BTreeSet;
 use tui::{
     backend::Backend,
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     widgets::{Block, Borders, List, ListItem},
     Frame,
 };
 
-use crate::git_utils::add_files;
+use crate::git_utils::{
+    add_files, count_untracked, discard_all, discard_file, stage_all, unstage_files,
+};
+use crate::keymap::KeyMap;
+use crate::theme::Theme;
+use crate::tui_module::confirm_dialog::{ConfirmDialog, DialogResult};
+use crate::tui_module::mouse::clicked_row;
+use crate::tui_module::navigation::{scroll_offset, Motion};
 use crate::tui_module::views::View;
 
 pub struct StatusView {
@@ -19,6 +27,23 @@ pub struct StatusView {
     pub input_mode: InputMode,
     pub input: String,
     pub selected: usize,
+    pub confirm: Option<ConfirmDialog<StatusAction>>,
+    /// Index of the first row drawn, so the selection stays in view.
+    pub offset: usize,
+    /// Where the list was last drawn, for mouse clicks.
+    pub list_area: Rect,
+    /// Paths checked for staging together, kept across refreshes.
+    pub checked: BTreeSet<String>,
+    /// Whether ignored files are listed, marked `!!`.
+    pub include_ignored: bool,
+}
+
+/// A destructive status operation waiting for confirmation.
+pub enum StatusAction {
+    DiscardAll,
+    DeleteUntracked,
+    /// Revert a tracked file to HEAD, or delete an untracked one.
+    DiscardFile(String),
 }
 
 #[derive(PartialEq)]
@@ -34,122 +59,347 @@ impl StatusView {
             input_mode: InputMode::Normal,
             input: String::new(),
             selected: 0,
+            confirm: None,
+            offset: 0,
+            list_area: Rect::default(),
+            checked: BTreeSet::new(),
+            include_ignored: false,
+        }
+    }
+
+    /// Whether keys, `q` included, belong to a prompt or dialog rather than
+    /// the global bindings.
+    pub fn captures_input(&self) -> bool {
+        self.input_mode != InputMode::Normal || self.confirm.is_some()
+    }
+
+    /// Returns the path of the highlighted entry, if it is a file.
+    pub fn selected_path(&self) -> Option<String> {
+        self.items
+            .get(self.selected)
+            .and_then(|item| file_entry(item))
+            .map(|(_, path)| path.to_string())
+    }
+
+    /// Fetches the current repository status and populates the items,
+    /// keeping the selection on the same file when it is still listed.
+    pub fn fetch_status(&mut self, repo: &GitRepo) -> Result<()> {
+        let selected_path = self.selected_path();
+        let result = self.load_items(repo);
+        self.selected = selected_path
+            .and_then(|path| {
+                self.items.iter().position(|item| {
+                    file_entry(item).is_some_and(|(_, item_path)| item_path == path)
+                })
+            })
+            // The file is gone; stay at the same row, or the last one.
+            .unwrap_or_else(|| self.selected.min(self.items.len().saturating_sub(1)));
+        // Step off a section header onto its first file.
+        if self.selected_path().is_none() && self.selected + 1 < self.items.len() {
+            self.selected += 1;
         }
+        result
     }
 
-    /// Fetches the current repository status and populates the items.
-    pub fn fetch_status(&mut self) -> Result<()> {
+    fn load_items(&mut self, repo: &GitRepo) -> Result<()> {
         self.items.clear();
-        let repo = GitRepo::open(".")?;
 
         let mut opts = StatusOptions::new();
         opts.include_untracked(true)
+            .include_ignored(self.include_ignored)
             .renames_head_to_index(true)
             .renames_index_to_workdir(true);
 
         let statuses = repo.statuses(Some(&mut opts))?;
 
+        // Forget checked files that no longer have changes.
+        self.checked.retain(|path| {
+            statuses
+                .iter()
+                .any(|entry| entry.path() == Some(path.as_str()))
+        });
+
         if statuses.is_empty() {
             self.items
                 .push("Nothing to commit, working tree clean.".to_string());
             return Ok(());
         }
 
+        // Conflicted files get their own group ahead of the other changes,
+        // then files with staged changes, then everything else.
+        let mut conflicted = Vec::new();
+        let mut staged = Vec::new();
+        let mut unstaged = Vec::new();
         for entry in statuses.iter() {
             let status = entry.status();
             let file_path = entry.path().unwrap_or("Unknown");
 
-            let status_str = match status {
-                s if s.is_index_new() => "A",
-                s if s.is_index_modified() => "M",
-                s if s.is_index_deleted() => "D",
-                s if s.is_wt_new() => "??",
-                s if s.is_wt_modified() => "M",
-                s if s.is_wt_deleted() => "D",
-                _ => " ", //
+            // One letter for a change on one side, porcelain-style `XY`
+            // for a file changed in both the index and the work tree.
+            let (group, status_str) = if status.is_conflicted() {
+                (&mut conflicted, "UU".to_string())
+            } else if status.is_ignored() {
+                (&mut unstaged, "!!".to_string())
+            } else if status.is_wt_new() {
+                (&mut unstaged, "??".to_string())
+            } else {
+                match (index_code(status), worktree_code(status)) {
+                    (Some(x), Some(y)) => (&mut staged, format!("{}{}", x, y)),
+                    (Some(x), None) => (&mut staged, x.to_string()),
+                    (None, Some(y)) => (&mut unstaged, y.to_string()),
+                    (None, None) => continue,
+                }
             };
+            group.push(format!("{} {}", status_str, file_path));
+        }
 
-            self.items.push(format!("{} {}", status_str, file_path));
+        for (header, group) in [
+            ("Unmerged paths:", conflicted),
+            ("Changes to be committed:", staged),
+            ("Changes not staged:", unstaged),
+        ] {
+            if !group.is_empty() {
+                self.items.push(header.to_string());
+                self.items.extend(group);
+            }
         }
 
         Ok(())
     }
+
+    /// Selects the clicked row, or moves the selection with the scroll wheel.
+    pub fn handle_mouse(&mut self, event: MouseEvent) {
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                self.selected = clicked_row(self.list_area, &event)
+                    .map(|row| row + self.offset)
+                    .filter(|&row| row < self.items.len())
+                    .unwrap_or(self.selected);
+            }
+            MouseEventKind::ScrollDown if self.selected + 1 < self.items.len() => {
+                self.selected += 1;
+            }
+            MouseEventKind::ScrollUp if self.selected > 0 => self.selected -= 1,
+            _ => {}
+        }
+    }
+
+    /// Stages `files` in one go, then reloads the status.
+    fn stage(&mut self, files: Vec<String>, messages: &mut Vec<String>, repo: &GitRepo) {
+        match add_files(repo, &files) {
+            Ok(_) => {
+                messages.push(match files.as_slice() {
+                    [file] => format!("Staged file '{}'.", file),
+                    files => format!("Staged {} files.", files.len()),
+                });
+                self.checked.clear();
+            }
+            Err(e) => messages.push(format!("Failed to stage files: {:#}", e)),
+        }
+        self.fetch_status(repo).unwrap_or_else(|e| {
+            messages.push(format!("Error fetching status: {}", e));
+        });
+    }
+
+    fn run_action(&mut self, action: StatusAction, messages: &mut Vec<String>, repo: &GitRepo) {
+        match action {
+            StatusAction::DiscardAll => match discard_all(repo, false) {
+                Ok(count) => {
+                    messages.push(format!("Discarded changes to {} file(s).", count));
+                    match count_untracked(repo) {
+                        Ok(0) => {}
+                        Ok(untracked) => {
+                            self.confirm = Some(ConfirmDialog::new(
+                                format!("Also delete {} untracked file(s)?", untracked),
+                                StatusAction::DeleteUntracked,
+                            ));
+                        }
+                        Err(e) => messages.push(format!("Error fetching status: {}", e)),
+                    }
+                }
+                Err(e) => messages.push(format!("Failed to discard changes: {:#}", e)),
+            },
+            StatusAction::DeleteUntracked => match discard_all(repo, true) {
+                Ok(count) => messages.push(format!("Deleted {} untracked file(s).", count)),
+                Err(e) => messages.push(format!("Failed to delete untracked files: {:#}", e)),
+            },
+            StatusAction::DiscardFile(path) => match discard_file(repo, &path) {
+                Ok(_) => messages.push(format!("Discarded changes to '{}'.", path)),
+                Err(e) => messages.push(format!("Failed to discard '{}': {:#}", path, e)),
+            },
+        }
+        self.fetch_status(repo).unwrap_or_else(|e| {
+            messages.push(format!("Error fetching status: {}", e));
+        });
+    }
 }
 
 impl View for StatusView {
-    fn render<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+    fn render<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect, theme: &Theme, keys: &KeyMap) {
         // Render the list of status items
+        self.offset = scroll_offset(self.offset, self.selected, area);
         let items: Vec<ListItem> = self
             .items
             .iter()
             .enumerate()
+            .skip(self.offset)
             .map(|(i, item)| {
-                let content = item.clone();
+                let checked = file_entry(item).is_some_and(|(_, path)| self.checked.contains(path));
+                let content = if checked {
+                    format!("[x] {}", item)
+                } else if self.checked.is_empty() {
+                    item.clone()
+                } else {
+                    format!("[ ] {}", item)
+                };
                 let mut list_item = ListItem::new(content);
+                if item.starts_with("!! ") {
+                    list_item = list_item.style(Style::default().add_modifier(Modifier::DIM));
+                } else if file_entry(item).is_none() {
+                    list_item = list_item.style(Style::default().add_modifier(Modifier::BOLD));
+                }
                 if i == self.selected {
-                    list_item = list_item.style(
-                        Style::default()
-                            .fg(Color::Yellow)
-                            .add_modifier(Modifier::BOLD),
-                    );
+                    list_item = list_item.style(theme.highlight_style());
                 }
                 list_item
             })
             .collect();
 
+        let mut title = match (&self.input_mode, self.checked.len()) {
+            (InputMode::AddingFiles, _) => "Status - Enter to stage the selected file".to_string(),
+            (_, 0) => "Status".to_string(),
+            (_, n) => format!(
+                "Status - {} checked, {} to stage",
+                n,
+                keys.key_for("status.stage")
+            ),
+        };
+        if self.include_ignored {
+            title.push_str(" (showing ignored)");
+        }
         let list = List::new(items)
-            .block(Block::default().borders(Borders::ALL).title("Status"))
-            .highlight_style(
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(theme.border_style())
+                    .title(title),
             )
+            .highlight_style(theme.highlight_style())
             .highlight_symbol(">> ");
         f.render_widget(list, area);
+        self.list_area = area;
+
+        if let Some(dialog) = &self.confirm {
+            dialog.render(f, area, theme);
+        }
     }
 
-    fn handle_input(&mut self, key: KeyEvent, messages: &mut Vec<String>) {
+    fn handle_input(
+        &mut self,
+        key: KeyEvent,
+        messages: &mut Vec<String>,
+        keys: &KeyMap,
+        repo: &GitRepo,
+    ) {
+        if let Some(dialog) = self.confirm.take() {
+            match dialog.handle_input(key) {
+                DialogResult::Open(dialog) => self.confirm = Some(dialog),
+                DialogResult::Confirmed(action) => self.run_action(action, messages, repo),
+                DialogResult::Cancelled => messages.push("Cancelled.".to_string()),
+            }
+            return;
+        }
+
+        if let Some(motion) = Motion::from_key(&key, keys) {
+            self.selected = motion.apply(self.selected, self.items.len(), self.list_area);
+            return;
+        }
+
         match self.input_mode {
-            InputMode::Normal => match key.code {
-                KeyCode::Char('a') => {
-                    self.input_mode = InputMode::AddingFiles;
-                    self.input.clear();
-                    messages.push(
-                        "Press 'Enter' to stage selected file or 'Esc' to cancel.".to_string(),
-                    );
+            InputMode::Normal => match keys.action("status", &key) {
+                Some("toggle") => {
+                    if let Some(path) = self.selected_path() {
+                        if !self.checked.remove(&path) {
+                            self.checked.insert(path);
+                        }
+                        self.selected = (self.selected + 1).min(self.items.len().saturating_sub(1));
+                    }
                 }
-                KeyCode::Down => {
-                    if self.selected < self.items.len().saturating_sub(1) {
-                        self.selected += 1;
+                Some("stage") => {
+                    if self.checked.is_empty() {
+                        self.input_mode = InputMode::AddingFiles;
+                        self.input.clear();
+                        messages.push(
+                            "Press 'Enter' to stage selected file or 'Esc' to cancel.".to_string(),
+                        );
+                    } else {
+                        let files = self.checked.iter().cloned().collect();
+                        self.stage(files, messages, repo);
                     }
                 }
-                KeyCode::Up => {
-                    if self.selected > 0 {
-                        self.selected -= 1;
+                Some("ignored") => {
+                    self.include_ignored = !self.include_ignored;
+                    self.fetch_status(repo).unwrap_or_else(|e| {
+                        messages.push(format!("Error fetching status: {}", e));
+                    });
+                }
+                Some("unstage") => {
+                    if let Some(file) = self.selected_path() {
+                        match unstage_files(repo, std::slice::from_ref(&file)) {
+                            Ok(_) => {
+                                messages.push(format!("Unstaged file '{}'.", file));
+                                self.fetch_status(repo).unwrap_or_else(|e| {
+                                    messages.push(format!("Error fetching status: {}", e));
+                                });
+                            }
+                            Err(e) => {
+                                messages.push(format!("Failed to unstage '{}': {}", file, e));
+                            }
+                        }
+                    }
+                }
+                Some("stage_all") => {
+                    match stage_all(repo) {
+                        Ok(count) => messages.push(format!("Staged {} file(s).", count)),
+                        Err(e) => messages.push(format!("Failed to stage files: {:#}", e)),
+                    }
+                    self.fetch_status(repo).unwrap_or_else(|e| {
+                        messages.push(format!("Error fetching status: {}", e));
+                    });
+                }
+                Some("discard") => {
+                    if let Some((status, path)) = self
+                        .items
+                        .get(self.selected)
+                        .and_then(|item| file_entry(item))
+                    {
+                        let message = if status == "??" {
+                            format!("Delete untracked '{}'? This cannot be undone.", path)
+                        } else if status == "!!" {
+                            format!("Delete ignored '{}'? This cannot be undone.", path)
+                        } else {
+                            format!("Discard all changes to '{}'? This cannot be undone.", path)
+                        };
+                        self.confirm = Some(ConfirmDialog::new(
+                            message,
+                            StatusAction::DiscardFile(path.to_string()),
+                        ));
                     }
                 }
+                Some("discard_all") => {
+                    self.confirm = Some(ConfirmDialog::new(
+                        "Discard all changes to tracked files? This cannot be undone.".to_string(),
+                        StatusAction::DiscardAll,
+                    ));
+                }
                 _ => {}
             },
             InputMode::AddingFiles => match key.code {
                 KeyCode::Enter => {
-                    if let Some(selected_item) = self.items.get(self.selected) {
-                        // Extract the file path from the status line
-                        if let Some((_, file_path)) = selected_item.split_once(' ') {
-                            let file = file_path.to_string();
-                            match add_files(".", &[file.clone()]) {
-                                Ok(_) => {
-                                    messages.push(format!("Staged file '{}'.", file));
-                                    self.fetch_status().unwrap_or_else(|e| {
-                                        messages.push(format!("Error fetching status: {}", e));
-                                    });
-                                }
-                                Err(e) => {
-                                    messages.push(format!("Failed to stage '{}': {}", file, e));
-                                }
-                            }
-                        }
-                    }
                     self.input_mode = InputMode::Normal;
+                    if let Some(file) = self.selected_path() {
+                        self.stage(vec![file], messages, repo);
+                    }
                 }
                 KeyCode::Esc => {
                     self.input_mode = InputMode::Normal;
@@ -160,9 +410,130 @@ impl View for StatusView {
         }
     }
 
-    fn update(&mut self) {
-        if let Err(e) = self.fetch_status() {
+    fn update(&mut self, repo: &GitRepo) {
+        if let Err(e) = self.fetch_status(repo) {
             self.items.push(format!("Error fetching status: {}", e));
         }
     }
 }
+
+/// Splits a file row into its status code and path; `None` for section
+/// headers and the clean-tree and error messages.
+fn file_entry(item: &str) -> Option<(&str, &str)> {
+    item.split_once(' ')
+        .filter(|(status, _)| status.len() <= 2)
+        .map(|(status, path)| (status, path.trim()))
+}
+
+/// The letter for a file's staged change, if it has one.
+fn index_code(status: Status) -> Option<char> {
+    match status {
+        s if s.is_index_new() => Some('A'),
+        s if s.is_index_modified() => Some('M'),
+        s if s.is_index_deleted() => Some('D'),
+        s if s.is_index_renamed() => Some('R'),
+        s if s.is_index_typechange() => Some('T'),
+        _ => None,
+    }
+}
+
+/// The letter for a file's unstaged change to a tracked file, if it has one.
+fn worktree_code(status: Status) -> Option<char> {
+    match status {
+        s if s.is_wt_modified() => Some('M'),
+        s if s.is_wt_deleted() => Some('D'),
+        s if s.is_wt_renamed() => Some('R'),
+        s if s.is_wt_typechange() => Some('T'),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+    use git2::Signature;
+    use std::{fs, path::Path};
+
+    /// A repository with `file.txt` committed and then modified.
+    fn repo_with_modified_file(dir: &Path) -> GitRepo {
+        let repo = GitRepo::init(dir).unwrap();
+        fs::write(dir.join("file.txt"), "one\n").unwrap();
+        {
+            let mut index = repo.index().unwrap();
+            index.add_path(Path::new("file.txt")).unwrap();
+            index.write().unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let signature = Signature::now("Test", "test@example.com").unwrap();
+            repo.commit(Some("HEAD"), &signature, &signature, "initial", &tree, &[])
+                .unwrap();
+        }
+        fs::write(dir.join("file.txt"), "two\n").unwrap();
+        repo
+    }
+
+    #[test]
+    fn a_then_enter_stages_the_selected_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = repo_with_modified_file(dir.path());
+        let keys = KeyMap::default();
+        let mut messages = Vec::new();
+        let mut view = StatusView::new();
+        view.update(&repo);
+        assert_eq!(view.items, ["Changes not staged:", "M file.txt"]);
+
+        for code in [KeyCode::Char('a'), KeyCode::Enter] {
+            let key = KeyEvent::new(code, KeyModifiers::NONE);
+            view.handle_input(key, &mut messages, &keys, &repo);
+        }
+
+        let status = repo.status_file(Path::new("file.txt")).unwrap();
+        assert!(status.is_index_modified(), "status: {:?}", status);
+        assert!(!status.is_wt_modified(), "status: {:?}", status);
+        assert_eq!(messages.last().unwrap(), "Staged file 'file.txt'.");
+    }
+
+    #[test]
+    fn selection_follows_the_file_across_refreshes() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = repo_with_modified_file(dir.path());
+        fs::write(dir.path().join("zebra.txt"), "new\n").unwrap();
+        let mut view = StatusView::new();
+        view.update(&repo);
+        view.selected = 2;
+        assert_eq!(view.selected_path().as_deref(), Some("zebra.txt"));
+
+        // A new file sorting first pushes the selected one down a row.
+        fs::write(dir.path().join("apple.txt"), "new\n").unwrap();
+        view.update(&repo);
+        assert_eq!(view.selected_path().as_deref(), Some("zebra.txt"));
+
+        // Once it is gone the selection stays in range.
+        fs::remove_file(dir.path().join("zebra.txt")).unwrap();
+        view.update(&repo);
+        assert_eq!(view.selected, 2);
+        assert_eq!(view.items.len(), 3);
+    }
+
+    #[test]
+    fn partly_staged_files_show_both_codes_under_staged() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = repo_with_modified_file(dir.path());
+        add_files(&repo, &["file.txt".to_string()]).unwrap();
+        fs::write(dir.path().join("file.txt"), "three\n").unwrap();
+        fs::write(dir.path().join("new.txt"), "new\n").unwrap();
+        let mut view = StatusView::new();
+        view.update(&repo);
+
+        assert_eq!(
+            view.items,
+            [
+                "Changes to be committed:",
+                "MM file.txt",
+                "Changes not staged:",
+                "?? new.txt"
+            ]
+        );
+        assert_eq!(view.selected_path().as_deref(), Some("file.txt"));
+    }
+}
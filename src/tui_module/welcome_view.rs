@@ -0,0 +1,152 @@
+use crate::git::repository::Repository;
+use crate::git_utils::discover_repo;
+use crate::keymap::KeyMap;
+use crate::theme::Theme;
+use crossterm::event::{KeyCode, KeyEvent};
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    style::Style,
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+/// Shown instead of the repository views when there is no repository,
+/// offering to create one, clone one or look in another directory.
+pub struct WelcomeView {
+    pub repo_path: String,
+    pub input_mode: InputMode,
+    pub input: String,
+    /// Create a bare repository when initializing.
+    pub bare: bool,
+    /// Directory for the app to reopen on, after an init or a change of
+    /// directory.
+    pub open: Option<String>,
+    /// Set when the user chose to clone, for the app to switch views.
+    pub clone_requested: bool,
+}
+
+#[derive(PartialEq)]
+pub enum InputMode {
+    Normal,
+    ChangingDirectory,
+}
+
+impl WelcomeView {
+    pub fn new(repo_path: String) -> WelcomeView {
+        WelcomeView {
+            repo_path,
+            input_mode: InputMode::Normal,
+            input: String::new(),
+            bare: false,
+            open: None,
+            clone_requested: false,
+        }
+    }
+
+    /// Whether keys, `q` included, belong to a prompt or dialog rather than
+    /// the global bindings.
+    pub fn captures_input(&self) -> bool {
+        self.input_mode != InputMode::Normal
+    }
+
+    pub fn render<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        area: Rect,
+        theme: &Theme,
+        keys: &KeyMap,
+    ) {
+        let mut text = format!(
+            "No Git repository found at or above '{}'.\n\n\
+             rugit works inside a Git repository. You can:\n\n\
+             [{}] Initialize a {}repository here\n\
+             [{}] Bare repository: {} (press to toggle)\n\
+             [{}] Clone a repository\n\
+             [{}] Open another directory\n\
+             [{}] Quit",
+            self.repo_path,
+            keys.key_for("welcome.init"),
+            if self.bare { "bare " } else { "" },
+            keys.key_for("welcome.bare"),
+            if self.bare { "on" } else { "off" },
+            keys.key_for("welcome.clone"),
+            keys.key_for("welcome.open"),
+            keys.key_for("global.quit")
+        );
+        if self.input_mode == InputMode::ChangingDirectory {
+            text.push_str(&format!(
+                "\n\nDirectory (Enter to open, Esc to cancel):\n{}_",
+                self.input
+            ));
+        }
+        let paragraph = Paragraph::new(text)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(theme.border_style())
+                    .title("Welcome to rugit"),
+            )
+            .style(Style::default().fg(theme.title))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: false });
+        f.render_widget(paragraph, area);
+    }
+
+    pub fn handle_input(&mut self, key: KeyEvent, messages: &mut Vec<String>, keys: &KeyMap) {
+        match self.input_mode {
+            InputMode::Normal => match keys.action("welcome", &key) {
+                Some("init") => self.init(messages),
+                Some("bare") => self.bare = !self.bare,
+                Some("clone") => self.clone_requested = true,
+                Some("open") => {
+                    self.input_mode = InputMode::ChangingDirectory;
+                    self.input = self.repo_path.clone();
+                }
+                _ => {}
+            },
+            InputMode::ChangingDirectory => match key.code {
+                KeyCode::Enter => {
+                    let path = self.input.trim().to_string();
+                    if std::path::Path::new(&path).is_dir() {
+                        // Open the enclosing repository, or show this screen
+                        // again for the new directory.
+                        self.open = Some(discover_repo(&path).unwrap_or(path));
+                        self.input_mode = InputMode::Normal;
+                        self.input.clear();
+                    } else {
+                        messages.push(format!("'{}' is not a directory.", path));
+                    }
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.input.clear();
+                }
+                KeyCode::Char(c) => self.input.push(c),
+                KeyCode::Backspace => {
+                    self.input.pop();
+                }
+                _ => {}
+            },
+        }
+    }
+
+    fn init(&mut self, messages: &mut Vec<String>) {
+        let result = if self.bare {
+            Repository::init_bare(&self.repo_path)
+        } else {
+            Repository::init(&self.repo_path)
+        };
+        match result {
+            Ok(_) => {
+                messages.push(format!(
+                    "Initialized empty {}repository in '{}'.",
+                    if self.bare { "bare " } else { "" },
+                    self.repo_path
+                ));
+                self.open = Some(self.repo_path.clone());
+            }
+            Err(e) => messages.push(format!("Failed to initialize repository: {}", e)),
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 pub fn print_error(message: &str) {
     eprintln!("Error: {}", message);
 }
@@ -5,3 +7,12 @@ pub fn print_error(message: &str) {
 pub fn print_info(message: &str) {
     println!("{}", message);
 }
+
+/// `$XDG_CONFIG_HOME/rugit`, defaulting to `~/.config/rugit`, where the
+/// settings, theme and key bindings live.
+pub fn config_dir() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join("rugit"))
+}